@@ -0,0 +1,329 @@
+use chrono::{DateTime, Utc};
+
+/// Default token budget for the context section of the system prompt
+pub const DEFAULT_CONTEXT_TOKENS: usize = 1500;
+
+/// Text used when no snippet makes it into the context
+pub const EMPTY_CONTEXT: &str = "No specific context from previous conversations.";
+
+/// Age at which a snippet's recency score halves, in days
+const RECENCY_HALF_LIFE_DAYS: f64 = 7.0;
+
+/// Weights of the recency, hop distance and mention frequency scores
+const RECENCY_WEIGHT: f64 = 0.5;
+const PROXIMITY_WEIGHT: f64 = 0.3;
+const FREQUENCY_WEIGHT: f64 = 0.2;
+
+/// Sections of the assembled context, in output order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextSection {
+    People,
+    Topics,
+    Tasks,
+    Documents,
+    History,
+}
+
+impl ContextSection {
+    /// All sections in the order they appear in the prompt
+    pub const ALL: [ContextSection; 5] = [
+        ContextSection::People,
+        ContextSection::Topics,
+        ContextSection::Tasks,
+        ContextSection::Documents,
+        ContextSection::History,
+    ];
+
+    /// Heading printed above the section
+    pub fn title(&self) -> &'static str {
+        match self {
+            ContextSection::People => "People",
+            ContextSection::Topics => "Topics",
+            ContextSection::Tasks => "Tasks",
+            ContextSection::Documents => "Documents",
+            ContextSection::History => "Earlier conversations",
+        }
+    }
+}
+
+/// Candidate piece of context with the signals used to rank it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContextSnippet {
+    pub section: ContextSection,
+    pub text: String,
+    /// When the underlying information was last mentioned, if known
+    pub last_seen: Option<DateTime<Utc>>,
+    /// Distance from an entity in the current message (0 = mentioned directly)
+    pub hops: usize,
+    /// How often the information came up
+    pub mentions: usize,
+}
+
+impl ContextSnippet {
+    /// Create a snippet about something mentioned directly, seen once
+    pub fn new(section: ContextSection, text: impl Into<String>) -> Self {
+        Self {
+            section,
+            text: text.into(),
+            last_seen: None,
+            hops: 0,
+            mentions: 1,
+        }
+    }
+
+    pub fn last_seen(mut self, last_seen: Option<DateTime<Utc>>) -> Self {
+        self.last_seen = last_seen;
+        self
+    }
+
+    pub fn hops(mut self, hops: usize) -> Self {
+        self.hops = hops;
+        self
+    }
+
+    pub fn mentions(mut self, mentions: usize) -> Self {
+        self.mentions = mentions;
+        self
+    }
+}
+
+/// Snippet with its relevance score and estimated token cost
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoredSnippet {
+    pub snippet: ContextSnippet,
+    pub score: f64,
+    pub tokens: usize,
+}
+
+/// What went into the assembled context and what was left out
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContextReport {
+    pub budget: usize,
+    /// Estimated tokens used, including section headings
+    pub used: usize,
+    pub included: Vec<ScoredSnippet>,
+    pub cut: Vec<ScoredSnippet>,
+}
+
+impl ContextReport {
+    /// Render the report for debugging, one line per snippet
+    pub fn render(&self) -> String {
+        let mut lines = vec![format!(
+            "Context: {}/{} tokens, {} included, {} cut",
+            self.used,
+            self.budget,
+            self.included.len(),
+            self.cut.len()
+        )];
+
+        for (marker, snippets) in [("+", &self.included), ("-", &self.cut)] {
+            for scored in snippets {
+                lines.push(format!(
+                    "  {} [{}] score {:.2}, {} tokens: {}",
+                    marker,
+                    scored.snippet.section.title(),
+                    scored.score,
+                    scored.tokens,
+                    preview(&scored.snippet.text, 60)
+                ));
+            }
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// Ranks context snippets and packs the best ones into a token budget
+#[derive(Debug, Clone)]
+pub struct ContextAssembler {
+    budget: usize,
+    now: DateTime<Utc>,
+}
+
+impl ContextAssembler {
+    /// Create an assembler with a budget in (estimated) tokens
+    pub fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            now: Utc::now(),
+        }
+    }
+
+    /// Score recency relative to `now` instead of the current time
+    pub fn at(mut self, now: DateTime<Utc>) -> Self {
+        self.now = now;
+        self
+    }
+
+    /// Relevance of a snippet in [0, 1]
+    ///
+    /// Combines recency (halving every week; unknown counts as half),
+    /// closeness to the current message and how often it was mentioned.
+    pub fn score(&self, snippet: &ContextSnippet) -> f64 {
+        let recency = snippet.last_seen.map_or(0.5, |last_seen| {
+            let age_days = (self.now - last_seen).num_seconds().max(0) as f64 / 86_400.0;
+            0.5_f64.powf(age_days / RECENCY_HALF_LIFE_DAYS)
+        });
+        let proximity = 1.0 / (1.0 + snippet.hops as f64);
+        let frequency = 1.0 - 1.0 / (1.0 + snippet.mentions as f64);
+
+        RECENCY_WEIGHT * recency + PROXIMITY_WEIGHT * proximity + FREQUENCY_WEIGHT * frequency
+    }
+
+    /// Pick the highest-scoring snippets that fit the budget and render them by section
+    ///
+    /// Snippets are considered best first; one that does not fit is cut and
+    /// smaller ones after it may still be included. Duplicate texts are kept once.
+    pub fn assemble(&self, snippets: Vec<ContextSnippet>) -> (String, ContextReport) {
+        let mut scored: Vec<ScoredSnippet> = Vec::new();
+        for snippet in snippets {
+            if snippet.text.trim().is_empty()
+                || scored.iter().any(|existing| existing.snippet.text == snippet.text)
+            {
+                continue;
+            }
+            scored.push(ScoredSnippet {
+                score: self.score(&snippet),
+                tokens: estimate_tokens(&snippet.text),
+                snippet,
+            });
+        }
+        scored.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+        let mut report = ContextReport {
+            budget: self.budget,
+            ..Default::default()
+        };
+        for candidate in scored {
+            let section = candidate.snippet.section;
+            let heading_cost = if report.included.iter().any(|s| s.snippet.section == section) {
+                0
+            } else {
+                estimate_tokens(section.title()) + 1
+            };
+            let cost = candidate.tokens + heading_cost;
+
+            if report.used + cost <= self.budget {
+                report.used += cost;
+                report.included.push(candidate);
+            } else {
+                report.cut.push(candidate);
+            }
+        }
+
+        let sections: Vec<String> = ContextSection::ALL
+            .iter()
+            .filter_map(|section| {
+                let lines: Vec<&str> = report
+                    .included
+                    .iter()
+                    .filter(|s| s.snippet.section == *section)
+                    .map(|s| s.snippet.text.as_str())
+                    .collect();
+                (!lines.is_empty()).then(|| format!("{}:\n{}", section.title(), lines.join("\n")))
+            })
+            .collect();
+
+        let context = if sections.is_empty() {
+            EMPTY_CONTEXT.to_string()
+        } else {
+            sections.join("\n\n")
+        };
+        (context, report)
+    }
+}
+
+/// Rough token count for prompt budgeting (about four characters per token)
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// First line of `text`, shortened to `max` characters
+fn preview(text: &str, max: usize) -> String {
+    let line = text.lines().next().unwrap_or_default();
+    if line.chars().count() <= max && !text.contains('\n') {
+        line.to_string()
+    } else {
+        let shortened: String = line.chars().take(max).collect();
+        format!("{}…", shortened)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2024-06-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+    }
+
+    #[test]
+    fn test_score_prefers_recent_close_and_frequent() {
+        let assembler = ContextAssembler::new(100).at(now());
+        let base = ContextSnippet::new(ContextSection::Topics, "x").last_seen(Some(now()));
+
+        let old = base.clone().last_seen(Some(now() - Duration::days(7)));
+        let far = base.clone().hops(2);
+        let frequent = base.clone().mentions(5);
+
+        assert!(assembler.score(&base) > assembler.score(&old));
+        assert!(assembler.score(&base) > assembler.score(&far));
+        assert!(assembler.score(&frequent) > assembler.score(&base));
+        // A week old halves the recency component
+        assert!((assembler.score(&base) - assembler.score(&old) - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_assemble_packs_best_snippets_into_budget() {
+        let assembler = ContextAssembler::new(20).at(now());
+        let snippets = vec![
+            ContextSnippet::new(ContextSection::Topics, "GraphQL: far away").hops(3),
+            ContextSnippet::new(ContextSection::People, "Alice works on GraphQL"),
+            ContextSnippet::new(ContextSection::People, "Alice works on GraphQL"),
+            ContextSnippet::new(ContextSection::Tasks, "A very long task description that cannot fit").hops(1),
+        ];
+
+        let (context, report) = assembler.assemble(snippets);
+
+        assert_eq!(context, "People:\nAlice works on GraphQL\n\nTopics:\nGraphQL: far away");
+        assert_eq!(report.included.len(), 2);
+        assert_eq!(report.cut.len(), 1);
+        assert_eq!(report.cut[0].snippet.section, ContextSection::Tasks);
+        assert!(report.used <= report.budget);
+    }
+
+    #[test]
+    fn test_assemble_orders_sections() {
+        let assembler = ContextAssembler::new(1000).at(now());
+        let snippets = vec![
+            ContextSnippet::new(ContextSection::History, "earlier"),
+            ContextSnippet::new(ContextSection::Documents, "spec.md"),
+            ContextSnippet::new(ContextSection::People, "Alice"),
+        ];
+
+        let (context, _) = assembler.assemble(snippets);
+
+        assert_eq!(context, "People:\nAlice\n\nDocuments:\nspec.md\n\nEarlier conversations:\nearlier");
+    }
+
+    #[test]
+    fn test_assemble_empty() {
+        let (context, report) = ContextAssembler::new(0)
+            .at(now())
+            .assemble(vec![ContextSnippet::new(ContextSection::People, "Alice")]);
+
+        assert_eq!(context, EMPTY_CONTEXT);
+        assert_eq!(report.cut.len(), 1);
+        assert!(report.render().starts_with("Context: 0/0 tokens, 0 included, 1 cut"));
+    }
+}
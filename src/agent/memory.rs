@@ -1,36 +1,73 @@
 use anyhow::{Context, Result};
-use crate::graph::{GraphDB, ExtractedEntities};
-use crate::llm::{LLMClient, EntityExtractor};
+use crate::graph::{Conversation, Document, EntityKind, EntityRef, GraphDB, ExtractedEntities, MergeProposal, MergeReport};
+use crate::llm::{ChatMessage, ChatModel, ChatRole, EntityExtractor, TextStream, ToolReply, ToolResult, ToolTurn};
+use super::context::{ContextAssembler, ContextReport, ContextSection, ContextSnippet, DEFAULT_CONTEXT_TOKENS};
+use super::retrieval::{group_paths, ContextRetriever, PersonContext, TraversalConfig};
+use super::tools::{memory_tools, AgentResponse, MemoryTool, ToolInvocation, DEFAULT_MAX_TOOL_ITERATIONS};
+use std::sync::{Arc, Mutex};
 use graphlite_sdk::Session;
 
+/// Default number of previous turns sent to the LLM with each request
+pub const DEFAULT_HISTORY_TURNS: usize = 10;
+
+/// Messages from earlier conversations considered per mentioned entity
+const HISTORY_SNIPPETS_PER_ENTITY: usize = 3;
+
+/// Appended to the system prompt when memory tools are available
+const TOOLS_PROMPT: &str = "You can call tools to look things up in your memory before answering: \
+search past messages, look up a person, list or complete tasks, and find related entities. \
+Use them when the context above is not enough, then answer the user directly.";
+
+/// Answer given when the model still wants tools after the iteration cap and wrote nothing
+const TOOL_LIMIT_REPLY: &str = "I couldn't finish looking that up in my memory. \
+Could you narrow the question down?";
+
 /// Agentic memory manager
 /// Orchestrates conversation storage, entity extraction, and context building
 pub struct AgenticMemory {
     graph_db: GraphDB,
     entity_extractor: EntityExtractor,
-    llm_client: LLMClient,
+    llm_client: Arc<dyn ChatModel>,
     current_conversation_id: Option<String>,
+    history_turns: usize,
+    traversal: TraversalConfig,
+    context_tokens: usize,
+    context_debug: bool,
+    last_context_report: Mutex<Option<ContextReport>>,
+    max_tool_iterations: usize,
 }
 
 impl AgenticMemory {
     /// Create a new agentic memory instance
+    ///
+    /// `llm_client` writes replies and `extraction_client` extracts entities
+    /// from each message. Either may be an [`LLMClient`](crate::llm::LLMClient)
+    /// or any other [`ChatModel`]; pass a clone of the same client, or an
+    /// `Arc` of one model, to use it for both.
     pub async fn new(
         db_path: &str,
         admin_user: &str,
         admin_password: &str,
-        llm_client: LLMClient,
+        llm_client: impl ChatModel + 'static,
+        extraction_client: impl ChatModel + 'static,
     ) -> Result<Self> {
         let graph_db = GraphDB::new(db_path, admin_user, admin_password)
             .await
             .context("Failed to initialize graph database")?;
 
-        let entity_extractor = EntityExtractor::new(llm_client.clone());
+        let entity_extractor = EntityExtractor::new(extraction_client);
 
         Ok(Self {
             graph_db,
             entity_extractor,
-            llm_client,
+            llm_client: Arc::new(llm_client),
             current_conversation_id: None,
+            history_turns: DEFAULT_HISTORY_TURNS,
+            traversal: TraversalConfig::default(),
+            context_tokens: DEFAULT_CONTEXT_TOKENS,
+            context_debug: false,
+            last_context_report: Mutex::new(None),
+            max_tool_iterations: DEFAULT_MAX_TOOL_ITERATIONS,
         })
     }
 
@@ -46,24 +83,104 @@ impl AgenticMemory {
         Ok(conv_id)
     }
 
+    /// Resume an existing conversation
+    ///
+    /// Fails if no conversation with this id exists. On success, new messages
+    /// are appended to it and its most recent turns are returned in
+    /// chronological order.
+    pub fn resume_conversation(
+        &mut self,
+        session: &Session,
+        conversation_id: &str,
+    ) -> Result<(Conversation, Vec<ChatMessage>)> {
+        let conversation = self
+            .graph_db
+            .get_conversation(session, conversation_id)
+            .context("Failed to look up conversation")?
+            .with_context(|| format!("Conversation not found: {}", conversation_id))?;
+
+        let history = self
+            .graph_db
+            .get_conversation_messages(session, &conversation.id, self.history_turns)
+            .context("Failed to load conversation history")?;
+
+        self.current_conversation_id = Some(conversation.id.clone());
+        Ok((conversation, chronological_turns(history)))
+    }
+
+    /// Resume the conversation with the most recent activity
+    pub fn resume_last_conversation(
+        &mut self,
+        session: &Session,
+    ) -> Result<(Conversation, Vec<ChatMessage>)> {
+        let conversation_id = self
+            .graph_db
+            .latest_conversation_id(session)
+            .context("Failed to look up the latest conversation")?
+            .context("No previous conversation to resume")?;
+
+        self.resume_conversation(session, &conversation_id)
+    }
+
+    /// Set how many previous turns are sent to the LLM (0 disables history)
+    pub fn set_history_turns(&mut self, turns: usize) {
+        self.history_turns = turns;
+    }
+
+    /// Set the depth and fan-out limits for multi-hop context retrieval
+    pub fn set_traversal(&mut self, traversal: TraversalConfig) {
+        self.traversal = traversal;
+    }
+
+    /// Set the relationship types extracted between entities (e.g. `WORKS_ON`)
+    pub fn set_relation_types<I, S>(&mut self, relation_types: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.entity_extractor.set_relation_types(relation_types);
+    }
+
+    /// Relationship types extracted and followed between entities
+    pub fn relation_types(&self) -> &[String] {
+        self.entity_extractor.relation_types()
+    }
+
+    /// Limits used for multi-hop context retrieval
+    pub fn traversal(&self) -> TraversalConfig {
+        self.traversal
+    }
+
     /// Get current conversation ID
     pub fn current_conversation(&self) -> Option<&String> {
         self.current_conversation_id.as_ref()
     }
 
-    /// Process and store a user message
+    /// Process and store a user message in the current conversation
+    ///
+    /// See [`Self::process_user_message_in`].
     pub async fn process_user_message(
         &self,
         session: &Session,
         message: &str,
     ) -> Result<(String, ExtractedEntities)> {
-        let conversation_id = self
-            .current_conversation_id
-            .as_ref()
-            .context("No active conversation")?;
+        let conversation_id = self.active_conversation()?;
+        self.process_user_message_in(session, conversation_id, message).await
+    }
 
+    /// Process and store a user message in the given conversation
+    ///
+    /// Tasks the message reports as finished are matched against open tasks
+    /// and completed. On return, `completed_tasks` holds the descriptions of
+    /// the tasks that were actually closed.
+    pub async fn process_user_message_in(
+        &self,
+        session: &Session,
+        conversation_id: &str,
+        message: &str,
+    ) -> Result<(String, ExtractedEntities)> {
         // Extract entities from the message
-        let entities = self
+        let mut entities = self
             .entity_extractor
             .extract(message)
             .await
@@ -75,20 +192,39 @@ impl AgenticMemory {
             .add_message(session, conversation_id, "user", message, &entities)
             .context("Failed to store user message")?;
 
+        // Close existing tasks instead of recording completions as new work
+        let mut completed = Vec::new();
+        for description in &entities.completed_tasks {
+            if let Some(task) = self
+                .graph_db
+                .complete_matching_task(session, description)
+                .context("Failed to complete task")?
+            {
+                completed.push(task.description);
+            }
+        }
+        entities.completed_tasks = completed;
+
         Ok((msg_id, entities))
     }
 
-    /// Store an assistant message
+    /// Store an assistant message in the current conversation
     pub fn store_assistant_message(
         &self,
         session: &Session,
         message: &str,
     ) -> Result<String> {
-        let conversation_id = self
-            .current_conversation_id
-            .as_ref()
-            .context("No active conversation")?;
+        let conversation_id = self.active_conversation()?;
+        self.store_assistant_message_in(session, conversation_id, message)
+    }
 
+    /// Store an assistant message in the given conversation
+    pub fn store_assistant_message_in(
+        &self,
+        session: &Session,
+        conversation_id: &str,
+        message: &str,
+    ) -> Result<String> {
         // For assistant messages, we typically don't extract entities
         // but we could if needed
         let entities = ExtractedEntities::default();
@@ -108,11 +244,199 @@ impl AgenticMemory {
         user_message: &str,
         entities: &ExtractedEntities,
     ) -> Result<String> {
-        // Build context from the graph based on extracted entities
-        let context = self.build_context(session, entities)?;
+        let conversation_id = self.current_conversation_id.as_deref();
+        self.generate_response_in(session, conversation_id, user_message, entities).await
+    }
 
-        // Construct the system prompt with context
+    /// Generate a response in the given conversation, whose recent turns are
+    /// sent as history (none without a conversation)
+    pub async fn generate_response_in(
+        &self,
+        session: &Session,
+        conversation_id: Option<&str>,
+        user_message: &str,
+        entities: &ExtractedEntities,
+    ) -> Result<String> {
+        let system_prompt = self.build_system_prompt(session, conversation_id, entities)?;
+        let messages = self.build_chat_messages(session, conversation_id, user_message)?;
+
+        // Generate response
+        let response = self
+            .llm_client
+            .chat(&system_prompt, &messages)
+            .await
+            .context("Failed to generate response")?;
+
+        Ok(response)
+    }
+
+    /// Generate a response, letting the LLM call memory tools first
+    ///
+    /// The model may search messages, look up people, list and complete
+    /// tasks and explore related entities. Tool results are fed back until
+    /// it answers; after the iteration cap it must answer without tools.
+    /// Failing tools are reported to the model rather than aborting the turn.
+    pub async fn generate_response_with_tools(
+        &self,
+        session: &Session,
+        user_message: &str,
+        entities: &ExtractedEntities,
+    ) -> Result<AgentResponse> {
+        let conversation_id = self.current_conversation_id.as_deref();
         let system_prompt = format!(
+            "{}\n\n{}",
+            self.build_system_prompt(session, conversation_id, entities)?,
+            TOOLS_PROMPT
+        );
+        let mut turns: Vec<ToolTurn> = self
+            .build_chat_messages(session, conversation_id, user_message)?
+            .into_iter()
+            .map(ToolTurn::Message)
+            .collect();
+        let tools = memory_tools();
+        let retriever = ContextRetriever::new(&self.graph_db)
+            .with_relation_types(self.entity_extractor.relation_types())
+            .with_traversal(self.traversal);
+        let mut invocations = Vec::new();
+
+        let mut rounds = 0;
+        loop {
+            let allow_calls = rounds < self.max_tool_iterations;
+            let reply = self
+                .llm_client
+                .chat_with_tools(&system_prompt, &turns, &tools, allow_calls)
+                .await
+                .context("Failed to generate response")?;
+
+            let (text, calls) = match reply {
+                ToolReply::Text(text) => return Ok(AgentResponse { text, invocations }),
+                ToolReply::ToolCalls { text, calls } if allow_calls => (text, calls),
+                // Tools were disabled but the model asked anyway; keep what it wrote
+                ToolReply::ToolCalls { text, .. } => {
+                    let text = if text.trim().is_empty() { TOOL_LIMIT_REPLY.to_string() } else { text };
+                    return Ok(AgentResponse { text, invocations });
+                }
+            };
+
+            let mut results = Vec::new();
+            for call in &calls {
+                let (output, is_error) = match MemoryTool::parse(call)
+                    .and_then(|tool| tool.run(session, &self.graph_db, &retriever))
+                {
+                    Ok(output) => (output, false),
+                    Err(error) => (format!("Error: {:#}", error), true),
+                };
+                results.push(ToolResult {
+                    call_id: call.id.clone(),
+                    content: output.clone(),
+                    is_error,
+                });
+                invocations.push(ToolInvocation { call: call.clone(), output, is_error });
+            }
+
+            turns.push(ToolTurn::ToolCalls { text, calls });
+            turns.push(ToolTurn::ToolResults(results));
+            rounds += 1;
+        }
+    }
+
+    /// Generate a response as a stream of text deltas
+    ///
+    /// The caller is responsible for collecting the full text and storing it
+    /// with [`AgenticMemory::store_assistant_message`] once the stream ends.
+    pub async fn generate_response_stream(
+        &self,
+        session: &Session,
+        user_message: &str,
+        entities: &ExtractedEntities,
+    ) -> Result<TextStream> {
+        let conversation_id = self.current_conversation_id.as_deref();
+        let system_prompt = self.build_system_prompt(session, conversation_id, entities)?;
+        let messages = self.build_chat_messages(session, conversation_id, user_message)?;
+
+        self.llm_client
+            .chat_stream(&system_prompt, &messages)
+            .await
+            .context("Failed to start response stream")
+    }
+
+    /// The conversation new messages go to
+    fn active_conversation(&self) -> Result<&str> {
+        self.current_conversation_id
+            .as_deref()
+            .context("No active conversation")
+    }
+
+    /// Answer turns supplied by the caller, such as an OpenAI-style request
+    /// carrying its own history, with memory context appended to `system`
+    ///
+    /// Nothing is stored; history snippets exclude `conversation_id`.
+    pub async fn chat_with_context(
+        &self,
+        session: &Session,
+        conversation_id: Option<&str>,
+        system: &str,
+        turns: &[ChatMessage],
+        entities: &ExtractedEntities,
+    ) -> Result<String> {
+        let context = self.build_context(session, conversation_id, entities)?;
+        self.llm_client
+            .chat(&append_memory_context(system, &context), turns)
+            .await
+            .context("Failed to generate response")
+    }
+
+    /// Streaming variant of [`Self::chat_with_context`]
+    pub async fn chat_with_context_stream(
+        &self,
+        session: &Session,
+        conversation_id: Option<&str>,
+        system: &str,
+        turns: &[ChatMessage],
+        entities: &ExtractedEntities,
+    ) -> Result<TextStream> {
+        let context = self.build_context(session, conversation_id, entities)?;
+        self.llm_client
+            .chat_stream(&append_memory_context(system, &context), turns)
+            .await
+            .context("Failed to start response stream")
+    }
+
+    /// Model replies are generated with
+    pub fn model(&self) -> &str {
+        self.llm_client.model()
+    }
+
+    /// Assemble the chat turns for a request: recent history followed by the new user message
+    fn build_chat_messages(
+        &self,
+        session: &Session,
+        conversation_id: Option<&str>,
+        user_message: &str,
+    ) -> Result<Vec<ChatMessage>> {
+        let history = match (conversation_id, self.history_turns) {
+            (Some(conversation_id), turns) if turns > 0 => self
+                .graph_db
+                // One extra row, since the current message is usually already stored
+                .get_conversation_messages(session, conversation_id, turns + 1)
+                .context("Failed to load conversation history")?,
+            _ => Vec::new(),
+        };
+
+        Ok(history_to_chat_messages(history, user_message, self.history_turns))
+    }
+
+    /// Construct the system prompt with context from the graph
+    fn build_system_prompt(
+        &self,
+        session: &Session,
+        conversation_id: Option<&str>,
+        entities: &ExtractedEntities,
+    ) -> Result<String> {
+        // Build context from the graph based on extracted entities
+        let context = self.build_context(session, conversation_id, entities)?;
+
+        Ok(format!(
             r#"You are a helpful AI assistant with persistent memory powered by a context graph.
 
 CONTEXT FROM YOUR MEMORY:
@@ -125,64 +449,204 @@ conversations mentioned, incorporate that knowledge naturally.
 Be conversational and helpful while demonstrating that you remember and understand the
 connections between different pieces of information."#,
             context
-        );
+        ))
+    }
 
-        // Generate response
-        let response = self
-            .llm_client
-            .complete(&system_prompt, user_message)
-            .await
-            .context("Failed to generate response")?;
+    /// Build context from graph based on extracted entities
+    ///
+    /// Candidate snippets are ranked and packed into the context token budget;
+    /// in debug mode the resulting report is kept for [`AgenticMemory::take_context_report`].
+    fn build_context(
+        &self,
+        session: &Session,
+        conversation_id: Option<&str>,
+        entities: &ExtractedEntities,
+    ) -> Result<String> {
+        let snippets = self.collect_context_snippets(session, conversation_id, entities)?;
+        let (context, report) = ContextAssembler::new(self.context_tokens).assemble(snippets);
 
-        Ok(response)
+        if self.context_debug {
+            if let Ok(mut last_report) = self.last_context_report.lock() {
+                *last_report = Some(report);
+            }
+        }
+
+        Ok(context)
     }
 
-    /// Build context from graph based on extracted entities
-    fn build_context(&self, session: &Session, entities: &ExtractedEntities) -> Result<String> {
-        let mut context_parts = Vec::new();
-
-        // Add information about mentioned people
-        if !entities.people.is_empty() {
-            context_parts.push(format!(
-                "People mentioned: {}",
-                entities.people.join(", ")
-            ));
+    /// Gather candidate context snippets for the entities in the current message
+    ///
+    /// History snippets come from conversations other than `conversation_id`.
+    fn collect_context_snippets(
+        &self,
+        session: &Session,
+        conversation_id: Option<&str>,
+        entities: &ExtractedEntities,
+    ) -> Result<Vec<ContextSnippet>> {
+        let mut snippets = Vec::new();
+        let retriever = ContextRetriever::new(&self.graph_db)
+            .with_relation_types(self.entity_extractor.relation_types())
+            .with_traversal(self.traversal);
+
+        // Everything known about each mentioned person; their messages go to history
+        for person in &entities.people {
+            let snippet = match retriever.person_context(session, person) {
+                Ok(person_context) if !person_context.is_empty() => {
+                    let PersonContext { mention_count, last_mentioned, .. } = person_context;
+                    let person_context = PersonContext {
+                        recent_messages: Vec::new(),
+                        ..person_context
+                    };
+                    ContextSnippet::new(ContextSection::People, person_context.render())
+                        .last_seen(last_mentioned)
+                        .mentions(mention_count)
+                }
+                Ok(_) => ContextSnippet::new(ContextSection::People, format!("{} (first mention)", person)),
+                // Context is best-effort, but a failed lookup must not claim the person is new
+                Err(_) => continue,
+            };
+            snippets.push(snippet);
         }
 
-        // Add information about mentioned topics
-        if !entities.topics.is_empty() {
-            context_parts.push(format!(
-                "Topics discussed: {}",
-                entities.topics.join(", ")
+        // Relationships stated in the message
+        for relation in &entities.relations {
+            snippets.push(ContextSnippet::new(
+                ContextSection::People,
+                format!("{} {} {} (just mentioned)", relation.subject, relation.predicate, relation.object),
             ));
+        }
 
-            // For each topic, try to find related entities
-            for topic in &entities.topics {
-                if let Ok(related) = self.graph_db.find_related_entities(session, topic) {
-                    if !related.is_empty() {
-                        context_parts.push(format!(
-                            "Related to '{}': {}",
-                            topic,
-                            related.join(", ")
-                        ));
-                    }
-                }
+        // For each topic, follow the graph out to related people, topics and tasks
+        for topic in &entities.topics {
+            let topic_ref = retriever
+                .resolve(session, EntityKind::Topic, topic)
+                .unwrap_or_else(|_| EntityRef::new(EntityKind::Topic, topic.as_str()));
+            let paths = retriever.traverse(session, &topic_ref).unwrap_or_default();
+            if paths.is_empty() {
+                snippets.push(ContextSnippet::new(ContextSection::Topics, format!("{} (no earlier context)", topic)));
+            }
+            for group in group_paths(&topic_ref, &paths) {
+                snippets.push(
+                    ContextSnippet::new(ContextSection::Topics, format!("{}: {}", topic, group.text))
+                        .hops(1)
+                        .mentions(group.mentions),
+                );
             }
         }
 
-        // Add information about tasks
-        if !entities.tasks.is_empty() {
-            context_parts.push(format!(
-                "Tasks mentioned: {}",
-                entities.tasks.join(", ")
-            ));
+        // Tasks from this message, then other open tasks
+        for task in &entities.tasks {
+            snippets.push(ContextSnippet::new(ContextSection::Tasks, format!("{} (new)", task)));
+        }
+        for task in self.graph_db.list_open_tasks(session).unwrap_or_default() {
+            if entities.tasks.contains(&task.description) {
+                continue;
+            }
+            let assigned_here = task
+                .assignee
+                .as_ref()
+                .is_some_and(|assignee| entities.people.iter().any(|p| p.eq_ignore_ascii_case(assignee)));
+            let mut details = vec![task.status.as_str().to_string()];
+            if let Some(due_date) = task.due_date {
+                details.push(format!("due {}", due_date));
+            }
+            if let Some(assignee) = &task.assignee {
+                details.push(format!("assigned to {}", assignee));
+            }
+            snippets.push(
+                ContextSnippet::new(ContextSection::Tasks, format!("{} [{}]", task.description, details.join(", ")))
+                    .last_seen(Some(task.created_at))
+                    .hops(if assigned_here { 1 } else { 2 }),
+            );
+        }
+
+        // Referenced documents, with links when we have them
+        for mention in &entities.documents {
+            let document = Document::from_mention(mention);
+            let text = match document.url {
+                Some(url) if url != document.title => format!("{} ({})", document.title, url),
+                _ => document.title,
+            };
+            snippets.push(ContextSnippet::new(ContextSection::Documents, text));
         }
 
-        if context_parts.is_empty() {
-            Ok("No specific context from previous conversations.".to_string())
-        } else {
-            Ok(context_parts.join("\n"))
+        // Messages from earlier conversations about the same people and topics,
+        // looked up under the canonical names their mentions were stored under
+        let mentioned = entities
+            .people
+            .iter()
+            .map(|name| (EntityKind::Person, name))
+            .chain(entities.topics.iter().map(|name| (EntityKind::Topic, name)))
+            .map(|(kind, name)| {
+                retriever
+                    .resolve(session, kind, name)
+                    .unwrap_or_else(|_| EntityRef::new(kind, name.as_str()))
+            });
+        for entity in mentioned {
+            let mentions = self.graph_db.get_entity_mentions(session, &entity).unwrap_or_default();
+            for mention in mentions
+                .into_iter()
+                .filter(|m| conversation_id != Some(m.conversation_id.as_str()))
+                .take(HISTORY_SNIPPETS_PER_ENTITY)
+            {
+                snippets.push(
+                    ContextSnippet::new(
+                        ContextSection::History,
+                        format!(
+                            "[{}, {}] {}: {}",
+                            mention.timestamp.format("%Y-%m-%d"),
+                            mention.conversation_title.as_deref().unwrap_or("untitled"),
+                            mention.role,
+                            mention.content
+                        ),
+                    )
+                    .last_seen(Some(mention.timestamp)),
+                );
+            }
         }
+
+        Ok(snippets)
+    }
+
+    /// Set the token budget for graph context in the system prompt
+    pub fn set_context_budget(&mut self, tokens: usize) {
+        self.context_tokens = tokens;
+    }
+
+    /// Keep a report of what each assembled context included and cut
+    pub fn set_context_debug(&mut self, enabled: bool) {
+        self.context_debug = enabled;
+    }
+
+    /// Take the report for the most recently assembled context (debug mode only)
+    pub fn take_context_report(&self) -> Option<ContextReport> {
+        self.last_context_report.lock().ok()?.take()
+    }
+
+    /// Set how many rounds of tool calls a response may use
+    pub fn set_max_tool_iterations(&mut self, iterations: usize) {
+        self.max_tool_iterations = iterations;
+    }
+
+    /// Merge the Person or Topic `alias` into `canonical`, moving its mentions
+    /// and relationships and recording the alias
+    pub fn merge_entities(
+        &self,
+        session: &Session,
+        kind: EntityKind,
+        alias: &str,
+        canonical: &str,
+    ) -> Result<MergeReport> {
+        self.graph_db
+            .merge_entities(session, kind, alias, canonical, self.entity_extractor.relation_types())
+    }
+
+    /// Ask the extraction model which Person or Topic names look like duplicates
+    ///
+    /// Nothing is changed; apply accepted proposals with [`Self::merge_entities`].
+    pub async fn propose_merges(&self, session: &Session, kind: EntityKind) -> Result<Vec<MergeProposal>> {
+        let names = self.graph_db.list_entity_names(session, kind)?;
+        self.entity_extractor.propose_merges(kind, &names).await
     }
 
     /// Get access to the graph database for custom queries
@@ -190,3 +654,130 @@ connections between different pieces of information."#,
         &self.graph_db
     }
 }
+
+/// Add graph context to a system prompt written by someone else
+fn append_memory_context(system: &str, context: &str) -> String {
+    let block = format!(
+        "CONTEXT FROM YOUR MEMORY:\n{}\n\nUse this context when it is relevant to the conversation.",
+        context
+    );
+    if system.trim().is_empty() {
+        block
+    } else {
+        format!("{}\n\n{}", system.trim_end(), block)
+    }
+}
+
+/// Convert stored messages (newest first) into chronological chat turns
+fn chronological_turns(history: Vec<(String, String, String)>) -> Vec<ChatMessage> {
+    history
+        .into_iter()
+        .rev()
+        .filter_map(|(role, content, _timestamp)| {
+            ChatRole::parse(&role).map(|role| ChatMessage { role, content })
+        })
+        .collect()
+}
+
+/// Convert stored messages (newest first) into chronological chat turns ending with `user_message`
+///
+/// `process_user_message` stores the user's message before the response is
+/// generated, so a trailing copy of it in the history is dropped rather than
+/// sent twice.
+fn history_to_chat_messages(
+    history: Vec<(String, String, String)>,
+    user_message: &str,
+    max_turns: usize,
+) -> Vec<ChatMessage> {
+    let mut turns = chronological_turns(history);
+
+    if turns.last() == Some(&ChatMessage::user(user_message)) {
+        turns.pop();
+    }
+    if turns.len() > max_turns {
+        turns.drain(..turns.len() - max_turns);
+    }
+
+    turns.push(ChatMessage::user(user_message));
+    turns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(role: &str, content: &str, timestamp: &str) -> (String, String, String) {
+        (role.to_string(), content.to_string(), timestamp.to_string())
+    }
+
+    #[test]
+    fn test_history_to_chat_messages_chronological() {
+        // get_conversation_messages returns newest first
+        let history = vec![
+            row("assistant", "Nice to meet you, Alice!", "2024-01-01T00:00:02Z"),
+            row("user", "My name is Alice", "2024-01-01T00:00:01Z"),
+        ];
+
+        let turns = history_to_chat_messages(history, "What did I just say?", 10);
+
+        assert_eq!(
+            turns,
+            vec![
+                ChatMessage::user("My name is Alice"),
+                ChatMessage::assistant("Nice to meet you, Alice!"),
+                ChatMessage::user("What did I just say?"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_history_to_chat_messages_skips_stored_current_message() {
+        let history = vec![
+            row("user", "What did I just say?", "2024-01-01T00:00:03Z"),
+            row("assistant", "Hi!", "2024-01-01T00:00:02Z"),
+            row("user", "Hello", "2024-01-01T00:00:01Z"),
+        ];
+
+        let turns = history_to_chat_messages(history, "What did I just say?", 10);
+
+        assert_eq!(turns.len(), 3);
+        assert_eq!(turns[0], ChatMessage::user("Hello"));
+        assert_eq!(turns[2], ChatMessage::user("What did I just say?"));
+    }
+
+    #[test]
+    fn test_history_to_chat_messages_limits_turns() {
+        let history = vec![
+            row("assistant", "third", "3"),
+            row("user", "second", "2"),
+            row("assistant", "first", "1"),
+        ];
+
+        let turns = history_to_chat_messages(history, "new", 2);
+
+        assert_eq!(
+            turns,
+            vec![
+                ChatMessage::user("second"),
+                ChatMessage::assistant("third"),
+                ChatMessage::user("new"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_history_to_chat_messages_ignores_unknown_roles() {
+        let history = vec![row("system", "ignored", "1")];
+        let turns = history_to_chat_messages(history, "hello", 10);
+        assert_eq!(turns, vec![ChatMessage::user("hello")]);
+    }
+
+    #[test]
+    fn test_append_memory_context() {
+        let prompt = append_memory_context("You are terse.\n", "People: Alice");
+        assert!(prompt.starts_with("You are terse.\n\nCONTEXT FROM YOUR MEMORY:\nPeople: Alice\n"));
+
+        let prompt = append_memory_context("  ", "People: Alice");
+        assert!(prompt.starts_with("CONTEXT FROM YOUR MEMORY:\n"));
+    }
+}
@@ -1,5 +1,9 @@
 pub mod memory;
 pub mod retrieval;
+pub mod context;
+pub mod tools;
 
 pub use memory::*;
 pub use retrieval::*;
+pub use context::*;
+pub use tools::*;
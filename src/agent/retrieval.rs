@@ -1,37 +1,390 @@
 use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
 use graphlite_sdk::Session;
-use crate::graph::GraphDB;
+use std::collections::HashMap;
+use crate::graph::{
+    CoMention, EntityKind, EntityMention, EntityRef, GraphDB, RelatedEntity, Task, TaskStatus,
+    DEFAULT_RELATION_TYPES,
+};
+
+/// Number of latest messages quoted in a person's context
+const PERSON_RECENT_MESSAGES: usize = 3;
+
+/// Maximum number of conversations listed in a person's context
+const PERSON_CONVERSATIONS: usize = 5;
+
+/// Maximum length of a quoted message, in characters
+const SNIPPET_LENGTH: usize = 160;
+
+/// Limits for multi-hop traversal from an entity
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TraversalConfig {
+    /// Maximum number of hops from the start entity
+    pub max_depth: usize,
+    /// Maximum number of neighbours followed from each entity
+    pub fan_out: usize,
+    /// Score multiplier applied for every hop after the first
+    pub hop_decay: f64,
+    /// Maximum number of paths returned
+    pub max_paths: usize,
+}
+
+impl Default for TraversalConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: 2,
+            fan_out: 5,
+            hop_decay: 0.5,
+            max_paths: 20,
+        }
+    }
+}
+
+/// How two consecutive entities on a path are connected
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathLink {
+    /// Both were mentioned in the same messages
+    CoMentioned { messages: usize },
+    /// A typed edge; `outgoing` when it points from the previous entity to the next
+    Relation { relation: String, outgoing: bool },
+}
+
+impl PathLink {
+    /// How strongly the link ties two entities together, in (0, 1]
+    fn strength(&self) -> f64 {
+        match self {
+            // Stated relationships beat co-occurrence
+            PathLink::Relation { .. } => 1.0,
+            PathLink::CoMentioned { messages } => *messages as f64 / (*messages as f64 + 1.0),
+        }
+    }
+
+    /// Describe the link from the previous entity's point of view, e.g. "works on GraphQL"
+    fn describe(&self, other: &str) -> String {
+        match self {
+            PathLink::CoMentioned { messages } if *messages > 1 => {
+                format!("mentioned with {} ({} times)", other, messages)
+            }
+            PathLink::CoMentioned { .. } => format!("mentioned with {}", other),
+            PathLink::Relation { relation, outgoing: true } => {
+                format!("{} {}", relation_verb(relation), other)
+            }
+            PathLink::Relation { relation, outgoing: false } => {
+                format!("{} {} them", other, relation_verb(relation))
+            }
+        }
+    }
+
+    /// The same link seen from the other end
+    fn reversed(&self) -> Self {
+        match self {
+            PathLink::Relation { relation, outgoing } => PathLink::Relation {
+                relation: relation.clone(),
+                outgoing: !outgoing,
+            },
+            link => link.clone(),
+        }
+    }
+}
+
+/// One hop of a [`ContextPath`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathStep {
+    pub link: PathLink,
+    pub entity: EntityRef,
+}
+
+/// Path from a start entity through the graph, scored by link strength and length
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContextPath {
+    pub start: EntityRef,
+    pub steps: Vec<PathStep>,
+    pub score: f64,
+}
+
+impl ContextPath {
+    /// Entity at the end of the path
+    pub fn end(&self) -> &EntityRef {
+        self.steps.last().map_or(&self.start, |step| &step.entity)
+    }
+}
+
+/// Everything the graph knows about one person
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PersonContext {
+    pub name: String,
+    /// Co-mentioned topics with the number of shared messages, most frequent first
+    pub topics: Vec<(String, usize)>,
+    /// Assigned, related or co-mentioned tasks with their status when known
+    pub tasks: Vec<(String, Option<TaskStatus>)>,
+    pub documents: Vec<String>,
+    /// Other people mentioned in the same messages
+    pub people: Vec<String>,
+    /// Typed relationships as sentences, e.g. "Alice works on GraphQL"
+    pub relations: Vec<String>,
+    /// Conversations the person came up in, with the date of the latest mention
+    pub conversations: Vec<(String, NaiveDate)>,
+    /// Latest messages mentioning the person, newest first
+    pub recent_messages: Vec<(DateTime<Utc>, String)>,
+    /// Number of messages mentioning the person
+    pub mention_count: usize,
+    pub last_mentioned: Option<DateTime<Utc>>,
+}
+
+impl PersonContext {
+    /// Assemble a person's context from raw graph results
+    ///
+    /// `mentions` must be sorted newest first; `tasks` is used to find tasks
+    /// assigned to the person and the status of linked tasks.
+    pub fn from_parts(
+        name: &str,
+        mentions: &[EntityMention],
+        co_mentions: &[CoMention],
+        related: &[RelatedEntity],
+        tasks: &[Task],
+    ) -> Self {
+        let mut context = PersonContext {
+            name: name.to_string(),
+            mention_count: mentions.len(),
+            last_mentioned: mentions.first().map(|mention| mention.timestamp),
+            ..Default::default()
+        };
+
+        // Count shared messages per co-mentioned entity, keeping first-seen order
+        let mut counts: Vec<(&EntityRef, usize)> = Vec::new();
+        for co_mention in co_mentions {
+            match counts.iter_mut().find(|(entity, _)| **entity == co_mention.entity) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((&co_mention.entity, 1)),
+            }
+        }
+        counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+
+        let mut task_names: Vec<&str> = tasks
+            .iter()
+            .filter(|task| task.assignee.as_deref().is_some_and(|a| a.eq_ignore_ascii_case(name)))
+            .map(|task| task.description.as_str())
+            .collect();
+
+        for (entity, count) in &counts {
+            match entity.kind {
+                EntityKind::Topic => context.topics.push((entity.name.clone(), *count)),
+                EntityKind::Task => task_names.push(&entity.name),
+                EntityKind::Document => push_unique(&mut context.documents, &entity.name),
+                EntityKind::Person => push_unique(&mut context.people, &entity.name),
+            }
+        }
+
+        for relation in related {
+            let verb = relation_verb(&relation.relation);
+            let sentence = if relation.outgoing {
+                format!("{} {} {}", name, verb, relation.entity.name)
+            } else {
+                format!("{} {} {}", relation.entity.name, verb, name)
+            };
+            push_unique(&mut context.relations, &sentence);
+            if relation.entity.kind == EntityKind::Task {
+                task_names.push(&relation.entity.name);
+            }
+        }
+
+        let statuses: HashMap<&str, TaskStatus> = tasks
+            .iter()
+            .map(|task| (task.description.as_str(), task.status))
+            .collect();
+        for task_name in task_names {
+            if !context.tasks.iter().any(|(existing, _)| existing == task_name) {
+                context.tasks.push((task_name.to_string(), statuses.get(task_name).copied()));
+            }
+        }
+
+        for mention in mentions {
+            let title = mention.conversation_title.as_deref().unwrap_or("untitled");
+            if context.conversations.len() < PERSON_CONVERSATIONS
+                && !context.conversations.iter().any(|(existing, _)| existing == title)
+            {
+                context.conversations.push((title.to_string(), mention.timestamp.date_naive()));
+            }
+        }
+
+        context.recent_messages = mentions
+            .iter()
+            .take(PERSON_RECENT_MESSAGES)
+            .map(|mention| (mention.timestamp, snippet(&mention.content, SNIPPET_LENGTH)))
+            .collect();
+
+        context
+    }
+
+    /// Whether the graph knows nothing about this person
+    pub fn is_empty(&self) -> bool {
+        self.topics.is_empty()
+            && self.tasks.is_empty()
+            && self.documents.is_empty()
+            && self.people.is_empty()
+            && self.relations.is_empty()
+            && self.recent_messages.is_empty()
+    }
+
+    /// Render the context as prompt text, one line per non-empty section
+    pub fn render(&self) -> String {
+        let mut lines = vec![format!("What you know about {}:", self.name)];
+
+        if !self.topics.is_empty() {
+            let topics: Vec<String> = self
+                .topics
+                .iter()
+                .map(|(topic, count)| match count {
+                    1 => topic.clone(),
+                    n => format!("{} ({} mentions)", topic, n),
+                })
+                .collect();
+            lines.push(format!("- Topics: {}", topics.join(", ")));
+        }
+        if !self.tasks.is_empty() {
+            let tasks: Vec<String> = self
+                .tasks
+                .iter()
+                .map(|(task, status)| match status {
+                    Some(status) => format!("{} [{}]", task, status.as_str()),
+                    None => task.clone(),
+                })
+                .collect();
+            lines.push(format!("- Tasks: {}", tasks.join(", ")));
+        }
+        if !self.documents.is_empty() {
+            lines.push(format!("- Documents: {}", self.documents.join(", ")));
+        }
+        if !self.people.is_empty() {
+            lines.push(format!("- Mentioned with: {}", self.people.join(", ")));
+        }
+        if !self.relations.is_empty() {
+            lines.push(format!("- Relationships: {}", self.relations.join("; ")));
+        }
+        if !self.conversations.is_empty() {
+            let conversations: Vec<String> = self
+                .conversations
+                .iter()
+                .map(|(title, date)| format!("{} ({})", title, date))
+                .collect();
+            lines.push(format!("- Mentioned in: {}", conversations.join(", ")));
+        }
+        if !self.recent_messages.is_empty() {
+            lines.push("- Latest messages:".to_string());
+            for (timestamp, text) in &self.recent_messages {
+                lines.push(format!("  [{}] \"{}\"", timestamp.format("%Y-%m-%d"), text));
+            }
+        }
+
+        lines.join("\n")
+    }
+}
 
 /// Context retrieval strategies for the agent
 pub struct ContextRetriever<'a> {
     graph_db: &'a GraphDB,
+    relation_types: Vec<String>,
+    traversal: TraversalConfig,
 }
 
 impl<'a> ContextRetriever<'a> {
     /// Create a new context retriever
     pub fn new(graph_db: &'a GraphDB) -> Self {
-        Self { graph_db }
+        Self {
+            graph_db,
+            relation_types: DEFAULT_RELATION_TYPES.iter().map(|t| t.to_string()).collect(),
+            traversal: TraversalConfig::default(),
+        }
+    }
+
+    /// Set the relationship types followed when looking up typed edges
+    pub fn with_relation_types(mut self, relation_types: &[String]) -> Self {
+        self.relation_types = relation_types.to_vec();
+        self
+    }
+
+    /// The entity a name refers to, following aliases and normalized names
+    ///
+    /// Names that match nothing are returned unchanged.
+    pub fn resolve(&self, session: &Session, kind: EntityKind, name: &str) -> Result<EntityRef> {
+        let resolved = self.graph_db.resolve_entity_name(session, kind, name)?;
+        Ok(EntityRef::new(kind, resolved.as_deref().unwrap_or(name)))
+    }
+
+    /// Gather everything the graph knows about a person
+    pub fn person_context(&self, session: &Session, person_name: &str) -> Result<PersonContext> {
+        let person = self.resolve(session, EntityKind::Person, person_name)?;
+        let person_name = person.name.as_str();
+
+        let mentions = self.graph_db.get_entity_mentions(session, &person)?;
+        let co_mentions = self.graph_db.get_co_mentions(session, &person)?;
+        let mut related = Vec::new();
+        for relation_type in &self.relation_types {
+            related.extend(self.graph_db.get_related_by(session, &person, relation_type)?);
+        }
+        let tasks = self.graph_db.list_tasks(session, None)?;
+
+        Ok(PersonContext::from_parts(
+            person_name,
+            &mentions,
+            &co_mentions,
+            &related,
+            &tasks,
+        ))
     }
 
     /// Retrieve context about a specific person
+    ///
+    /// Covers co-mentioned topics, linked tasks, the conversations the person
+    /// came up in, and snippets of the latest messages about them.
     pub fn get_person_context(&self, session: &Session, person_name: &str) -> Result<String> {
-        // Query graph for information about this person
-        // This is a placeholder - actual implementation depends on GraphLite query results
-        Ok(format!("Context about {}: [To be implemented]", person_name))
+        let context = self.person_context(session, person_name)?;
+
+        if context.is_empty() {
+            Ok(format!("No previous context found for person '{}'", person_name))
+        } else {
+            Ok(context.render())
+        }
     }
 
-    /// Retrieve context about a topic
+    /// Set the limits used by [`ContextRetriever::traverse`]
+    pub fn with_traversal(mut self, traversal: TraversalConfig) -> Self {
+        self.traversal = traversal;
+        self
+    }
+
+    /// Entities directly connected to `entity`, strongest first
+    fn neighbors(&self, session: &Session, entity: &EntityRef) -> Result<Vec<PathStep>> {
+        let co_mentions = self.graph_db.get_co_mentions(session, entity)?;
+        let mut related = Vec::new();
+        for relation_type in &self.relation_types {
+            related.extend(self.graph_db.get_related_by(session, entity, relation_type)?);
+        }
+        Ok(rank_neighbors(&co_mentions, &related))
+    }
+
+    /// Walk the graph outwards from `start`, returning the best-scoring paths
+    ///
+    /// Neighbours are entities co-mentioned in a message or connected by a
+    /// typed relationship. Each entity is reached at most once, through the
+    /// strongest link found first.
+    pub fn traverse(&self, session: &Session, start: &EntityRef) -> Result<Vec<ContextPath>> {
+        traverse_with(start, &self.traversal, |entity| self.neighbors(session, entity))
+    }
+
+    /// Retrieve context about a topic, following links up to the configured depth
     pub fn get_topic_context(&self, session: &Session, topic_name: &str) -> Result<String> {
-        let related = self.graph_db.find_related_entities(session, topic_name)?;
+        let topic = self.resolve(session, EntityKind::Topic, topic_name)?;
+        let paths = self.traverse(session, &topic)?;
 
-        if related.is_empty() {
+        if paths.is_empty() {
             Ok(format!("No previous context found for topic '{}'", topic_name))
         } else {
-            Ok(format!(
-                "Topic '{}' is related to: {}",
-                topic_name,
-                related.join(", ")
-            ))
+            let lines: Vec<String> = render_paths(&topic, &paths)
+                .into_iter()
+                .map(|line| format!("- {}", line))
+                .collect();
+            Ok(format!("Related to '{}':\n{}", topic_name, lines.join("\n")))
         }
     }
 
@@ -59,3 +412,448 @@ impl<'a> ContextRetriever<'a> {
         }
     }
 }
+
+/// Breadth-first traversal from `start`, with neighbour lookup supplied by the caller
+///
+/// A path's score is the product of its link strengths, multiplied by
+/// `hop_decay` for every hop after the first.
+fn traverse_with<F>(start: &EntityRef, config: &TraversalConfig, mut neighbors: F) -> Result<Vec<ContextPath>>
+where
+    F: FnMut(&EntityRef) -> Result<Vec<PathStep>>,
+{
+    let mut visited = vec![start.clone()];
+    let mut frontier = vec![ContextPath {
+        start: start.clone(),
+        steps: Vec::new(),
+        score: 1.0,
+    }];
+    let mut paths = Vec::new();
+
+    for depth in 1..=config.max_depth {
+        let decay = if depth == 1 { 1.0 } else { config.hop_decay };
+        let mut next_frontier = Vec::new();
+
+        for path in &frontier {
+            let followed = neighbors(path.end())?
+                .into_iter()
+                .filter(|step| !visited.contains(&step.entity))
+                .take(config.fan_out)
+                .collect::<Vec<_>>();
+
+            for step in followed {
+                visited.push(step.entity.clone());
+                let mut steps = path.steps.clone();
+                let score = path.score * step.link.strength() * decay;
+                steps.push(step);
+                next_frontier.push(ContextPath {
+                    start: start.clone(),
+                    steps,
+                    score,
+                });
+            }
+        }
+
+        paths.extend(next_frontier.iter().cloned());
+        frontier = next_frontier;
+        if frontier.is_empty() {
+            break;
+        }
+    }
+
+    paths.sort_by(|a, b| b.score.total_cmp(&a.score));
+    paths.truncate(config.max_paths);
+    Ok(paths)
+}
+
+/// Turn co-mentions and typed relations into one step per neighbour, strongest first
+fn rank_neighbors(co_mentions: &[CoMention], related: &[RelatedEntity]) -> Vec<PathStep> {
+    let mut steps: Vec<PathStep> = Vec::new();
+    for relation in related {
+        if !steps.iter().any(|step| step.entity == relation.entity) {
+            steps.push(PathStep {
+                link: PathLink::Relation {
+                    relation: relation.relation.clone(),
+                    outgoing: relation.outgoing,
+                },
+                entity: relation.entity.clone(),
+            });
+        }
+    }
+
+    let mut counts: Vec<(&EntityRef, usize)> = Vec::new();
+    for co_mention in co_mentions {
+        match counts.iter_mut().find(|(entity, _)| **entity == co_mention.entity) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((&co_mention.entity, 1)),
+        }
+    }
+    for (entity, messages) in counts {
+        if !steps.iter().any(|step| step.entity == *entity) {
+            steps.push(PathStep {
+                link: PathLink::CoMentioned { messages },
+                entity: entity.clone(),
+            });
+        }
+    }
+
+    // Stable sort keeps relations ahead of equally strong co-mentions
+    steps.sort_by(|a, b| b.link.strength().total_cmp(&a.link.strength()));
+    steps
+}
+
+/// Paths sharing their first hop, rendered as one line
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathGroup {
+    /// First-hop entity the line is about
+    pub entity: EntityRef,
+    pub text: String,
+    /// Score of the best path in the group
+    pub score: f64,
+    /// Messages the first hop shares with the start entity (1 for typed relations)
+    pub mentions: usize,
+}
+
+/// Render paths as one line per first-hop entity, best first, e.g.
+/// "Alice (works on GraphQL, also mentioned with Bob, API design)"
+pub fn render_paths(start: &EntityRef, paths: &[ContextPath]) -> Vec<String> {
+    group_paths(start, paths).into_iter().map(|group| group.text).collect()
+}
+
+/// Group paths by their first hop, best first; see [`render_paths`]
+///
+/// Third hops are shown in brackets after the entity they hang off.
+pub fn group_paths(start: &EntityRef, paths: &[ContextPath]) -> Vec<PathGroup> {
+    let mut first_hops: Vec<&PathStep> = Vec::new();
+    for path in paths {
+        if let Some(first) = path.steps.first() {
+            if !first_hops.iter().any(|step| step.entity == first.entity) {
+                first_hops.push(first);
+            }
+        }
+    }
+
+    first_hops
+        .into_iter()
+        .map(|first| {
+            let mut parts = vec![first.link.reversed().describe(&start.name)];
+            let mut mentioned_with = Vec::new();
+
+            let second_hops = paths
+                .iter()
+                .filter(|path| path.steps.len() == 2 && path.steps[0].entity == first.entity);
+            for path in second_hops {
+                let second = &path.steps[1];
+                let mut name = second.entity.name.clone();
+                let third_hops: Vec<String> = paths
+                    .iter()
+                    .filter(|p| p.steps.len() == 3 && p.steps[..2] == path.steps[..])
+                    .map(|p| p.steps[2].link.describe(&p.steps[2].entity.name))
+                    .collect();
+                if !third_hops.is_empty() {
+                    name = format!("{} [{}]", name, third_hops.join("; "));
+                }
+
+                match second.link {
+                    PathLink::CoMentioned { .. } => mentioned_with.push(name),
+                    _ => parts.push(format!("also {}", second.link.describe(&name))),
+                }
+            }
+            if !mentioned_with.is_empty() {
+                parts.push(format!("also mentioned with {}", mentioned_with.join(", ")));
+            }
+
+            let score = paths
+                .iter()
+                .filter(|path| path.steps.first().is_some_and(|step| step.entity == first.entity))
+                .map(|path| path.score)
+                .fold(0.0, f64::max);
+            let mentions = match first.link {
+                PathLink::CoMentioned { messages } => messages,
+                PathLink::Relation { .. } => 1,
+            };
+
+            PathGroup {
+                entity: first.entity.clone(),
+                text: format!("{} ({})", first.entity.name, parts.join(", ")),
+                score,
+                mentions,
+            }
+        })
+        .collect()
+}
+
+/// Turn an edge label such as `WORKS_ON` into "works on"
+fn relation_verb(relation: &str) -> String {
+    relation.to_lowercase().replace('_', " ")
+}
+
+/// Add `value` to `values` unless it is already present
+fn push_unique(values: &mut Vec<String>, value: &str) {
+    if !values.iter().any(|existing| existing == value) {
+        values.push(value.to_string());
+    }
+}
+
+/// Collapse whitespace and shorten text to at most `max` characters
+fn snippet(text: &str, max: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= max {
+        text
+    } else {
+        let mut shortened: String = text.chars().take(max.saturating_sub(1)).collect();
+        shortened.push('…');
+        shortened
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::parse_timestamp;
+
+    fn mention(content: &str, timestamp: &str, title: &str) -> EntityMention {
+        EntityMention {
+            message_id: format!("msg-{}", timestamp),
+            role: "user".to_string(),
+            content: content.to_string(),
+            timestamp: parse_timestamp(timestamp).unwrap(),
+            conversation_id: format!("conv-{}", title),
+            conversation_title: Some(title.to_string()),
+        }
+    }
+
+    fn co_mention(kind: EntityKind, name: &str, message_id: &str) -> CoMention {
+        CoMention {
+            entity: EntityRef::new(kind, name),
+            message_id: message_id.to_string(),
+            timestamp: None,
+        }
+    }
+
+    fn task(description: &str, status: TaskStatus, assignee: Option<&str>) -> Task {
+        Task {
+            id: description.to_lowercase(),
+            description: description.to_string(),
+            status,
+            created_at: parse_timestamp("2024-01-01T00:00:00Z").unwrap(),
+            due_date: None,
+            assignee: assignee.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_person_context_from_parts() {
+        let mentions = vec![
+            mention("Alice shipped   the API", "2024-03-02T10:00:00Z", "Standup"),
+            mention("Alice and Bob on GraphQL", "2024-03-01T10:00:00Z", "Planning"),
+            mention("Alice likes GraphQL", "2024-02-01T10:00:00Z", "Planning"),
+        ];
+        let co_mentions = vec![
+            co_mention(EntityKind::Topic, "GraphQL", "m1"),
+            co_mention(EntityKind::Person, "Bob", "m1"),
+            co_mention(EntityKind::Topic, "API design", "m2"),
+            co_mention(EntityKind::Topic, "GraphQL", "m3"),
+            co_mention(EntityKind::Task, "Ship the API", "m2"),
+        ];
+        let related = vec![
+            RelatedEntity {
+                relation: "WORKS_ON".to_string(),
+                outgoing: true,
+                entity: EntityRef::new(EntityKind::Topic, "GraphQL"),
+                message_id: None,
+            },
+            RelatedEntity {
+                relation: "KNOWS".to_string(),
+                outgoing: false,
+                entity: EntityRef::new(EntityKind::Person, "Bob"),
+                message_id: None,
+            },
+        ];
+        let tasks = vec![
+            task("Ship the API", TaskStatus::Completed, None),
+            task("Write docs", TaskStatus::Pending, Some("alice")),
+        ];
+
+        let context = PersonContext::from_parts("Alice", &mentions, &co_mentions, &related, &tasks);
+
+        assert_eq!(
+            context.topics,
+            vec![("GraphQL".to_string(), 2), ("API design".to_string(), 1)]
+        );
+        assert_eq!(
+            context.tasks,
+            vec![
+                ("Write docs".to_string(), Some(TaskStatus::Pending)),
+                ("Ship the API".to_string(), Some(TaskStatus::Completed)),
+            ]
+        );
+        assert_eq!(context.people, vec!["Bob"]);
+        assert_eq!(context.relations, vec!["Alice works on GraphQL", "Bob knows Alice"]);
+        assert_eq!(
+            context.conversations,
+            vec![
+                ("Standup".to_string(), NaiveDate::from_ymd_opt(2024, 3, 2).unwrap()),
+                ("Planning".to_string(), NaiveDate::from_ymd_opt(2024, 3, 1).unwrap()),
+            ]
+        );
+        assert_eq!(context.recent_messages.len(), 3);
+        assert_eq!(context.mention_count, 3);
+        assert_eq!(context.last_mentioned, parse_timestamp("2024-03-02T10:00:00Z"));
+        assert_eq!(context.recent_messages[0].1, "Alice shipped the API");
+    }
+
+    #[test]
+    fn test_person_context_render() {
+        let context = PersonContext {
+            name: "Alice".to_string(),
+            topics: vec![("GraphQL".to_string(), 2)],
+            tasks: vec![("Write docs".to_string(), Some(TaskStatus::Pending))],
+            recent_messages: vec![(
+                parse_timestamp("2024-03-02T10:00:00Z").unwrap(),
+                "Alice shipped the API".to_string(),
+            )],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            context.render(),
+            "What you know about Alice:\n\
+             - Topics: GraphQL (2 mentions)\n\
+             - Tasks: Write docs [pending]\n\
+             - Latest messages:\n  [2024-03-02] \"Alice shipped the API\""
+        );
+        assert!(PersonContext::default().is_empty());
+    }
+
+    #[test]
+    fn test_snippet() {
+        assert_eq!(snippet("short\n text", 20), "short text");
+        assert_eq!(snippet("abcdefghij", 5), "abcd…");
+    }
+
+    fn topic(name: &str) -> EntityRef {
+        EntityRef::new(EntityKind::Topic, name)
+    }
+
+    fn person(name: &str) -> EntityRef {
+        EntityRef::new(EntityKind::Person, name)
+    }
+
+    fn co(entity: EntityRef, messages: usize) -> PathStep {
+        PathStep { link: PathLink::CoMentioned { messages }, entity }
+    }
+
+    fn rel(relation: &str, outgoing: bool, entity: EntityRef) -> PathStep {
+        PathStep {
+            link: PathLink::Relation { relation: relation.to_string(), outgoing },
+            entity,
+        }
+    }
+
+    /// Neighbour lookup over a fixed adjacency list
+    fn graph(edges: Vec<(EntityRef, Vec<PathStep>)>) -> impl FnMut(&EntityRef) -> Result<Vec<PathStep>> {
+        move |entity| {
+            Ok(edges
+                .iter()
+                .find(|(from, _)| from == entity)
+                .map(|(_, steps)| steps.clone())
+                .unwrap_or_default())
+        }
+    }
+
+    fn sample_graph() -> impl FnMut(&EntityRef) -> Result<Vec<PathStep>> {
+        graph(vec![
+            (topic("GraphQL"), vec![rel("WORKS_ON", false, person("Alice")), co(person("Carol"), 1)]),
+            (
+                person("Alice"),
+                vec![
+                    rel("WORKS_ON", true, topic("GraphQL")),
+                    co(person("Bob"), 3),
+                    co(topic("API design"), 1),
+                ],
+            ),
+            (person("Bob"), vec![rel("WORKS_ON", true, topic("Billing"))]),
+        ])
+    }
+
+    #[test]
+    fn test_traverse_scores_and_depth() {
+        let paths = traverse_with(&topic("GraphQL"), &TraversalConfig::default(), sample_graph()).unwrap();
+        let ends: Vec<(&str, usize)> = paths
+            .iter()
+            .map(|path| (path.end().name.as_str(), path.steps.len()))
+            .collect();
+
+        // Depth 2 by default: Billing (3 hops) is not reached, GraphQL is not revisited
+        assert_eq!(
+            ends,
+            vec![("Alice", 1), ("Carol", 1), ("Bob", 2), ("API design", 2)]
+        );
+        assert_eq!(paths[0].score, 1.0);
+        assert_eq!(paths[1].score, 0.5);
+        assert_eq!(paths[2].score, 0.375); // 1.0 * 3/4 * 0.5
+    }
+
+    #[test]
+    fn test_traverse_fan_out_and_max_depth() {
+        let config = TraversalConfig { max_depth: 3, fan_out: 1, ..Default::default() };
+        let paths = traverse_with(&topic("GraphQL"), &config, sample_graph()).unwrap();
+        let ends: Vec<&str> = paths.iter().map(|path| path.end().name.as_str()).collect();
+
+        assert_eq!(ends, vec!["Alice", "Bob", "Billing"]);
+    }
+
+    #[test]
+    fn test_rank_neighbors_prefers_relations() {
+        let co_mentions = vec![
+            co_mention(EntityKind::Person, "Bob", "m1"),
+            co_mention(EntityKind::Person, "Alice", "m1"),
+            co_mention(EntityKind::Person, "Bob", "m2"),
+        ];
+        let related = vec![
+            RelatedEntity {
+                relation: "WORKS_ON".to_string(),
+                outgoing: false,
+                entity: person("Alice"),
+                message_id: None,
+            },
+            RelatedEntity {
+                relation: "KNOWS".to_string(),
+                outgoing: true,
+                entity: person("Carol"),
+                message_id: None,
+            },
+            RelatedEntity {
+                relation: "KNOWS".to_string(),
+                outgoing: false,
+                entity: person("Alice"),
+                message_id: None,
+            },
+        ];
+
+        let steps = rank_neighbors(&co_mentions, &related);
+
+        assert_eq!(
+            steps,
+            vec![
+                rel("WORKS_ON", false, person("Alice")),
+                rel("KNOWS", true, person("Carol")),
+                co(person("Bob"), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_paths() {
+        let config = TraversalConfig { max_depth: 3, ..Default::default() };
+        let paths = traverse_with(&topic("GraphQL"), &config, sample_graph()).unwrap();
+
+        assert_eq!(
+            render_paths(&topic("GraphQL"), &paths),
+            vec![
+                "Alice (works on GraphQL, also mentioned with Bob [works on Billing], API design)",
+                "Carol (mentioned with GraphQL)",
+            ]
+        );
+    }
+}
@@ -0,0 +1,312 @@
+use anyhow::{Context, Result};
+use graphlite_sdk::Session;
+use serde_json::json;
+
+use super::retrieval::{render_paths, ContextRetriever};
+use crate::graph::{EntityKind, GraphDB, Task, TaskStatus};
+use crate::llm::{ToolCall, ToolDefinition};
+
+/// Default number of tool-calling rounds before the model must answer
+pub const DEFAULT_MAX_TOOL_ITERATIONS: usize = 5;
+
+/// Messages returned by `search_memory` unless the model asks for fewer
+const DEFAULT_SEARCH_LIMIT: usize = 5;
+
+/// Upper bound on `search_memory` results, whatever the model asks for
+const MAX_SEARCH_LIMIT: usize = 20;
+
+/// A memory tool call with validated arguments
+#[derive(Debug, Clone, PartialEq)]
+pub enum MemoryTool {
+    /// Find past messages containing the query words
+    SearchMemory { query: String, limit: usize },
+    /// Everything known about a person
+    GetPerson { name: String },
+    /// Tasks with the given status, or all open tasks
+    ListTasks { status: Option<TaskStatus> },
+    /// Close a task by id or description
+    CompleteTask { task: String },
+    /// Entities connected to an entity within a few hops
+    FindRelated { kind: EntityKind, name: String },
+}
+
+/// A tool call made during a turn, with what it returned
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolInvocation {
+    pub call: ToolCall,
+    pub output: String,
+    pub is_error: bool,
+}
+
+/// Final answer of a tool-using turn and the tools called to produce it
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgentResponse {
+    pub text: String,
+    pub invocations: Vec<ToolInvocation>,
+}
+
+/// Definitions of the memory tools offered to the model
+pub fn memory_tools() -> Vec<ToolDefinition> {
+    let tool = |name: &str, description: &str, input_schema: serde_json::Value| ToolDefinition {
+        name: name.to_string(),
+        description: description.to_string(),
+        input_schema,
+    };
+
+    vec![
+        tool(
+            "search_memory",
+            "Search past messages from all conversations for words or phrases",
+            json!({
+                "type": "object",
+                "properties": {
+                    "query": {"type": "string", "description": "Words to look for"},
+                    "limit": {"type": "integer", "minimum": 1, "maximum": MAX_SEARCH_LIMIT}
+                },
+                "required": ["query"]
+            }),
+        ),
+        tool(
+            "get_person",
+            "Everything remembered about a person: topics, tasks, relationships and recent messages",
+            json!({
+                "type": "object",
+                "properties": {"name": {"type": "string"}},
+                "required": ["name"]
+            }),
+        ),
+        tool(
+            "list_tasks",
+            "List tracked tasks with their status, due date and assignee",
+            json!({
+                "type": "object",
+                "properties": {
+                    "status": {"type": "string", "enum": ["pending", "in_progress", "completed", "open"]}
+                }
+            }),
+        ),
+        tool(
+            "complete_task",
+            "Mark a task as completed, by id or by description",
+            json!({
+                "type": "object",
+                "properties": {"task": {"type": "string", "description": "Task id or description"}},
+                "required": ["task"]
+            }),
+        ),
+        tool(
+            "find_related",
+            "Find people, topics, tasks and documents connected to an entity in the memory graph",
+            json!({
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string"},
+                    "kind": {"type": "string", "enum": ["person", "topic", "task", "document"]}
+                },
+                "required": ["name"]
+            }),
+        ),
+    ]
+}
+
+impl MemoryTool {
+    /// Validate a tool call from the model
+    pub fn parse(call: &ToolCall) -> Result<Self> {
+        let args = &call.arguments;
+        let text = |field: &str| -> Result<String> {
+            args[field]
+                .as_str()
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(String::from)
+                .with_context(|| format!("'{}' requires a non-empty '{}' argument", call.name, field))
+        };
+
+        match call.name.as_str() {
+            "search_memory" => Ok(MemoryTool::SearchMemory {
+                query: text("query")?,
+                limit: args["limit"]
+                    .as_u64()
+                    .map_or(DEFAULT_SEARCH_LIMIT, |limit| (limit as usize).clamp(1, MAX_SEARCH_LIMIT)),
+            }),
+            "get_person" => Ok(MemoryTool::GetPerson { name: text("name")? }),
+            "list_tasks" => {
+                // Without a status (or with "open") only pending and in-progress tasks are listed
+                let status = match args["status"].as_str() {
+                    None | Some("open") => None,
+                    Some(status) => Some(
+                        TaskStatus::parse(status).with_context(|| format!("Unknown task status: {}", status))?,
+                    ),
+                };
+                Ok(MemoryTool::ListTasks { status })
+            }
+            "complete_task" => Ok(MemoryTool::CompleteTask { task: text("task")? }),
+            "find_related" => {
+                let kind = match args["kind"].as_str().unwrap_or("topic") {
+                    "person" => EntityKind::Person,
+                    "topic" => EntityKind::Topic,
+                    "task" => EntityKind::Task,
+                    "document" => EntityKind::Document,
+                    other => anyhow::bail!("Unknown entity kind: {}", other),
+                };
+                Ok(MemoryTool::FindRelated { kind, name: text("name")? })
+            }
+            other => anyhow::bail!("Unknown tool: {}", other),
+        }
+    }
+
+    /// Run the tool against the graph and describe the result for the model
+    pub fn run(&self, session: &Session, graph_db: &GraphDB, retriever: &ContextRetriever) -> Result<String> {
+        match self {
+            MemoryTool::SearchMemory { query, limit } => {
+                let messages = graph_db.search_messages(session, query, *limit)?;
+                if messages.is_empty() {
+                    return Ok(format!("No messages found for \"{}\".", query));
+                }
+                Ok(messages
+                    .iter()
+                    .map(|m| {
+                        format!(
+                            "[{}, {}] {}: {}",
+                            m.timestamp.format("%Y-%m-%d"),
+                            m.conversation_title.as_deref().unwrap_or("untitled"),
+                            m.role,
+                            m.content
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"))
+            }
+            MemoryTool::GetPerson { name } => retriever.get_person_context(session, name),
+            MemoryTool::ListTasks { status } => {
+                let tasks = match status {
+                    Some(status) => graph_db.list_tasks(session, Some(*status))?,
+                    None => graph_db.list_open_tasks(session)?,
+                };
+                if tasks.is_empty() {
+                    return Ok("No matching tasks.".to_string());
+                }
+                Ok(tasks.iter().map(describe_task).collect::<Vec<_>>().join("\n"))
+            }
+            MemoryTool::CompleteTask { task } => {
+                // Only a full id counts: ids are hex, so words like "add" or
+                // "cafe" would match id prefixes of unrelated tasks
+                let by_id = graph_db
+                    .list_tasks(session, None)?
+                    .into_iter()
+                    .find(|found| found.id == *task);
+                let completed = match by_id {
+                    Some(found) if found.status == TaskStatus::Completed => {
+                        return Ok(format!("Task already completed: {}", found.description));
+                    }
+                    Some(found) => {
+                        graph_db.set_task_status(session, &found.id, TaskStatus::Completed)?;
+                        Some(found)
+                    }
+                    None => graph_db.complete_matching_task(session, task)?,
+                };
+                Ok(match completed {
+                    Some(found) => format!("Completed task: {}", found.description),
+                    None => format!("No open task matches \"{}\".", task),
+                })
+            }
+            MemoryTool::FindRelated { kind, name } => {
+                let start = retriever.resolve(session, *kind, name)?;
+                let lines = render_paths(&start, &retriever.traverse(session, &start)?);
+                if lines.is_empty() {
+                    return Ok(format!("Nothing related to {} was found.", name));
+                }
+                Ok(lines.join("\n"))
+            }
+        }
+    }
+}
+
+/// One-line description of a task for tool output
+fn describe_task(task: &Task) -> String {
+    let mut details = vec![task.status.as_str().to_string()];
+    if let Some(due_date) = task.due_date {
+        details.push(format!("due {}", due_date));
+    }
+    if let Some(assignee) = &task.assignee {
+        details.push(format!("assigned to {}", assignee));
+    }
+    format!("[{}] {} ({})", task.id, task.description, details.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(name: &str, arguments: serde_json::Value) -> ToolCall {
+        ToolCall {
+            id: "call_1".to_string(),
+            name: name.to_string(),
+            arguments,
+        }
+    }
+
+    #[test]
+    fn test_memory_tools_are_named_uniquely() {
+        let tools = memory_tools();
+        let names: Vec<&str> = tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["search_memory", "get_person", "list_tasks", "complete_task", "find_related"]);
+        for tool in &tools {
+            assert_eq!(tool.input_schema["type"], "object");
+        }
+    }
+
+    #[test]
+    fn test_parse_tool_calls() {
+        assert_eq!(
+            MemoryTool::parse(&call("search_memory", json!({"query": " GraphQL ", "limit": 500}))).unwrap(),
+            MemoryTool::SearchMemory { query: "GraphQL".to_string(), limit: MAX_SEARCH_LIMIT }
+        );
+        assert_eq!(
+            MemoryTool::parse(&call("search_memory", json!({"query": "GraphQL"}))).unwrap(),
+            MemoryTool::SearchMemory { query: "GraphQL".to_string(), limit: DEFAULT_SEARCH_LIMIT }
+        );
+        assert_eq!(
+            MemoryTool::parse(&call("list_tasks", json!({"status": "in-progress"}))).unwrap(),
+            MemoryTool::ListTasks { status: Some(TaskStatus::InProgress) }
+        );
+        assert_eq!(
+            MemoryTool::parse(&call("list_tasks", json!({}))).unwrap(),
+            MemoryTool::ListTasks { status: None }
+        );
+        assert_eq!(
+            MemoryTool::parse(&call("find_related", json!({"name": "Alice", "kind": "person"}))).unwrap(),
+            MemoryTool::FindRelated { kind: EntityKind::Person, name: "Alice".to_string() }
+        );
+        assert_eq!(
+            MemoryTool::parse(&call("find_related", json!({"name": "GraphQL"}))).unwrap(),
+            MemoryTool::FindRelated { kind: EntityKind::Topic, name: "GraphQL".to_string() }
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_calls() {
+        assert!(MemoryTool::parse(&call("get_person", json!({}))).is_err());
+        assert!(MemoryTool::parse(&call("get_person", json!({"name": "  "}))).is_err());
+        assert!(MemoryTool::parse(&call("complete_task", json!("not an object"))).is_err());
+        assert!(MemoryTool::parse(&call("list_tasks", json!({"status": "someday"}))).is_err());
+        assert!(MemoryTool::parse(&call("delete_everything", json!({}))).is_err());
+    }
+
+    #[test]
+    fn test_describe_task() {
+        let task = Task {
+            id: "0123456789abcdef".to_string(),
+            description: "Review the schema".to_string(),
+            status: TaskStatus::Pending,
+            created_at: chrono::Utc::now(),
+            due_date: chrono::NaiveDate::from_ymd_opt(2024, 6, 1),
+            assignee: Some("Alice".to_string()),
+        };
+
+        assert_eq!(
+            describe_task(&task),
+            "[0123456789abcdef] Review the schema (pending, due 2024-06-01, assigned to Alice)"
+        );
+    }
+}
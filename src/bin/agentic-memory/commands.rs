@@ -0,0 +1,339 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use colored::*;
+use graphlite_sdk::Session;
+use std::io::Write;
+
+use agentic_memory::agent::AgenticMemory;
+use agentic_memory::graph::{ConversationSummary, EntityKind, GraphDB, Task, TaskStatus};
+
+/// Number of conversations shown per page
+pub const CONVERSATIONS_PAGE_SIZE: usize = 20;
+
+/// Handle a `/command` typed into the REPL
+pub async fn run_repl_command(memory: &AgenticMemory, session: &Session, input: &str) -> Result<()> {
+    let graph = memory.graph();
+    let mut parts = input.split_whitespace();
+    let command = parts.next().unwrap_or_default();
+    let argument = parts.next();
+    let rest = parts.collect::<Vec<_>>().join(" ");
+
+    match command {
+        "/conversations" => match argument {
+            // A numeric argument selects a page, anything else is a conversation id
+            None => list_conversations(graph, session, 0, CONVERSATIONS_PAGE_SIZE),
+            Some(arg) => match arg.parse::<usize>() {
+                Ok(page) => list_conversations(
+                    graph,
+                    session,
+                    page.saturating_sub(1) * CONVERSATIONS_PAGE_SIZE,
+                    CONVERSATIONS_PAGE_SIZE,
+                ),
+                Err(_) => show_transcript(graph, session, arg),
+            },
+        },
+        "/tasks" => list_tasks(graph, session, argument == Some("all")),
+        "/done" => update_task_status(graph, session, argument, TaskStatus::Completed),
+        "/start" => update_task_status(graph, session, argument, TaskStatus::InProgress),
+        "/due" => {
+            let task = graph.find_task(session, argument.context("Usage: /due <task-id> <YYYY-MM-DD>")?)?;
+            let due_date = NaiveDate::parse_from_str(&rest, "%Y-%m-%d")
+                .context("Usage: /due <task-id> <YYYY-MM-DD>")?;
+            graph.set_task_due_date(session, &task.id, due_date)?;
+            println!("{}", format!("Task '{}' is due {}", task.description, due_date).green());
+            Ok(())
+        }
+        "/assign" => {
+            let task = graph.find_task(session, argument.context("Usage: /assign <task-id> <person>")?)?;
+            if rest.is_empty() {
+                anyhow::bail!("Usage: /assign <task-id> <person>");
+            }
+            graph.assign_task(session, &task.id, &rest)?;
+            println!("{}", format!("Assigned '{}' to {}", task.description, rest).green());
+            Ok(())
+        }
+        "/merge" => {
+            let usage = "Usage: /merge <person|topic> <alias> into <canonical>";
+            let kind = merge_kind(argument).context(usage)?;
+            let (alias, canonical) = rest
+                .split_once(" into ")
+                .map(|(alias, canonical)| (alias.trim(), canonical.trim()))
+                .filter(|(alias, canonical)| !alias.is_empty() && !canonical.is_empty())
+                .context(usage)?;
+            let report = memory.merge_entities(session, kind, alias, canonical)?;
+            println!(
+                "{}",
+                format!(
+                    "Merged '{}' into '{}' ({} mentions, {} relationships moved)",
+                    report.alias, report.canonical, report.mentions_moved, report.relations_moved
+                )
+                .green()
+            );
+            Ok(())
+        }
+        "/aliases" => {
+            let aliases = graph.list_aliases(session)?;
+            if aliases.is_empty() {
+                println!("{}", "No aliases recorded.".yellow());
+            }
+            for alias in aliases {
+                println!("  {:<7} {} -> {}", alias.kind.label(), alias.alias, alias.canonical);
+            }
+            Ok(())
+        }
+        "/suggest-merges" => {
+            let kind = merge_kind(argument).context("Usage: /suggest-merges <person|topic>")?;
+            suggest_merges(memory, session, kind).await
+        }
+        "/help" => {
+            print_repl_help();
+            Ok(())
+        }
+        _ => {
+            println!("{}", format!("Unknown command: {}", command).yellow());
+            print_repl_help();
+            Ok(())
+        }
+    }
+}
+
+/// Print the available REPL commands
+fn print_repl_help() {
+    println!("{}", "Commands:".bold());
+    println!("  /conversations [page]   List past conversations");
+    println!("  /conversations <id>     Show a conversation's full transcript");
+    println!("  /tasks [all]            List open tasks (or all tasks)");
+    println!("  /start <task-id>        Mark a task as in progress");
+    println!("  /done <task-id>         Mark a task as completed");
+    println!("  /due <task-id> <date>   Set a due date (YYYY-MM-DD)");
+    println!("  /assign <task-id> <who> Assign a task to a person");
+    println!("  /merge <kind> ...       Merge a person/topic: <alias> into <canonical>");
+    println!("  /aliases                Show recorded aliases");
+    println!("  /suggest-merges <kind>  Ask the model for person/topic duplicates");
+    println!("  /help                   Show this help");
+    println!("  exit, quit              End the conversation\n");
+}
+
+/// Stable node id (as used by `export`) of an entity given as `kind:name`
+///
+/// People and topics are resolved through aliases and normalized names, and
+/// tasks may be given by id prefix, so `person:alice` or `task:3f2b` work.
+pub fn entity_node_id(graph: &GraphDB, session: &Session, entity: &str) -> Result<String> {
+    let (kind, name) = entity
+        .split_once(':')
+        .map(|(kind, name)| (kind.trim().to_lowercase(), name.trim()))
+        .filter(|(_, name)| !name.is_empty())
+        .with_context(|| format!("Expected <kind>:<name>, e.g. person:Alice, got '{}'", entity))?;
+
+    match kind.as_str() {
+        "person" | "topic" => {
+            let kind = if kind == "person" { EntityKind::Person } else { EntityKind::Topic };
+            let resolved = graph
+                .resolve_entity_name(session, kind, name)?
+                .with_context(|| format!("{} not found: {}", kind.label(), name))?;
+            Ok(format!("{}:{}", kind.label().to_lowercase(), resolved))
+        }
+        "task" => Ok(format!("task:{}", graph.find_task(session, name)?.id)),
+        "document" => Ok(format!("document:{}", name)),
+        other => anyhow::bail!("Unknown entity kind '{}' (expected person, topic, task or document)", other),
+    }
+}
+
+/// Entity kind named in `/merge` and `/suggest-merges`
+fn merge_kind(argument: Option<&str>) -> Option<EntityKind> {
+    match argument?.to_lowercase().as_str() {
+        "person" | "people" => Some(EntityKind::Person),
+        "topic" | "topics" => Some(EntityKind::Topic),
+        _ => None,
+    }
+}
+
+/// Ask the model for likely duplicates and merge the ones the user confirms
+async fn suggest_merges(memory: &AgenticMemory, session: &Session, kind: EntityKind) -> Result<()> {
+    let proposals = memory.propose_merges(session, kind).await?;
+    if proposals.is_empty() {
+        println!("{}", "No duplicates suggested.".yellow());
+        return Ok(());
+    }
+
+    for proposal in proposals {
+        print!(
+            "Merge {} into '{}'? [y/N] ",
+            proposal
+                .aliases
+                .iter()
+                .map(|alias| format!("'{}'", alias))
+                .collect::<Vec<_>>()
+                .join(", "),
+            proposal.canonical
+        );
+        std::io::stdout().flush()?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+            continue;
+        }
+
+        for alias in &proposal.aliases {
+            let report = memory.merge_entities(session, kind, alias, &proposal.canonical)?;
+            println!(
+                "{}",
+                format!("Merged '{}' into '{}'", report.alias, report.canonical).green()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Print open tasks (or all tasks) as a table
+fn list_tasks(graph: &GraphDB, session: &Session, include_completed: bool) -> Result<()> {
+    let tasks = if include_completed {
+        graph.list_tasks(session, None)?
+    } else {
+        graph.list_open_tasks(session)?
+    };
+
+    if tasks.is_empty() {
+        println!("{}", "No tasks found.".yellow());
+        return Ok(());
+    }
+
+    print_task_table(&tasks);
+    println!();
+    Ok(())
+}
+
+/// Print tasks as a fixed-width table with short ids
+fn print_task_table(tasks: &[Task]) {
+    println!(
+        "{}",
+        format!(
+            "{:<8}  {:<11}  {:<10}  {:<16}  {}",
+            "ID", "STATUS", "DUE", "ASSIGNEE", "DESCRIPTION"
+        )
+        .bold()
+    );
+
+    for task in tasks {
+        let due = task
+            .due_date
+            .map(|date| date.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "{:<8}  {:<11}  {:<10}  {:<16}  {}",
+            task.id.chars().take(8).collect::<String>(),
+            task.status.as_str(),
+            due,
+            truncate(task.assignee.as_deref().unwrap_or("-"), 16),
+            task.description,
+        );
+    }
+}
+
+/// Move the task identified by `argument` (id or id prefix) to `status`
+fn update_task_status(
+    graph: &GraphDB,
+    session: &Session,
+    argument: Option<&str>,
+    status: TaskStatus,
+) -> Result<()> {
+    let task_id = argument.context("Usage: /done <task-id> or /start <task-id>")?;
+    let task = graph.find_task(session, task_id)?;
+    graph.set_task_status(session, &task.id, status)?;
+
+    println!(
+        "{}",
+        format!("Task '{}' is now {}", task.description, status.as_str()).green()
+    );
+    Ok(())
+}
+
+/// Print one page of conversations as a table
+pub fn list_conversations(
+    graph: &GraphDB,
+    session: &Session,
+    offset: usize,
+    limit: usize,
+) -> Result<()> {
+    let conversations = graph.list_conversations(session, offset, limit)?;
+
+    if conversations.is_empty() {
+        println!("{}", "No conversations found.".yellow());
+        return Ok(());
+    }
+
+    print_conversation_table(&conversations);
+    if conversations.len() == limit {
+        println!(
+            "{}",
+            format!("Showing {}-{}; more may be available.", offset + 1, offset + limit).dimmed()
+        );
+    }
+    println!();
+
+    Ok(())
+}
+
+/// Print conversation summaries as a fixed-width table
+fn print_conversation_table(conversations: &[ConversationSummary]) {
+    println!(
+        "{}",
+        format!(
+            "{:<36}  {:<30}  {:<16}  {:>8}  {:<16}",
+            "ID", "TITLE", "STARTED", "MESSAGES", "LAST ACTIVITY"
+        )
+        .bold()
+    );
+
+    for conversation in conversations {
+        println!(
+            "{:<36}  {:<30}  {:<16}  {:>8}  {:<16}",
+            conversation.id,
+            truncate(conversation.title.as_deref().unwrap_or("-"), 30),
+            conversation.started_at.format("%Y-%m-%d %H:%M"),
+            conversation.message_count,
+            conversation.last_activity.format("%Y-%m-%d %H:%M"),
+        );
+    }
+}
+
+/// Print the full transcript of one conversation in chronological order
+pub fn show_transcript(graph: &GraphDB, session: &Session, conversation_id: &str) -> Result<()> {
+    let Some(conversation) = graph.get_conversation(session, conversation_id)? else {
+        println!("{}", format!("Conversation not found: {}", conversation_id).yellow());
+        return Ok(());
+    };
+
+    println!(
+        "{}",
+        format!(
+            "{} (started {})",
+            conversation.title.as_deref().unwrap_or("untitled"),
+            conversation.started_at.format("%Y-%m-%d %H:%M")
+        )
+        .bold()
+    );
+
+    for (role, content, timestamp) in graph.get_conversation_transcript(session, conversation_id)? {
+        let speaker = match role.as_str() {
+            "user" => "You:".bright_blue().bold(),
+            "assistant" => "Assistant:".bright_green().bold(),
+            other => other.normal(),
+        };
+        println!("{} {} {}", format!("[{}]", timestamp).dimmed(), speaker, content);
+    }
+    println!();
+
+    Ok(())
+}
+
+/// Shorten text to at most `max` characters, marking the cut with an ellipsis
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        text.to_string()
+    } else {
+        let mut shortened: String = text.chars().take(max.saturating_sub(1)).collect();
+        shortened.push('…');
+        shortened
+    }
+}
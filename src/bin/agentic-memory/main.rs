@@ -0,0 +1,639 @@
+mod commands;
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use colored::*;
+use dotenv::dotenv;
+use futures::StreamExt;
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+#[cfg(feature = "server")]
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use agentic_memory::{agent, graph, AgenticMemory, ChatMessage, ChatRole, GraphDB, LLMClient, LLMProvider, RetryPolicy};
+
+/// Agentic Memory - AI Assistant with Context Graph powered by GraphLite
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Path to the GraphLite database
+    #[arg(short, long, default_value = "./data/memory.db")]
+    db_path: String,
+
+    /// Database admin username
+    #[arg(short, long, default_value = "admin")]
+    user: String,
+
+    /// Database admin password
+    #[arg(short, long, default_value = "admin123")]
+    password: String,
+
+    /// Conversation title
+    #[arg(short, long)]
+    title: Option<String>,
+
+    /// Resume an existing conversation by id
+    #[arg(long, value_name = "CONVERSATION_ID", conflicts_with = "resume_last")]
+    resume: Option<String>,
+
+    /// Resume the most recently active conversation
+    #[arg(long)]
+    resume_last: bool,
+
+    /// Token budget for graph context in the system prompt
+    #[arg(long, default_value_t = agent::DEFAULT_CONTEXT_TOKENS)]
+    context_tokens: usize,
+
+    /// Show which context snippets were included or cut for each reply
+    #[arg(long)]
+    debug_context: bool,
+
+    /// Let the assistant call memory tools (search, people, tasks) before answering
+    #[arg(long)]
+    tools: bool,
+
+    /// Maximum rounds of tool calls per reply
+    #[arg(long, default_value_t = agent::DEFAULT_MAX_TOOL_ITERATIONS)]
+    max_tool_iterations: usize,
+
+    /// LLM provider for entity extraction [env: EXTRACTION_PROVIDER] (default: LLM_PROVIDER)
+    #[arg(long, value_name = "PROVIDER")]
+    extraction_provider: Option<String>,
+
+    /// Model for entity extraction [env: EXTRACTION_MODEL] (default: the reply model)
+    #[arg(long, value_name = "MODEL")]
+    extraction_model: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// Subcommands that run instead of the interactive assistant
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// List past conversations, or print one conversation's transcript
+    Conversations {
+        /// Conversation id whose full transcript should be printed
+        conversation_id: Option<String>,
+
+        /// Maximum number of conversations to list
+        #[arg(long, default_value_t = commands::CONVERSATIONS_PAGE_SIZE)]
+        limit: usize,
+
+        /// Number of conversations to skip
+        #[arg(long, default_value_t = 0)]
+        offset: usize,
+    },
+    /// Write the whole memory graph to a JSON Lines file
+    Export {
+        /// Output file (default: standard output)
+        file: Option<PathBuf>,
+    },
+    /// Merge a JSON Lines export into the database; safe to repeat
+    Import {
+        /// File written by `export` ("-" for standard input)
+        file: PathBuf,
+    },
+    /// Write the graph, or the part around one entity, as Graphviz DOT or GraphML
+    Visualize {
+        /// Output file (default: standard output)
+        file: Option<PathBuf>,
+
+        /// Output format: dot or graphml
+        #[arg(long, default_value = "dot")]
+        format: graph::GraphFormat,
+
+        /// Only include nodes near this entity, e.g. person:Alice or topic:Rust
+        #[arg(long, value_name = "KIND:NAME", conflicts_with = "conversation")]
+        entity: Option<String>,
+
+        /// Only include nodes near this conversation
+        #[arg(long, value_name = "CONVERSATION_ID")]
+        conversation: Option<String>,
+
+        /// Number of hops to follow from the entity or conversation
+        #[arg(long, default_value_t = 2)]
+        depth: usize,
+    },
+    /// Serve the memory over an HTTP JSON API
+    #[cfg(feature = "server")]
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:3000")]
+        bind: SocketAddr,
+    },
+    /// Serve the memory to agent hosts over the Model Context Protocol on stdio
+    #[cfg(feature = "mcp")]
+    Mcp,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Load environment variables
+    dotenv().ok();
+
+    // Parse command line arguments
+    let args = Args::parse();
+
+    match &args.command {
+        #[cfg(feature = "server")]
+        Some(Command::Serve { bind }) => {
+            let memory = create_memory(&args).await?;
+            return agentic_memory::server::serve(memory, &args.user, &args.password, *bind).await;
+        }
+        #[cfg(feature = "mcp")]
+        Some(Command::Mcp) => {
+            let memory = create_memory(&args).await?;
+            return agentic_memory::mcp::serve_stdio(memory, &args.user, &args.password).await;
+        }
+        Some(command) => return run_command(&args, command).await,
+        None => {}
+    }
+
+    // Display welcome banner
+    print_banner();
+
+    // Initialize agentic memory
+    println!("{}", "Initializing agentic memory system...".cyan());
+    let mut memory = create_memory(&args).await?;
+
+    // Create database session
+    let session = memory
+        .session(&args.user, &args.password)
+        .context("Failed to create database session")?;
+
+    // Resume an existing conversation or start a new one
+    let resumed = if let Some(conversation_id) = args.resume.as_deref() {
+        Some(memory.resume_conversation(&session, conversation_id)?)
+    } else if args.resume_last {
+        Some(memory.resume_last_conversation(&session)?)
+    } else {
+        None
+    };
+
+    if let Some((conversation, history)) = resumed {
+        println!(
+            "{}",
+            format!(
+                "Resumed conversation: {} ({})",
+                conversation.id,
+                conversation.title.as_deref().unwrap_or("untitled")
+            )
+            .green()
+        );
+        print_history(&history);
+    } else {
+        let conversation_title = args.title.or_else(|| Some("New Conversation".to_string()));
+        let conv_id = memory
+            .start_conversation(&session, conversation_title)
+            .context("Failed to start conversation")?;
+
+        println!("{}", format!("Started conversation: {}", conv_id).green());
+    }
+    println!("{}", "Type your message and press Enter. Use /help for commands, 'exit' or 'quit' to end the conversation.\n".yellow());
+
+    // Interactive REPL
+    let mut rl = DefaultEditor::new()?;
+
+    loop {
+        let readline = rl.readline(&format!("{} ", "You:".bright_blue().bold()));
+
+        match readline {
+            Ok(line) => {
+                let user_input = line.trim();
+
+                // Check for exit commands
+                if user_input.eq_ignore_ascii_case("exit") || user_input.eq_ignore_ascii_case("quit") {
+                    println!("{}", "Goodbye! Your memory has been saved.".green());
+                    break;
+                }
+
+                // Skip empty messages
+                if user_input.is_empty() {
+                    continue;
+                }
+
+                // Add to history
+                rl.add_history_entry(user_input)?;
+
+                // Handle REPL commands without sending them to the LLM
+                if user_input.starts_with('/') {
+                    if let Err(e) = commands::run_repl_command(&memory, &session, user_input).await {
+                        eprintln!("{}", format!("Error: {}", e).red());
+                    }
+                    continue;
+                }
+
+                // Process the message, printing the reply as it streams in
+                match process_message(&memory, &session, user_input, args.tools).await {
+                    Ok(response) => {
+                        // Store assistant response
+                        if let Err(e) = memory.store_assistant_message(&session, &response) {
+                            eprintln!("{}", format!("Warning: Failed to store assistant message: {}", e).yellow());
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("{}", format!("Error: {}", e).red());
+                    }
+                }
+            }
+            Err(ReadlineError::Interrupted) => {
+                println!("{}", "Interrupted. Use 'exit' or 'quit' to end the conversation.".yellow());
+            }
+            Err(ReadlineError::Eof) => {
+                println!("{}", "Goodbye! Your memory has been saved.".green());
+                break;
+            }
+            Err(err) => {
+                eprintln!("{}", format!("Error: {}", err).red());
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Open the memory with the LLM clients and settings given on the command line
+async fn create_memory(args: &Args) -> Result<AgenticMemory> {
+    let (llm_client, extraction_client) = create_llm_client(args)?;
+
+    let mut memory = AgenticMemory::new(
+        &args.db_path,
+        &args.user,
+        &args.password,
+        llm_client,
+        extraction_client,
+    )
+    .await
+    .context("Failed to initialize agentic memory")?;
+
+    memory.set_context_budget(args.context_tokens);
+    memory.set_context_debug(args.debug_context);
+    memory.set_max_tool_iterations(args.max_tool_iterations);
+
+    if let Ok(relation_types) = env::var("RELATION_TYPES") {
+        memory.set_relation_types(relation_types.split(','));
+    }
+
+    Ok(memory)
+}
+
+/// Process a user message and stream the response to stdout
+///
+/// With `tools`, the assistant may call memory tools first and the final
+/// answer is printed once complete. Returns the full response text.
+async fn process_message(
+    memory: &AgenticMemory,
+    session: &graphlite_sdk::Session,
+    message: &str,
+    tools: bool,
+) -> Result<String> {
+    // Show thinking indicator
+    print!("{}", "Thinking... ".cyan());
+    std::io::Write::flush(&mut std::io::stdout())?;
+
+    // Extract entities and store message
+    let (_msg_id, entities) = memory
+        .process_user_message(session, message)
+        .await
+        .context("Failed to process user message")?;
+
+    // Print extracted entities if any
+    if !entities.people.is_empty()
+        || !entities.topics.is_empty()
+        || !entities.tasks.is_empty()
+        || !entities.documents.is_empty()
+        || !entities.completed_tasks.is_empty()
+        || !entities.relations.is_empty()
+    {
+        println!("\r                    \r"); // Clear thinking indicator
+
+        let mut entity_info = Vec::new();
+        if !entities.people.is_empty() {
+            entity_info.push(format!("People: {}", entities.people.join(", ")));
+        }
+        if !entities.topics.is_empty() {
+            entity_info.push(format!("Topics: {}", entities.topics.join(", ")));
+        }
+        if !entities.tasks.is_empty() {
+            entity_info.push(format!("Tasks: {}", entities.tasks.join(", ")));
+        }
+        if !entities.documents.is_empty() {
+            entity_info.push(format!("Documents: {}", entities.documents.join(", ")));
+        }
+        if !entities.completed_tasks.is_empty() {
+            entity_info.push(format!("Completed: {}", entities.completed_tasks.join(", ")));
+        }
+        if !entities.relations.is_empty() {
+            let relations: Vec<String> = entities
+                .relations
+                .iter()
+                .map(|r| format!("{} -{}-> {}", r.subject, r.predicate, r.object))
+                .collect();
+            entity_info.push(format!("Relations: {}", relations.join(", ")));
+        }
+
+        println!("{}", format!("[Extracted: {}]", entity_info.join(" | ")).dimmed());
+    } else {
+        println!("\r                    \r"); // Clear thinking indicator
+    }
+
+    if tools {
+        let response = memory
+            .generate_response_with_tools(session, message, &entities)
+            .await?;
+
+        if let Some(report) = memory.take_context_report() {
+            println!("{}", report.render().dimmed());
+        }
+        for invocation in &response.invocations {
+            let status = if invocation.is_error { "failed" } else { "ok" };
+            println!(
+                "{}",
+                format!("[Tool: {} {} -> {}]", invocation.call.name, invocation.call.arguments, status).dimmed()
+            );
+        }
+        println!("{} {}\n", "Assistant:".bright_green().bold(), response.text);
+        return Ok(response.text);
+    }
+
+    // Stream the response with context
+    let mut stream = memory
+        .generate_response_stream(session, message, &entities)
+        .await
+        .context("Failed to generate response")?;
+
+    if let Some(report) = memory.take_context_report() {
+        println!("{}", report.render().dimmed());
+    }
+
+    print!("{} ", "Assistant:".bright_green().bold());
+    std::io::Write::flush(&mut std::io::stdout())?;
+
+    let mut response = String::new();
+    while let Some(delta) = stream.next().await {
+        let delta = match delta {
+            Ok(delta) => delta,
+            Err(e) => {
+                println!();
+                return Err(e.context("Response stream interrupted"));
+            }
+        };
+        print!("{}", delta);
+        std::io::Write::flush(&mut std::io::stdout())?;
+        response.push_str(&delta);
+    }
+    println!("\n");
+
+    Ok(response)
+}
+
+/// Run a non-interactive subcommand against the database
+async fn run_command(args: &Args, command: &Command) -> Result<()> {
+    let graph = GraphDB::new(&args.db_path, &args.user, &args.password)
+        .await
+        .context("Failed to initialize graph database")?;
+    let session = graph
+        .session(&args.user, &args.password)
+        .context("Failed to create database session")?;
+
+    match command {
+        Command::Conversations { conversation_id: Some(id), .. } => {
+            commands::show_transcript(&graph, &session, id)
+        }
+        Command::Conversations { conversation_id: None, limit, offset } => {
+            commands::list_conversations(&graph, &session, *offset, *limit)
+        }
+        Command::Export { file } => {
+            let summary = match file {
+                Some(path) => {
+                    let file = File::create(path)
+                        .with_context(|| format!("Failed to create {}", path.display()))?;
+                    graph.export_jsonl(&session, &mut BufWriter::new(file))?
+                }
+                None => graph.export_jsonl(&session, &mut io::stdout().lock())?,
+            };
+            eprintln!(
+                "{}",
+                format!("Exported {} nodes and {} edges", summary.nodes, summary.edges).green()
+            );
+            Ok(())
+        }
+        Command::Import { file } => {
+            let summary = if file.as_os_str() == "-" {
+                graph.import_jsonl(&session, io::stdin().lock())?
+            } else {
+                let reader = File::open(file)
+                    .with_context(|| format!("Failed to open {}", file.display()))?;
+                graph.import_jsonl(&session, BufReader::new(reader))?
+            };
+            println!(
+                "{}",
+                format!(
+                    "Imported {} new nodes ({} already present) and {} new edges ({} already present)",
+                    summary.nodes_created, summary.nodes_merged, summary.edges_created, summary.edges_existing
+                )
+                .green()
+            );
+            if summary.skipped > 0 {
+                println!("{}", format!("Skipped {} records", summary.skipped).yellow());
+            }
+            Ok(())
+        }
+        Command::Visualize { file, format, entity, conversation, depth } => {
+            let mut snapshot = graph.snapshot(&session)?;
+            let start = match (entity, conversation) {
+                (Some(entity), _) => Some(commands::entity_node_id(&graph, &session, entity)?),
+                (None, Some(id)) => Some(format!("conversation:{}", id)),
+                (None, None) => None,
+            };
+            if let Some(start) = start {
+                snapshot = snapshot.neighborhood(&start, *depth)?;
+            }
+
+            let output = graph::render_graph(&snapshot, *format);
+            match file {
+                Some(path) => std::fs::write(path, output)
+                    .with_context(|| format!("Failed to write {}", path.display())),
+                None => {
+                    print!("{}", output);
+                    Ok(())
+                }
+            }
+        }
+        #[cfg(feature = "server")]
+        Command::Serve { .. } => unreachable!("the server needs the LLM clients and is started from main"),
+        #[cfg(feature = "mcp")]
+        Command::Mcp => unreachable!("the MCP server needs the LLM clients and is started from main"),
+    }
+}
+
+/// Print previous turns of a resumed conversation
+fn print_history(history: &[ChatMessage]) {
+    if history.is_empty() {
+        return;
+    }
+
+    println!("{}", "Recent messages:".dimmed());
+    for message in history {
+        let speaker = match message.role {
+            ChatRole::User => "You:",
+            ChatRole::Assistant => "Assistant:",
+        };
+        println!("{}", format!("{} {}", speaker, message.content).dimmed());
+    }
+    println!();
+}
+
+/// Provider names accepted by `LLM_PROVIDER`, depending on the enabled
+/// features; the first is the default
+const PROVIDERS: &[&str] = &[
+    #[cfg(feature = "anthropic")]
+    "anthropic",
+    #[cfg(feature = "openai")]
+    "openai",
+    #[cfg(feature = "openrouter")]
+    "openrouter",
+    #[cfg(feature = "local")]
+    "local",
+];
+
+/// Create the reply and entity extraction LLM clients from the environment
+///
+/// Replies use `LLM_PROVIDER`/`LLM_MODEL`. Extraction uses
+/// `--extraction-provider`/`--extraction-model` (or `EXTRACTION_PROVIDER`/
+/// `EXTRACTION_MODEL`) and falls back to the reply provider; the reply model
+/// is only reused when both use the same provider.
+fn create_llm_client(args: &Args) -> Result<(LLMClient, LLMClient)> {
+    let provider_name = env::var("LLM_PROVIDER")
+        .unwrap_or_else(|_| PROVIDERS[0].to_string())
+        .to_lowercase();
+    let model = env::var("LLM_MODEL").ok();
+
+    let provider = llm_provider("LLM_PROVIDER", &provider_name, model.clone())?;
+    let llm_client = configure_llm_client(LLMClient::new(provider))?;
+
+    let extraction_provider = args
+        .extraction_provider
+        .clone()
+        .or_else(|| env::var("EXTRACTION_PROVIDER").ok())
+        .map(|name| name.to_lowercase());
+    let extraction_model = args
+        .extraction_model
+        .clone()
+        .or_else(|| env::var("EXTRACTION_MODEL").ok());
+
+    if extraction_provider.is_none() && extraction_model.is_none() {
+        return Ok((llm_client.clone(), llm_client));
+    }
+
+    let extraction_provider = extraction_provider.unwrap_or_else(|| provider_name.clone());
+    let extraction_model = extraction_model.or(model.filter(|_| extraction_provider == provider_name));
+    let provider = llm_provider("EXTRACTION_PROVIDER", &extraction_provider, extraction_model)?;
+    let extraction_client = configure_llm_client(LLMClient::new(provider))?;
+
+    Ok((llm_client, extraction_client))
+}
+
+/// Build a provider by name, reading its API key and settings from the
+/// environment; `model` overrides the provider's default model
+fn llm_provider(variable: &str, provider_name: &str, model: Option<String>) -> Result<LLMProvider> {
+    let provider = match provider_name {
+        #[cfg(feature = "anthropic")]
+        "anthropic" => {
+            let api_key = env::var("ANTHROPIC_API_KEY")
+                .context("ANTHROPIC_API_KEY not set in environment")?;
+            let model = model.unwrap_or_else(|| "claude-3-5-sonnet-20241022".to_string());
+
+            LLMProvider::Anthropic { api_key, model }
+        }
+        #[cfg(feature = "openai")]
+        "openai" => {
+            let api_key = env::var("OPENAI_API_KEY")
+                .context("OPENAI_API_KEY not set in environment")?;
+            let model = model.unwrap_or_else(|| "gpt-4-turbo-preview".to_string());
+
+            LLMProvider::OpenAI { api_key, model }
+        }
+        #[cfg(feature = "openrouter")]
+        "openrouter" => {
+            let api_key = env::var("OPENROUTER_API_KEY")
+                .context("OPENROUTER_API_KEY not set in environment")?;
+            let model = model.unwrap_or_else(|| "anthropic/claude-3.5-sonnet".to_string());
+            let app_name = env::var("OPENROUTER_APP_NAME").ok();
+            let site_url = env::var("OPENROUTER_SITE_URL").ok();
+
+            LLMProvider::OpenRouter {
+                api_key,
+                model,
+                app_name,
+                site_url,
+            }
+        }
+        #[cfg(feature = "local")]
+        "local" => {
+            let base_url = env::var("LLM_BASE_URL")
+                .unwrap_or_else(|_| "http://localhost:11434/v1".to_string());
+            let api_key = env::var("LLM_API_KEY").ok().filter(|key| !key.is_empty());
+            let model = model.unwrap_or_else(|| "llama3.1".to_string());
+
+            LLMProvider::OpenAICompatible {
+                base_url,
+                api_key,
+                model,
+            }
+        }
+        _ => {
+            anyhow::bail!(
+                "Unknown {}: {}. This build supports: {}",
+                variable,
+                provider_name,
+                PROVIDERS.join(", ")
+            );
+        }
+    };
+
+    Ok(provider)
+}
+
+/// Apply the retry and timeout settings from the environment
+fn configure_llm_client(mut client: LLMClient) -> Result<LLMClient> {
+    if let Ok(max_retries) = env::var("LLM_MAX_RETRIES") {
+        let max_retries = max_retries
+            .trim()
+            .parse()
+            .context("LLM_MAX_RETRIES must be a non-negative integer")?;
+        client = client.with_retry_policy(RetryPolicy {
+            max_retries,
+            ..Default::default()
+        });
+    }
+    if let Ok(timeout) = env::var("LLM_TIMEOUT_SECS") {
+        let seconds: u64 = timeout
+            .trim()
+            .parse()
+            .context("LLM_TIMEOUT_SECS must be a whole number of seconds")?;
+        client = client.with_timeout(Duration::from_secs(seconds));
+    }
+
+    Ok(client)
+}
+
+/// Print welcome banner
+fn print_banner() {
+    println!("\n{}", "╔══════════════════════════════════════════════════════════╗".bright_cyan());
+    println!("{}", "║                                                          ║".bright_cyan());
+    println!("{}", "║              🧠 AGENTIC MEMORY SYSTEM 🧠                 ║".bright_cyan().bold());
+    println!("{}", "║                                                          ║".bright_cyan());
+    println!("{}", "║        AI Assistant with Context Graph Memory            ║".bright_cyan());
+    println!("{}", "║              Powered by GraphLite                        ║".bright_cyan());
+    println!("{}", "║                                                          ║".bright_cyan());
+    println!("{}", "╚══════════════════════════════════════════════════════════╝".bright_cyan());
+    println!();
+}
@@ -0,0 +1,595 @@
+//! Portable JSON Lines export and import of the memory graph
+//!
+//! The first line is a header naming the format and its version. Every other
+//! line is a node or an edge; all nodes come before the edges. Nodes carry a
+//! stable id derived from the properties that identify them (e.g.
+//! `person:Alice`, `message:<uuid>`), and edges refer to their endpoints by
+//! that id, so an export does not depend on GraphLite's internal node ids.
+
+use anyhow::{Context, Result};
+use graphlite_sdk::{Session, Value};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+use super::operations::GraphDB;
+use super::query::{unescape_string, Query, QueryParam};
+use super::schema::{normalize_relation_type, now, EntityKind};
+
+/// Value of the header's `format` field
+pub const EXPORT_FORMAT: &str = "agentic-memory";
+
+/// Version of the export format written by this build
+pub const EXPORT_VERSION: u32 = 1;
+
+/// Node labels included in an export, in output order
+pub const EXPORT_LABELS: &[&str] = &["Conversation", "Message", "Person", "Topic", "Task", "Document", "Alias"];
+
+/// Properties of a node or edge, sorted by name
+pub type Properties = serde_json::Map<String, serde_json::Value>;
+
+/// One line of an export
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExportRecord {
+    Header {
+        format: String,
+        version: u32,
+        exported_at: String,
+    },
+    Node {
+        id: String,
+        label: String,
+        properties: Properties,
+    },
+    Edge {
+        label: String,
+        from: String,
+        to: String,
+        properties: Properties,
+    },
+}
+
+/// Node of a [`GraphSnapshot`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotNode {
+    /// Stable id, e.g. `person:Alice`
+    pub id: String,
+    pub label: String,
+    pub properties: Properties,
+}
+
+/// Edge of a [`GraphSnapshot`], between stable node ids
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotEdge {
+    pub label: String,
+    pub from: String,
+    pub to: String,
+    pub properties: Properties,
+}
+
+/// All memory nodes and the edges between them, in a stable order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphSnapshot {
+    pub nodes: Vec<SnapshotNode>,
+    pub edges: Vec<SnapshotEdge>,
+}
+
+/// Number of records written by an export
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExportSummary {
+    pub nodes: usize,
+    pub edges: usize,
+}
+
+/// What an import changed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportSummary {
+    pub nodes_created: usize,
+    /// Nodes that already existed (they only gain missing properties)
+    pub nodes_merged: usize,
+    pub edges_created: usize,
+    /// Edges identical to one already in the database
+    pub edges_existing: usize,
+    /// Nodes with an unknown label and edges whose endpoints were not imported
+    pub skipped: usize,
+}
+
+/// A node located by label and identifying properties
+#[derive(Debug, Clone, PartialEq)]
+struct NodeKey {
+    label: String,
+    identity: Vec<(&'static str, String)>,
+}
+
+impl NodeKey {
+    /// `MATCH` pattern binding the node to `variable`, with its parameters
+    fn pattern(&self, variable: &str) -> (String, Vec<(String, String)>) {
+        let mut params = Vec::new();
+        let properties = self
+            .identity
+            .iter()
+            .enumerate()
+            .map(|(index, (property, value))| {
+                let param = format!("{}{}", variable, index);
+                params.push((param.clone(), value.clone()));
+                format!("`{}`: ${}", property, param)
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        (format!("({}:{} {{{}}})", variable, self.label, properties), params)
+    }
+}
+
+impl GraphDB {
+    /// Read every memory node and edge
+    ///
+    /// Nodes are sorted by label (in [`EXPORT_LABELS`] order) and id, edges by
+    /// label, endpoints and properties, so an unchanged database always gives
+    /// the same snapshot.
+    pub fn snapshot(&self, session: &Session) -> Result<GraphSnapshot> {
+        let result = Query::new("MATCH (n) RETURN n")
+            .fetch(session)
+            .context("Failed to read nodes")?;
+
+        let mut stable_ids = HashMap::new();
+        let mut nodes = Vec::new();
+        for row in &result.rows {
+            let Some(Value::Node(node)) = row.get_value("n") else {
+                continue;
+            };
+            let Some(label) = node.labels.iter().find(|label| EXPORT_LABELS.contains(&label.as_str())) else {
+                continue;
+            };
+            let properties = json_properties(&node.properties);
+            let Some(id) = stable_id(label, &properties) else {
+                continue;
+            };
+            stable_ids.insert(node.id.clone(), id.clone());
+            let order = EXPORT_LABELS.iter().position(|known| known == label);
+            nodes.push((order, id, label.clone(), properties));
+        }
+        nodes.sort_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1)));
+
+        let result = Query::new("MATCH (a)-[r]->(b) RETURN r")
+            .fetch(session)
+            .context("Failed to read edges")?;
+
+        let mut edges = Vec::new();
+        for row in &result.rows {
+            let Some(Value::Edge(edge)) = row.get_value("r") else {
+                continue;
+            };
+            let (Some(from), Some(to)) = (stable_ids.get(&edge.from_node), stable_ids.get(&edge.to_node)) else {
+                continue;
+            };
+            let properties = json_properties(&edge.properties);
+            let sort_key = serde_json::to_string(&properties)?;
+            edges.push((edge.label.clone(), from.clone(), to.clone(), sort_key, properties));
+        }
+        edges.sort_by(|a, b| (&a.0, &a.1, &a.2, &a.3).cmp(&(&b.0, &b.1, &b.2, &b.3)));
+
+        Ok(GraphSnapshot {
+            nodes: nodes
+                .into_iter()
+                .map(|(_, id, label, properties)| SnapshotNode { id, label, properties })
+                .collect(),
+            edges: edges
+                .into_iter()
+                .map(|(label, from, to, _, properties)| SnapshotEdge { label, from, to, properties })
+                .collect(),
+        })
+    }
+
+    /// Write every memory node and edge to `writer` as JSON Lines
+    ///
+    /// Records come in [`GraphDB::snapshot`] order, so exporting an unchanged
+    /// database twice gives identical files apart from the header's timestamp.
+    pub fn export_jsonl(&self, session: &Session, writer: &mut impl Write) -> Result<ExportSummary> {
+        let snapshot = self.snapshot(session)?;
+        let summary = ExportSummary {
+            nodes: snapshot.nodes.len(),
+            edges: snapshot.edges.len(),
+        };
+        let header = ExportRecord::Header {
+            format: EXPORT_FORMAT.to_string(),
+            version: EXPORT_VERSION,
+            exported_at: now().to_rfc3339(),
+        };
+        let records = std::iter::once(header)
+            .chain(snapshot.nodes.into_iter().map(|node| ExportRecord::Node {
+                id: node.id,
+                label: node.label,
+                properties: node.properties,
+            }))
+            .chain(snapshot.edges.into_iter().map(|edge| ExportRecord::Edge {
+                label: edge.label,
+                from: edge.from,
+                to: edge.to,
+                properties: edge.properties,
+            }));
+        for record in records {
+            serde_json::to_writer(&mut *writer, &record)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+
+        Ok(summary)
+    }
+
+    /// Import an export written by [`GraphDB::export_jsonl`]
+    ///
+    /// Nodes are matched by their identifying properties: existing nodes keep
+    /// their values and only gain properties they lack, the rest are created.
+    /// People and topics are resolved like extracted names, so "alice" lands
+    /// on an existing "Alice". An edge is only created if no edge with the
+    /// same label, endpoints and properties exists, so importing a file twice
+    /// changes nothing the second time.
+    pub fn import_jsonl(&self, session: &Session, reader: impl BufRead) -> Result<ImportSummary> {
+        let mut summary = ImportSummary::default();
+        let mut nodes: HashMap<String, NodeKey> = HashMap::new();
+        let mut header_seen = false;
+
+        for (index, line) in reader.lines().enumerate() {
+            let line = line.context("Failed to read import file")?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: ExportRecord = serde_json::from_str(&line)
+                .with_context(|| format!("Invalid record on line {}", index + 1))?;
+
+            if !header_seen {
+                check_header(&record)?;
+                header_seen = true;
+                continue;
+            }
+
+            match record {
+                ExportRecord::Header { .. } => anyhow::bail!("Unexpected header on line {}", index + 1),
+                ExportRecord::Node { id, label, properties } => {
+                    match self
+                        .import_node(session, &label, &properties, &mut summary)
+                        .with_context(|| format!("Failed to import node {}", id))?
+                    {
+                        Some(key) => {
+                            nodes.insert(id, key);
+                        }
+                        None => summary.skipped += 1,
+                    }
+                }
+                ExportRecord::Edge { label, from, to, properties } => {
+                    let (Some(from), Some(to)) = (nodes.get(&from), nodes.get(&to)) else {
+                        summary.skipped += 1;
+                        continue;
+                    };
+                    self.import_edge(session, &label, from, to, &properties, &mut summary)
+                        .with_context(|| format!("Failed to import {} edge on line {}", label, index + 1))?;
+                }
+            }
+        }
+
+        if !header_seen {
+            anyhow::bail!("Import file is empty");
+        }
+        Ok(summary)
+    }
+
+    /// Find or create one node, returning how to match it, or `None` for
+    /// labels that are not part of the memory graph
+    fn import_node(
+        &self,
+        session: &Session,
+        label: &str,
+        properties: &Properties,
+        summary: &mut ImportSummary,
+    ) -> Result<Option<NodeKey>> {
+        let Some(mut identity) = identity(label, properties) else {
+            return Ok(None);
+        };
+        if let Some(kind @ (EntityKind::Person | EntityKind::Topic)) = EntityKind::from_label(label) {
+            if let Some(name) = self.resolve_entity_name(session, kind, &identity[0].1)? {
+                identity[0].1 = name;
+            }
+        }
+        let key = NodeKey {
+            label: label.to_string(),
+            identity,
+        };
+        let properties = query_params(properties)?;
+
+        let (pattern, params) = key.pattern("n");
+        let existing = bind_all(Query::new(format!("MATCH {} RETURN n", pattern)), &params).fetch(session)?;
+        let existing = existing.rows.iter().find_map(|row| match row.get_value("n") {
+            Some(Value::Node(node)) => Some(node),
+            _ => None,
+        });
+
+        match existing {
+            Some(node) => {
+                let missing: Vec<_> = properties
+                    .into_iter()
+                    .filter(|(name, _)| !node.properties.contains_key(name.as_str()))
+                    .collect();
+                if !missing.is_empty() {
+                    let assignments = missing
+                        .iter()
+                        .map(|(name, _)| format!("n.{} = $p_{}", name, name))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    let query = Query::new(format!("MATCH {} SET {}", pattern, assignments));
+                    missing
+                        .into_iter()
+                        .fold(bind_all(query, &params), |query, (name, value)| {
+                            query.bind(&format!("p_{}", name), value)
+                        })
+                        .execute(session)?;
+                }
+                summary.nodes_merged += 1;
+            }
+            None => {
+                Query::insert_node(
+                    label,
+                    properties.iter().map(|(name, value)| (name.as_str(), value.clone())).collect(),
+                )
+                .execute(session)?;
+                summary.nodes_created += 1;
+            }
+        }
+
+        Ok(Some(key))
+    }
+
+    /// Create an edge unless an identical one exists
+    fn import_edge(
+        &self,
+        session: &Session,
+        label: &str,
+        from: &NodeKey,
+        to: &NodeKey,
+        properties: &Properties,
+        summary: &mut ImportSummary,
+    ) -> Result<()> {
+        if normalize_relation_type(label).as_deref() != Some(label) {
+            anyhow::bail!("Invalid edge label: {}", label);
+        }
+        let (from_pattern, from_params) = from.pattern("a");
+        let (to_pattern, to_params) = to.pattern("b");
+        let params: Vec<_> = from_params.into_iter().chain(to_params).collect();
+
+        let existing = bind_all(
+            Query::new(format!("MATCH {}-[r:{}]->{} RETURN r", from_pattern, label, to_pattern)),
+            &params,
+        )
+        .fetch(session)?;
+        let exists = existing.rows.iter().any(|row| match row.get_value("r") {
+            Some(Value::Edge(edge)) => json_properties(&edge.properties) == *properties,
+            _ => false,
+        });
+        if exists {
+            summary.edges_existing += 1;
+            return Ok(());
+        }
+
+        let values = query_params(properties)?;
+        let edge_properties = if values.is_empty() {
+            String::new()
+        } else {
+            let placeholders = values
+                .iter()
+                .map(|(name, _)| format!("`{}`: $p_{}", name, name))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!(" {{{}}}", placeholders)
+        };
+        let query = Query::new(format!(
+            "MATCH {}, {} INSERT (a)-[:{}{}]->(b)",
+            from_pattern, to_pattern, label, edge_properties
+        ));
+        values
+            .into_iter()
+            .fold(bind_all(query, &params), |query, (name, value)| {
+                query.bind(&format!("p_{}", name), value)
+            })
+            .execute(session)?;
+        summary.edges_created += 1;
+
+        Ok(())
+    }
+}
+
+/// Check that the first record is a header for a format version this build reads
+fn check_header(record: &ExportRecord) -> Result<()> {
+    match record {
+        ExportRecord::Header { format, version, .. } if format == EXPORT_FORMAT => {
+            if *version > EXPORT_VERSION {
+                anyhow::bail!(
+                    "Export format version {} is newer than this build supports ({}); upgrade to import it",
+                    version,
+                    EXPORT_VERSION
+                );
+            }
+            Ok(())
+        }
+        _ => anyhow::bail!("Not an {} export: the first line must be its header", EXPORT_FORMAT),
+    }
+}
+
+/// Properties that identify a node with the given label, or `None` for
+/// labels outside the memory graph
+///
+/// Mirrors how nodes are deduplicated when they are created: conversations,
+/// messages and tasks by `id`, people and topics by name, documents by URL or
+/// else title, aliases by kind and normalized key.
+fn identity(label: &str, properties: &Properties) -> Option<Vec<(&'static str, String)>> {
+    let text = |property: &'static str| {
+        properties
+            .get(property)?
+            .as_str()
+            .filter(|value| !value.is_empty())
+            .map(|value| (property, value.to_string()))
+    };
+
+    match label {
+        "Conversation" | "Message" | "Task" => Some(vec![text("id")?]),
+        "Person" | "Topic" => Some(vec![text("name")?]),
+        "Document" => Some(vec![text("url").or_else(|| text("title"))?]),
+        "Alias" => Some(vec![text("kind")?, text("key")?]),
+        _ => None,
+    }
+}
+
+/// Stable id of a node, e.g. `person:Alice` or `alias:Person:al`
+fn stable_id(label: &str, properties: &Properties) -> Option<String> {
+    let values: Vec<String> = identity(label, properties)?
+        .into_iter()
+        .map(|(_, value)| value)
+        .collect();
+    Some(format!("{}:{}", label.to_lowercase(), values.join(":")))
+}
+
+/// Node or edge properties as JSON, skipping values with no JSON form
+fn json_properties(properties: &HashMap<String, Value>) -> Properties {
+    properties
+        .iter()
+        .filter_map(|(name, value)| {
+            let value = match value {
+                Value::String(s) => unescape_string(s).into(),
+                Value::Number(n) if n.fract() == 0.0 => (*n as i64).into(),
+                Value::Number(n) => serde_json::Number::from_f64(*n)?.into(),
+                Value::Boolean(b) => (*b).into(),
+                _ => return None,
+            };
+            Some((name.clone(), value))
+        })
+        .collect()
+}
+
+/// Validate imported properties and convert them to query parameters
+fn query_params(properties: &Properties) -> Result<Vec<(String, QueryParam)>> {
+    properties
+        .iter()
+        .map(|(name, value)| {
+            let mut chars = name.chars();
+            let valid_name = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid_name {
+                anyhow::bail!("Invalid property name: {}", name);
+            }
+            let param = match value {
+                serde_json::Value::String(s) => QueryParam::from(s),
+                serde_json::Value::Number(n) => n
+                    .as_i64()
+                    .map(QueryParam::from)
+                    .with_context(|| format!("Unsupported number for property {}: {}", name, n))?,
+                other => anyhow::bail!("Unsupported value for property {}: {}", name, other),
+            };
+            Ok((name.clone(), param))
+        })
+        .collect()
+}
+
+/// Bind `(name, value)` parameters produced by [`NodeKey::pattern`]
+fn bind_all(query: Query, params: &[(String, String)]) -> Query {
+    params
+        .iter()
+        .fold(query, |query, (name, value)| query.bind(name, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn properties(value: serde_json::Value) -> Properties {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_stable_ids() {
+        assert_eq!(
+            stable_id("Message", &properties(json!({"id": "m-1", "role": "user"}))).as_deref(),
+            Some("message:m-1")
+        );
+        assert_eq!(stable_id("Person", &properties(json!({"name": "Alice"}))).as_deref(), Some("person:Alice"));
+        assert_eq!(
+            stable_id("Document", &properties(json!({"title": "Roadmap", "url": "https://x.test/r"}))).as_deref(),
+            Some("document:https://x.test/r")
+        );
+        assert_eq!(
+            stable_id("Document", &properties(json!({"title": "Roadmap"}))).as_deref(),
+            Some("document:Roadmap")
+        );
+        assert_eq!(
+            stable_id("Alias", &properties(json!({"kind": "Person", "key": "al", "canonical": "Alice"}))).as_deref(),
+            Some("alias:Person:al")
+        );
+
+        // Unknown labels and nodes missing their identifying property are not exported
+        assert_eq!(stable_id("SchemaVersion", &properties(json!({"id": "x"}))), None);
+        assert_eq!(stable_id("Task", &properties(json!({"description": "Ship it"}))), None);
+    }
+
+    #[test]
+    fn test_record_format() {
+        let record = ExportRecord::Edge {
+            label: "MENTIONED_IN".to_string(),
+            from: "person:Alice".to_string(),
+            to: "message:m-1".to_string(),
+            properties: Properties::new(),
+        };
+        let line = serde_json::to_string(&record).unwrap();
+        assert_eq!(
+            line,
+            r#"{"type":"edge","label":"MENTIONED_IN","from":"person:Alice","to":"message:m-1","properties":{}}"#
+        );
+        assert_eq!(serde_json::from_str::<ExportRecord>(&line).unwrap(), record);
+    }
+
+    #[test]
+    fn test_check_header() {
+        let header = |format: &str, version: u32| ExportRecord::Header {
+            format: format.to_string(),
+            version,
+            exported_at: "2024-06-01T00:00:00+00:00".to_string(),
+        };
+
+        assert!(check_header(&header(EXPORT_FORMAT, EXPORT_VERSION)).is_ok());
+        assert!(check_header(&header(EXPORT_FORMAT, EXPORT_VERSION + 1))
+            .unwrap_err()
+            .to_string()
+            .contains("newer"));
+        assert!(check_header(&header("something-else", 1)).is_err());
+        assert!(check_header(&ExportRecord::Node {
+            id: "person:Alice".to_string(),
+            label: "Person".to_string(),
+            properties: Properties::new(),
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_json_properties_unescapes_strings() {
+        let stored = HashMap::from([
+            ("content".to_string(), Value::String("it\\'s\\nfine".to_string())),
+            ("count".to_string(), Value::Number(3.0)),
+            ("created".to_string(), Value::Null),
+        ]);
+
+        assert_eq!(json_properties(&stored), properties(json!({"content": "it's\nfine", "count": 3})));
+    }
+
+    #[test]
+    fn test_query_params_rejects_unsafe_input() {
+        let params = query_params(&properties(json!({"name": "Alice", "rank": 2}))).unwrap();
+        assert_eq!(
+            params,
+            vec![
+                ("name".to_string(), QueryParam::String("Alice".to_string())),
+                ("rank".to_string(), QueryParam::Integer(2)),
+            ]
+        );
+
+        assert!(query_params(&properties(json!({"name`}) DETACH DELETE n //": "x"}))).is_err());
+        assert!(query_params(&properties(json!({"tags": ["a"]}))).is_err());
+    }
+}
@@ -0,0 +1,240 @@
+//! Graph setup, indexes and schema migrations
+//!
+//! The database records the layout version it was last migrated to in a
+//! single `SchemaVersion` node. Databases written before versioning have no
+//! such node and count as version 0.
+
+use anyhow::{Context, Result};
+use graphlite_sdk::{Session, Value};
+use std::collections::{HashMap, HashSet};
+
+use super::query::{number_column, unescape_string, Query};
+use super::schema::{new_id, now};
+
+/// Schema holding the memory graph
+pub const MEMORY_SCHEMA: &str = "/memory";
+
+/// Graph every session works in
+pub const MEMORY_GRAPH: &str = "/memory/context";
+
+/// Indexes as (name, label, property)
+///
+/// GraphLite has no uniqueness constraints, so these keys are kept unique
+/// by checking for an existing node before inserting. Its indexes are not
+/// persisted either, so they are created each time the database is opened.
+const INDEXES: &[(&str, &str, &str)] = &[
+    ("conversation_id", "Conversation", "id"),
+    ("message_id", "Message", "id"),
+    ("person_name", "Person", "name"),
+    ("topic_name", "Topic", "name"),
+];
+
+/// One step in the schema history
+struct Migration {
+    version: u32,
+    description: &'static str,
+    apply: fn(&Session) -> Result<()>,
+}
+
+/// All migrations, oldest first
+const MIGRATIONS: &[Migration] = &[Migration {
+    version: 1,
+    description: "give tasks created without an id a UUID",
+    apply: add_task_ids,
+}];
+
+/// Schema version written by this build
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Point a session at the memory graph, creating the graph on first use
+pub fn use_memory_graph(session: &Session) -> Result<()> {
+    let set_graph = Query::new(format!("SESSION SET GRAPH {}", MEMORY_GRAPH));
+    if set_graph.execute(session).is_ok() {
+        return Ok(());
+    }
+
+    // CREATE GRAPH IF NOT EXISTS still fails for an existing graph, so the
+    // graph is only created once setting it has failed
+    Query::new(format!("CREATE SCHEMA IF NOT EXISTS {}", MEMORY_SCHEMA))
+        .execute(session)
+        .context("Failed to create the memory schema")?;
+    Query::new(format!("CREATE GRAPH IF NOT EXISTS {}", MEMORY_GRAPH))
+        .execute(session)
+        .context("Failed to create the memory graph")?;
+    set_graph
+        .execute(session)
+        .context("Failed to select the memory graph")
+}
+
+/// Create the lookup indexes if they do not exist yet
+///
+/// GraphLite refuses to build an index on a thread that belongs to a tokio
+/// runtime, so the statements run on a separate plain thread.
+pub fn ensure_indexes(session: &Session) -> Result<()> {
+    std::thread::scope(|scope| {
+        scope
+            .spawn(|| {
+                for (name, label, property) in INDEXES {
+                    Query::new(format!("CREATE INDEX IF NOT EXISTS {} ON {} ({})", name, label, property))
+                        .execute(session)
+                        .with_context(|| format!("Failed to create index {}", name))?;
+                }
+                Ok(())
+            })
+            .join()
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("Index creation panicked")))
+    })
+}
+
+/// Schema version recorded in the database (0 if none is recorded)
+pub fn schema_version(session: &Session) -> Result<u32> {
+    let result = Query::new("MATCH (v:SchemaVersion) RETURN v.version")
+        .fetch(session)
+        .context("Failed to read the schema version")?;
+
+    Ok(result
+        .rows
+        .iter()
+        .filter_map(|row| number_column(row, "v.version"))
+        .fold(0, |version, stored| version.max(stored as u32)))
+}
+
+/// Bring the database up to [`SCHEMA_VERSION`], applying each pending
+/// migration in order and recording the version after each one
+///
+/// Fails without changing anything if the database was written by a newer
+/// build. Returns the descriptions of the migrations applied.
+pub fn migrate(session: &Session) -> Result<Vec<&'static str>> {
+    let current = schema_version(session)?;
+    check_version(current)?;
+
+    let mut applied = Vec::new();
+    for migration in pending_migrations(current) {
+        (migration.apply)(session).with_context(|| {
+            format!(
+                "Schema migration {} ({}) failed",
+                migration.version, migration.description
+            )
+        })?;
+        set_schema_version(session, migration.version)?;
+        applied.push(migration.description);
+    }
+    Ok(applied)
+}
+
+/// Refuse databases whose schema is newer than this build understands
+fn check_version(stored: u32) -> Result<()> {
+    if stored > SCHEMA_VERSION {
+        anyhow::bail!(
+            "Database schema version {} is newer than this build supports ({}); upgrade agentic-memory to open it",
+            stored,
+            SCHEMA_VERSION
+        );
+    }
+    Ok(())
+}
+
+/// Migrations after `stored`, in order
+fn pending_migrations(stored: u32) -> impl Iterator<Item = &'static Migration> {
+    MIGRATIONS.iter().filter(move |migration| migration.version > stored)
+}
+
+/// Replace the recorded schema version
+fn set_schema_version(session: &Session, version: u32) -> Result<()> {
+    Query::new("MATCH (v:SchemaVersion) DELETE v")
+        .execute(session)
+        .context("Failed to clear the schema version")?;
+    Query::insert_node(
+        "SchemaVersion",
+        vec![
+            ("version", (version as i64).into()),
+            ("updated_at", now().to_rfc3339().into()),
+        ],
+    )
+    .execute(session)
+    .context("Failed to record the schema version")
+}
+
+/// Migration 1: tasks used to be stored without an `id`, which task
+/// commands, assignment and export now rely on
+///
+/// GraphLite ignores `WHERE` on `MATCH ... SET`, so tasks are matched by
+/// description and creation time, and tasks that cannot be told apart from
+/// one that already has an id are left alone rather than overwriting it.
+fn add_task_ids(session: &Session) -> Result<()> {
+    let result = Query::new("MATCH (t:Task) RETURN t")
+        .fetch(session)
+        .context("Failed to read tasks")?;
+
+    let text = |properties: &HashMap<String, Value>, name: &str| match properties.get(name) {
+        Some(Value::String(value)) => Some(unescape_string(value)),
+        _ => None,
+    };
+    let mut with_id = HashSet::new();
+    let mut without_id = Vec::new();
+    for row in &result.rows {
+        let Some(Value::Node(node)) = row.get_value("t") else {
+            continue;
+        };
+        let Some(description) = text(&node.properties, "description") else {
+            continue;
+        };
+        let key = (description, text(&node.properties, "created_at"));
+        if node.properties.contains_key("id") {
+            with_id.insert(key);
+        } else if !without_id.contains(&key) {
+            without_id.push(key);
+        }
+    }
+
+    for (description, created_at) in without_id {
+        // Without a creation time the match is by description alone
+        let taken = match &created_at {
+            Some(_) => with_id.contains(&(description.clone(), created_at.clone())),
+            None => with_id.iter().any(|(other, _)| *other == description),
+        };
+        if taken {
+            continue;
+        }
+        let query = match created_at {
+            Some(created_at) => {
+                Query::new("MATCH (t:Task {`description`: $description, `created_at`: $created_at}) SET t.id = $id")
+                    .bind("created_at", created_at)
+            }
+            None => Query::new("MATCH (t:Task {`description`: $description}) SET t.id = $id"),
+        };
+        query
+            .bind("description", description)
+            .bind("id", new_id())
+            .execute(session)
+            .context("Failed to assign a task id")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrations_are_ordered() {
+        let versions: Vec<u32> = MIGRATIONS.iter().map(|migration| migration.version).collect();
+        assert!(versions.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(versions.first(), Some(&1));
+        assert_eq!(versions.last(), Some(&SCHEMA_VERSION));
+    }
+
+    #[test]
+    fn test_pending_migrations() {
+        assert_eq!(pending_migrations(0).count(), MIGRATIONS.len());
+        assert_eq!(pending_migrations(SCHEMA_VERSION).count(), 0);
+    }
+
+    #[test]
+    fn test_check_version() {
+        assert!(check_version(0).is_ok());
+        assert!(check_version(SCHEMA_VERSION).is_ok());
+        let error = check_version(SCHEMA_VERSION + 1).unwrap_err().to_string();
+        assert!(error.contains("newer than this build"), "{}", error);
+    }
+}
@@ -1,5 +1,13 @@
 pub mod schema;
 pub mod operations;
+pub mod query;
+pub mod export;
+pub mod migrations;
+pub mod visualize;
 
 pub use schema::*;
 pub use operations::*;
+pub use query::*;
+pub use export::*;
+pub use migrations::*;
+pub use visualize::*;
@@ -1,13 +1,21 @@
 use anyhow::{Context, Result};
-use graphlite_sdk::{GraphLite, Session, Value};
-use std::path::Path;
-use super::schema::{Conversation, Message, Person, Topic, ExtractedEntities, new_id, now};
+use graphlite_sdk::{GraphLite, Session};
+use super::migrations::{ensure_indexes, migrate, use_memory_graph};
+use super::query::{count_column, string_column, Query, QueryParam};
+use super::schema::{
+    CoMention, Conversation, ConversationSummary, Document, EntityAlias, EntityKind, EntityMention,
+    EntityRef, ExtractedEntities, ExtractedRelation, MergeReport, RelatedEntity, Task, TaskStatus,
+    new_id, normalize_entity_name, normalize_relation_type, now, parse_timestamp,
+};
+use chrono::NaiveDate;
+use std::collections::{BTreeSet, HashMap};
 
 /// Configuration for different entity types
 enum EntityConfig<'a> {
     Person { name: &'a str },
     Topic { name: &'a str },
-    Task { description: &'a str },
+    Task { id: &'a str, description: &'a str },
+    Document { title: &'a str, url: Option<&'a str>, doc_type: &'a str },
 }
 
 impl<'a> EntityConfig<'a> {
@@ -17,40 +25,117 @@ impl<'a> EntityConfig<'a> {
             EntityConfig::Person { .. } => "Person",
             EntityConfig::Topic { .. } => "Topic",
             EntityConfig::Task { .. } => "Task",
+            EntityConfig::Document { .. } => "Document",
         }
     }
 
     /// Get the property name used as the identifier
+    ///
+    /// Documents are identified by URL when they have one, otherwise by title.
     fn id_property(&self) -> &'static str {
         match self {
             EntityConfig::Person { .. } | EntityConfig::Topic { .. } => "name",
             EntityConfig::Task { .. } => "description",
+            EntityConfig::Document { url: Some(_), .. } => "url",
+            EntityConfig::Document { url: None, .. } => "title",
         }
     }
 
-    /// Get the escaped identifier value
-    fn id_value(&self) -> String {
-        let raw_value = match self {
+    /// Get the raw identifier value (escaped when bound to a query)
+    fn id_value(&self) -> &'a str {
+        match self {
             EntityConfig::Person { name } => name,
             EntityConfig::Topic { name } => name,
-            EntityConfig::Task { description } => description,
-        };
-        GraphDB::escape_string(raw_value)
+            EntityConfig::Task { description, .. } => description,
+            EntityConfig::Document { url: Some(url), .. } => url,
+            EntityConfig::Document { title, url: None, .. } => title,
+        }
+    }
+
+    /// Property and value that identify this particular node when linking
+    ///
+    /// Deduplicated entities are matched by their identifier; Tasks are never
+    /// deduplicated, so each one is matched by its own `id`.
+    fn match_key(&self) -> (&'static str, &'a str) {
+        match self {
+            EntityConfig::Task { id, .. } => ("id", id),
+            _ => (self.id_property(), self.id_value()),
+        }
     }
 
     /// Whether this entity type should be deduplicated
     fn should_deduplicate(&self) -> bool {
-        matches!(self, EntityConfig::Person { .. } | EntityConfig::Topic { .. })
+        matches!(
+            self,
+            EntityConfig::Person { .. } | EntityConfig::Topic { .. } | EntityConfig::Document { .. }
+        )
     }
 
     /// Additional properties for INSERT query (empty for Person/Topic)
-    fn additional_properties(&self) -> Option<String> {
+    fn additional_properties(&self) -> Vec<(&'static str, QueryParam)> {
         match self {
-            EntityConfig::Task { .. } => Some(format!(
-                ", status: 'pending', created_at: '{}'",
-                now().to_rfc3339()
-            )),
-            _ => None,
+            EntityConfig::Task { id, .. } => vec![
+                ("id", (*id).into()),
+                ("status", TaskStatus::Pending.as_str().into()),
+                ("created_at", now().to_rfc3339().into()),
+            ],
+            EntityConfig::Document { title, url: Some(_), doc_type } => vec![
+                ("title", (*title).into()),
+                ("doc_type", (*doc_type).into()),
+            ],
+            EntityConfig::Document { url: None, doc_type, .. } => {
+                vec![("doc_type", (*doc_type).into())]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// All properties for the INSERT query, identifier first
+    fn insert_properties(&self) -> Vec<(&'static str, QueryParam)> {
+        let mut properties = vec![(self.id_property(), self.id_value().into())];
+        properties.extend(self.additional_properties());
+        properties
+    }
+}
+
+/// `RETURN` columns giving the kind (`entity_kind`) and display name
+/// (`entity_name`) of an entity bound to `e`
+const ENTITY_COLUMNS: &str = "CASE \
+       WHEN e:Person THEN 'Person' \
+       WHEN e:Topic THEN 'Topic' \
+       WHEN e:Task THEN 'Task' \
+       WHEN e:Document THEN 'Document' \
+     END AS entity_kind, \
+     CASE \
+       WHEN e:Person THEN e.name \
+       WHEN e:Topic THEN e.name \
+       WHEN e:Task THEN e.description \
+       WHEN e:Document THEN e.title \
+     END AS entity_name";
+
+/// Read the entity described by [`ENTITY_COLUMNS`] from a result row
+fn entity_ref_column(row: &graphlite_sdk::Row) -> Option<EntityRef> {
+    Some(EntityRef::new(
+        EntityKind::from_label(&string_column(row, "entity_kind")?)?,
+        string_column(row, "entity_name")?,
+    ))
+}
+
+/// Node linked to a message, identified so it can be matched again
+#[derive(Debug, Clone, PartialEq)]
+struct LinkedEntity {
+    label: &'static str,
+    property: &'static str,
+    value: String,
+}
+
+impl From<&EntityConfig<'_>> for LinkedEntity {
+    fn from(config: &EntityConfig<'_>) -> Self {
+        let (property, value) = config.match_key();
+        Self {
+            label: config.label(),
+            property,
+            value: value.to_string(),
         }
     }
 }
@@ -62,18 +147,29 @@ pub struct GraphDB {
 
 impl GraphDB {
     /// Initialize a new GraphLite database
-    pub async fn new(db_path: &str, _admin_user: &str, _admin_password: &str) -> Result<Self> {
+    ///
+    /// Creates the memory graph and its indexes if needed and applies any
+    /// pending schema migrations. Fails if the database was written by a
+    /// newer version with a schema this build does not know.
+    pub async fn new(db_path: &str, admin_user: &str, admin_password: &str) -> Result<Self> {
         // Open the database (creates it if it doesn't exist)
         let db = GraphLite::open(db_path)
             .context("Failed to open GraphLite database")?;
+        let graph_db = Self { db };
+
+        let session = graph_db.session(admin_user, admin_password)?;
+        ensure_indexes(&session)?;
+        migrate(&session).context("Failed to migrate the database schema")?;
 
-        Ok(Self { db })
+        Ok(graph_db)
     }
 
-    /// Create a new session
+    /// Create a new session working in the memory graph
     pub fn session(&self, username: &str, _password: &str) -> Result<Session> {
-        self.db.session(username)
-            .context("Failed to create database session")
+        let session = self.db.session(username)
+            .context("Failed to create database session")?;
+        use_memory_graph(&session)?;
+        Ok(session)
     }
 
     /// Start a new conversation
@@ -81,19 +177,71 @@ impl GraphDB {
         let conv_id = new_id();
         let timestamp = now();
 
-        let query = format!(
-            "INSERT (:Conversation {{id: '{}', started_at: '{}', title: '{}'}})",
-            conv_id,
-            timestamp.to_rfc3339(),
-            title.unwrap_or_else(|| "New Conversation".to_string())
-        );
-
-        session.execute(&query)
+        Query::new("INSERT (:Conversation {id: $id, started_at: $started_at, title: $title})")
+            .bind("id", &conv_id)
+            .bind("started_at", timestamp.to_rfc3339())
+            .bind("title", title.unwrap_or_else(|| "New Conversation".to_string()))
+            .execute(session)
             .context("Failed to create conversation node")?;
 
         Ok(conv_id)
     }
 
+    /// Look up a conversation by id
+    ///
+    /// # Returns
+    /// * `Ok(Some(Conversation))` - The conversation node
+    /// * `Ok(None)` - No conversation with this id exists
+    /// * `Err(_)` - Query execution error
+    pub fn get_conversation(
+        &self,
+        session: &Session,
+        conversation_id: &str,
+    ) -> Result<Option<Conversation>> {
+        let result = Query::new(
+            "MATCH (c:Conversation {id: $conversation_id}) \
+             RETURN c.id, c.started_at, c.title",
+        )
+        .bind("conversation_id", conversation_id)
+        .fetch(session)?;
+
+        Ok(result.rows.first().and_then(|row| {
+            Some(Conversation {
+                id: string_column(row, "c.id")?,
+                started_at: parse_timestamp(&string_column(row, "c.started_at")?)?,
+                title: string_column(row, "c.title"),
+            })
+        }))
+    }
+
+    /// Find the id of the conversation with the most recent activity
+    ///
+    /// Conversations are ranked by their newest message; if no conversation
+    /// has messages yet, the most recently started one is returned.
+    pub fn latest_conversation_id(&self, session: &Session) -> Result<Option<String>> {
+        let result = Query::new(
+            "MATCH (m:Message)-[:PART_OF]->(c:Conversation) \
+             RETURN c.id \
+             ORDER BY m.timestamp DESC \
+             LIMIT 1",
+        )
+        .fetch(session)?;
+
+        if let Some(id) = result.rows.first().and_then(|row| string_column(row, "c.id")) {
+            return Ok(Some(id));
+        }
+
+        let result = Query::new(
+            "MATCH (c:Conversation) \
+             RETURN c.id \
+             ORDER BY c.started_at DESC \
+             LIMIT 1",
+        )
+        .fetch(session)?;
+
+        Ok(result.rows.first().and_then(|row| string_column(row, "c.id")))
+    }
+
     /// Add a message to a conversation with entity extraction
     pub fn add_message(
         &self,
@@ -107,22 +255,21 @@ impl GraphDB {
         let timestamp = now();
 
         // Insert the message node
-        let query = format!(
-            "INSERT (:Message {{id: '{}', role: '{}', content: '{}', timestamp: '{}'}})",
-            msg_id,
-            role,
-            Self::escape_string(content),
-            timestamp.to_rfc3339()
-        );
-        session.execute(&query)?;
+        Query::new("INSERT (:Message {id: $id, role: $role, content: $content, timestamp: $timestamp})")
+            .bind("id", &msg_id)
+            .bind("role", role)
+            .bind("content", content)
+            .bind("timestamp", timestamp.to_rfc3339())
+            .execute(session)?;
 
         // Link message to conversation
-        let link_query = format!(
-            "MATCH (c:Conversation {{id: '{}'}}), (m:Message {{id: '{}'}}) \
+        Query::new(
+            "MATCH (c:Conversation {id: $conversation_id}), (m:Message {id: $message_id}) \
              INSERT (m)-[:PART_OF]->(c)",
-            conversation_id, msg_id
-        );
-        session.execute(&link_query)?;
+        )
+        .bind("conversation_id", conversation_id)
+        .bind("message_id", &msg_id)
+        .execute(session)?;
 
         // Create entity nodes and relationships
         self.link_entities(session, &msg_id, entities)?;
@@ -136,82 +283,155 @@ impl GraphDB {
         session: &Session,
         message_id: &str,
         config: EntityConfig,
-    ) -> Result<()> {
+    ) -> Result<LinkedEntity> {
         let label = config.label();
-        let id_prop = config.id_property();
-        let id_value = config.id_value();
-
-        // Create entity node if needed
-        if config.should_deduplicate() {
-            // Use OPTIONAL MATCH pattern for deduplication
-            let insert_query = format!(
-                "OPTIONAL MATCH (e:{} {{{}: '{}'}}) \
-                 WITH e \
-                 WHERE e IS NULL \
-                 INSERT (:{} {{{}: '{}'{}}})",
-                label, id_prop, id_value,
-                label, id_prop, id_value,
-                config.additional_properties().unwrap_or_default()
-            );
-            let _ = session.execute(&insert_query); // May already exist
-        } else {
-            // Always create new entity (for Tasks)
-            let insert_query = format!(
-                "INSERT (:{} {{{}: '{}'{}}})",
-                label, id_prop, id_value,
-                config.additional_properties().unwrap_or_default()
-            );
-            session.execute(&insert_query)?;
+        let (match_prop, match_value) = config.match_key();
+
+        // Create entity node if needed (Tasks are always created anew)
+        if !config.should_deduplicate() || !self.entity_exists(session, &config)? {
+            Query::insert_node(label, config.insert_properties())
+                .execute(session)?;
         }
 
         // Link entity to message
-        let link_query = format!(
-            "MATCH (e:{} {{{}: '{}'}}), (m:Message {{id: '{}'}}) \
+        Query::new(format!(
+            "MATCH (e:{} {{`{}`: $value}}), (m:Message {{id: $message_id}}) \
              INSERT (e)-[:MENTIONED_IN]->(m)",
-            label, id_prop, id_value, message_id
-        );
-        session.execute(&link_query)?;
+            label, match_prop
+        ))
+        .bind("value", match_value)
+        .bind("message_id", message_id)
+        .execute(session)?;
 
-        Ok(())
+        Ok(LinkedEntity::from(&config))
     }
 
-    /// Create entity nodes and link them to a message
+    /// Check whether a node with the entity's identifier already exists
+    fn entity_exists(&self, session: &Session, config: &EntityConfig) -> Result<bool> {
+        let result = Query::new(format!(
+            "MATCH (e:{} {{`{}`: $value}}) RETURN count(e)",
+            config.label(),
+            config.id_property()
+        ))
+        .bind("value", config.id_value())
+        .fetch(session)?;
+
+        Ok(result
+            .rows
+            .first()
+            .and_then(count_column)
+            .is_some_and(|count| count > 0.0))
+    }
+
+    /// Create entity nodes and link them to a message, then add the typed
+    /// relationships between them
     fn link_entities(
         &self,
         session: &Session,
         message_id: &str,
         entities: &ExtractedEntities,
     ) -> Result<()> {
-        // Link all people
+        // Entities linked to this message, keyed by how relations refer to them
+        let mut linked: HashMap<String, LinkedEntity> = HashMap::new();
+
+        // Link all people, reusing the node an equivalent name or alias refers to
         for person_name in &entities.people {
-            self.link_entity(
+            let name = self
+                .resolve_entity_name(session, EntityKind::Person, person_name)?
+                .unwrap_or_else(|| person_name.clone());
+            let entity = self.link_entity(
                 session,
                 message_id,
-                EntityConfig::Person { name: person_name },
+                EntityConfig::Person { name: &name },
             )?;
+            linked.insert(relation_key(person_name), entity);
         }
 
         // Link all topics
         for topic_name in &entities.topics {
-            self.link_entity(
+            let name = self
+                .resolve_entity_name(session, EntityKind::Topic, topic_name)?
+                .unwrap_or_else(|| topic_name.clone());
+            let entity = self.link_entity(
                 session,
                 message_id,
-                EntityConfig::Topic { name: topic_name },
+                EntityConfig::Topic { name: &name },
             )?;
+            linked.entry(relation_key(topic_name)).or_insert(entity);
         }
 
         // Link all tasks
         for task_desc in &entities.tasks {
-            self.link_entity(
+            let task_id = new_id();
+            let entity = self.link_entity(
+                session,
+                message_id,
+                EntityConfig::Task { id: &task_id, description: task_desc },
+            )?;
+            linked.entry(relation_key(task_desc)).or_insert(entity);
+        }
+
+        // Link all documents, filling in url/doc_type from the mention
+        for mention in &entities.documents {
+            let document = Document::from_mention(mention);
+            let entity = self.link_entity(
                 session,
                 message_id,
-                EntityConfig::Task { description: task_desc },
+                EntityConfig::Document {
+                    title: &document.title,
+                    url: document.url.as_deref(),
+                    doc_type: &document.doc_type,
+                },
             )?;
+            linked.entry(relation_key(&document.title)).or_insert_with(|| entity.clone());
+            linked.entry(relation_key(mention)).or_insert(entity);
+        }
+
+        for relation in &entities.relations {
+            self.link_relation(session, message_id, relation, &linked)?;
         }
 
         Ok(())
     }
 
+    /// Write a typed edge such as `(Alice)-[:WORKS_ON]->(GraphQL)`
+    ///
+    /// Both ends must be entities linked to the same message; relations that
+    /// refer to anything else, or whose type is not a valid edge label, are
+    /// skipped. The edge records the message it was extracted from.
+    fn link_relation(
+        &self,
+        session: &Session,
+        message_id: &str,
+        relation: &ExtractedRelation,
+        linked: &HashMap<String, LinkedEntity>,
+    ) -> Result<()> {
+        let (Some(subject), Some(object), Some(edge_label)) = (
+            linked.get(&relation_key(&relation.subject)),
+            linked.get(&relation_key(&relation.object)),
+            normalize_relation_type(&relation.predicate),
+        ) else {
+            return Ok(());
+        };
+        if subject == object {
+            return Ok(());
+        }
+
+        Query::new(format!(
+            "MATCH (s:{} {{`{}`: $subject}}), (o:{} {{`{}`: $object}}) \
+             INSERT (s)-[:{} {{message_id: $message_id, created_at: $created_at}}]->(o)",
+            subject.label, subject.property, object.label, object.property, edge_label
+        ))
+        .bind("subject", &subject.value)
+        .bind("object", &object.value)
+        .bind("message_id", message_id)
+        .bind("created_at", now().to_rfc3339())
+        .execute(session)
+        .with_context(|| format!("Failed to store {} relation", edge_label))?;
+
+        Ok(())
+    }
+
     /// Query recent messages from a conversation
     ///
     /// Returns messages as (role, content, timestamp) tuples ordered by timestamp DESC.
@@ -231,41 +451,658 @@ impl GraphDB {
         conversation_id: &str,
         limit: usize,
     ) -> Result<Vec<(String, String, String)>> {
-        let query = format!(
-            "MATCH (m:Message)-[:PART_OF]->(c:Conversation {{id: '{}'}}) \
+        let result = Query::new(
+            "MATCH (m:Message)-[:PART_OF]->(c:Conversation {id: $conversation_id}) \
              RETURN m.role, m.content, m.timestamp \
              ORDER BY m.timestamp DESC \
-             LIMIT {}",
-            conversation_id, limit
-        );
-
-        let result = session.query(&query)?;
+             LIMIT $limit",
+        )
+        .bind("conversation_id", conversation_id)
+        .bind("limit", limit)
+        .fetch(session)?;
 
         // Parse result rows into (role, content, timestamp) tuples
         let messages: Vec<(String, String, String)> = result
             .rows
             .iter()
             .filter_map(|row| {
-                match (
-                    row.get_value("m.role"),
-                    row.get_value("m.content"),
-                    row.get_value("m.timestamp"),
-                ) {
-                    (Some(Value::String(role)), Some(Value::String(content)), Some(Value::String(ts))) => {
-                        Some((role.clone(), content.clone(), ts.clone()))
-                    }
-                    _ => None, // Skip rows with missing or wrong-typed values
-                }
+                // Skip rows with missing or wrong-typed values
+                Some((
+                    string_column(row, "m.role")?,
+                    string_column(row, "m.content")?,
+                    string_column(row, "m.timestamp")?,
+                ))
+            })
+            .collect();
+
+        Ok(messages)
+    }
+
+    /// Query every message of a conversation in chronological order
+    ///
+    /// Returns messages as (role, content, timestamp) tuples ordered by timestamp ASC.
+    pub fn get_conversation_transcript(
+        &self,
+        session: &Session,
+        conversation_id: &str,
+    ) -> Result<Vec<(String, String, String)>> {
+        let result = Query::new(
+            "MATCH (m:Message)-[:PART_OF]->(c:Conversation {id: $conversation_id}) \
+             RETURN m.role, m.content, m.timestamp \
+             ORDER BY m.timestamp ASC",
+        )
+        .bind("conversation_id", conversation_id)
+        .fetch(session)?;
+
+        let mut messages: Vec<(String, String, String)> = result
+            .rows
+            .iter()
+            .filter_map(|row| {
+                Some((
+                    string_column(row, "m.role")?,
+                    string_column(row, "m.content")?,
+                    string_column(row, "m.timestamp")?,
+                ))
             })
             .collect();
 
+        // RFC 3339 timestamps sort lexicographically; don't rely on the engine's ORDER BY alone
+        messages.sort_by(|a, b| a.2.cmp(&b.2));
+
         Ok(messages)
     }
 
+    /// List conversations, most recently active first
+    ///
+    /// Message counts and last activity are aggregated here rather than in GQL,
+    /// since GraphLite's `max()` does not support string timestamps.
+    ///
+    /// # Arguments
+    /// * `session` - Active GraphLite session
+    /// * `offset` - Number of conversations to skip (for paging)
+    /// * `limit` - Maximum number of conversations to return
+    pub fn list_conversations(
+        &self,
+        session: &Session,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<ConversationSummary>> {
+        let conversations = Query::new("MATCH (c:Conversation) RETURN c.id, c.started_at, c.title")
+            .fetch(session)
+            .context("Failed to query conversations")?;
+
+        let conversations: Vec<Conversation> = conversations
+            .rows
+            .iter()
+            .filter_map(|row| {
+                Some(Conversation {
+                    id: string_column(row, "c.id")?,
+                    started_at: parse_timestamp(&string_column(row, "c.started_at")?)?,
+                    title: string_column(row, "c.title"),
+                })
+            })
+            .collect();
+
+        let messages = Query::new(
+            "MATCH (m:Message)-[:PART_OF]->(c:Conversation) \
+             RETURN c.id, m.timestamp",
+        )
+        .fetch(session)
+        .context("Failed to query conversation messages")?;
+
+        let message_times: Vec<(String, String)> = messages
+            .rows
+            .iter()
+            .filter_map(|row| Some((string_column(row, "c.id")?, string_column(row, "m.timestamp")?)))
+            .collect();
+
+        Ok(summarize_conversations(conversations, &message_times, offset, limit))
+    }
+
+    /// List tasks, optionally filtered by status
+    ///
+    /// Tasks are returned oldest first. Tasks without an `id` (created before
+    /// ids were assigned) are skipped.
+    pub fn list_tasks(&self, session: &Session, status: Option<TaskStatus>) -> Result<Vec<Task>> {
+        let result = Query::new(
+            "MATCH (t:Task) \
+             RETURN t.id, t.description, t.status, t.created_at, t.due_date",
+        )
+        .fetch(session)
+        .context("Failed to query tasks")?;
+
+        let assignees = self.task_assignees(session)?;
+
+        let mut tasks: Vec<Task> = result
+            .rows
+            .iter()
+            .filter_map(|row| {
+                let id = string_column(row, "t.id")?;
+                Some(Task {
+                    assignee: assignees.get(&id).cloned(),
+                    description: string_column(row, "t.description")?,
+                    status: TaskStatus::parse(&string_column(row, "t.status")?)?,
+                    created_at: parse_timestamp(&string_column(row, "t.created_at")?)?,
+                    due_date: string_column(row, "t.due_date")
+                        .and_then(|due| NaiveDate::parse_from_str(&due, "%Y-%m-%d").ok()),
+                    id,
+                })
+            })
+            .filter(|task| status.is_none_or(|status| task.status == status))
+            .collect();
+
+        tasks.sort_by_key(|task| task.created_at);
+        Ok(tasks)
+    }
+
+    /// List tasks that are pending or in progress
+    pub fn list_open_tasks(&self, session: &Session) -> Result<Vec<Task>> {
+        Ok(self
+            .list_tasks(session, None)?
+            .into_iter()
+            .filter(|task| task.status.is_open())
+            .collect())
+    }
+
+    /// Find a task by its full id or a unique id prefix
+    pub fn find_task(&self, session: &Session, id_or_prefix: &str) -> Result<Task> {
+        let tasks = self.list_tasks(session, None)?;
+
+        if let Some(task) = tasks.iter().find(|task| task.id == id_or_prefix) {
+            return Ok(task.clone());
+        }
+
+        let mut matches = tasks.into_iter().filter(|task| task.id.starts_with(id_or_prefix));
+        match (matches.next(), matches.next()) {
+            (Some(task), None) => Ok(task),
+            (Some(_), Some(_)) => anyhow::bail!("Task id prefix '{}' is ambiguous", id_or_prefix),
+            (None, _) => anyhow::bail!("Task not found: {}", id_or_prefix),
+        }
+    }
+
+    /// Fail with "Task not found" unless a task has exactly this id
+    fn ensure_task_exists(&self, session: &Session, task_id: &str) -> Result<()> {
+        let result = Query::new("MATCH (t:Task {id: $task_id}) RETURN count(t)")
+            .bind("task_id", task_id)
+            .fetch(session)
+            .context("Failed to look up task")?;
+
+        if !result.rows.first().and_then(count_column).is_some_and(|count| count > 0.0) {
+            anyhow::bail!("Task not found: {}", task_id);
+        }
+        Ok(())
+    }
+
+    /// Move a task to a new status
+    pub fn set_task_status(&self, session: &Session, task_id: &str, status: TaskStatus) -> Result<()> {
+        self.ensure_task_exists(session, task_id)?;
+        Query::new("MATCH (t:Task {id: $task_id}) SET t.status = $status, t.updated_at = $updated_at")
+            .bind("task_id", task_id)
+            .bind("status", status.as_str())
+            .bind("updated_at", now().to_rfc3339())
+            .execute(session)
+            .context("Failed to update task status")
+    }
+
+    /// Attach a due date to a task
+    pub fn set_task_due_date(&self, session: &Session, task_id: &str, due_date: NaiveDate) -> Result<()> {
+        self.ensure_task_exists(session, task_id)?;
+        Query::new("MATCH (t:Task {id: $task_id}) SET t.due_date = $due_date")
+            .bind("task_id", task_id)
+            .bind("due_date", due_date.format("%Y-%m-%d").to_string())
+            .execute(session)
+            .context("Failed to set task due date")
+    }
+
+    /// Assign a task to a person, replacing any previous assignee
+    ///
+    /// The name is resolved like an extracted one, so "alice" or an alias
+    /// assigns the task to the existing "Alice"; a `Person` node is only
+    /// created when no match exists.
+    pub fn assign_task(&self, session: &Session, task_id: &str, person_name: &str) -> Result<()> {
+        self.ensure_task_exists(session, task_id)?;
+        let person_name = match self.resolve_entity_name(session, EntityKind::Person, person_name)? {
+            Some(name) => name,
+            None => {
+                let person = EntityConfig::Person { name: person_name };
+                Query::insert_node(person.label(), person.insert_properties())
+                    .execute(session)?;
+                person_name.to_string()
+            }
+        };
+
+        Query::new("MATCH (t:Task {id: $task_id})-[r:ASSIGNED_TO]->(:Person) DELETE r")
+            .bind("task_id", task_id)
+            .execute(session)
+            .context("Failed to remove previous assignee")?;
+
+        Query::new(
+            "MATCH (t:Task {id: $task_id}), (p:Person {name: $person_name}) \
+             INSERT (t)-[:ASSIGNED_TO]->(p)",
+        )
+        .bind("task_id", task_id)
+        .bind("person_name", &person_name)
+        .execute(session)
+        .context("Failed to assign task")
+    }
+
+    /// Mark the open task that best matches a description as completed
+    ///
+    /// Used when a message reports work as finished ("I finished X"), so the
+    /// existing task is closed instead of a new one being created.
+    ///
+    /// # Returns
+    /// * `Ok(Some(Task))` - The task that was completed
+    /// * `Ok(None)` - No open task matched the description
+    pub fn complete_matching_task(&self, session: &Session, description: &str) -> Result<Option<Task>> {
+        let open_tasks = self.list_open_tasks(session)?;
+
+        let Some(task) = best_task_match(&open_tasks, description) else {
+            return Ok(None);
+        };
+
+        self.set_task_status(session, &task.id, TaskStatus::Completed)?;
+        Ok(Some(Task { status: TaskStatus::Completed, ..task.clone() }))
+    }
+
+    /// Map task ids to the name of their assignee
+    fn task_assignees(&self, session: &Session) -> Result<HashMap<String, String>> {
+        let result = Query::new("MATCH (t:Task)-[:ASSIGNED_TO]->(p:Person) RETURN t.id, p.name")
+            .fetch(session)
+            .context("Failed to query task assignees")?;
+
+        Ok(result
+            .rows
+            .iter()
+            .filter_map(|row| Some((string_column(row, "t.id")?, string_column(row, "p.name")?)))
+            .collect())
+    }
+
+    /// Messages that mention an entity, newest first
+    pub fn get_entity_mentions(&self, session: &Session, entity: &EntityRef) -> Result<Vec<EntityMention>> {
+        let result = Query::new(format!(
+            "MATCH (s:{} {{`{}`: $name}})-[:MENTIONED_IN]->(m:Message)-[:PART_OF]->(c:Conversation) \
+             RETURN m.id, m.role, m.content, m.timestamp, c.id, c.title",
+            entity.kind.label(),
+            entity.kind.name_property()
+        ))
+        .bind("name", &entity.name)
+        .fetch(session)
+        .context("Failed to query entity mentions")?;
+
+        let mut mentions: Vec<EntityMention> = result
+            .rows
+            .iter()
+            .filter_map(|row| {
+                Some(EntityMention {
+                    message_id: string_column(row, "m.id")?,
+                    role: string_column(row, "m.role")?,
+                    content: string_column(row, "m.content")?,
+                    timestamp: parse_timestamp(&string_column(row, "m.timestamp")?)?,
+                    conversation_id: string_column(row, "c.id")?,
+                    conversation_title: string_column(row, "c.title"),
+                })
+            })
+            .collect();
+
+        mentions.sort_by_key(|mention| std::cmp::Reverse(mention.timestamp));
+        Ok(mentions)
+    }
+
+    /// Messages containing the words of `query`, best matches first
+    ///
+    /// Matching is case-insensitive and done in Rust over all messages;
+    /// messages containing more of the query words rank higher, then newer ones.
+    pub fn search_messages(&self, session: &Session, query: &str, limit: usize) -> Result<Vec<EntityMention>> {
+        let result = Query::new(
+            "MATCH (m:Message)-[:PART_OF]->(c:Conversation) \
+             RETURN m.id, m.role, m.content, m.timestamp, c.id, c.title",
+        )
+        .fetch(session)
+        .context("Failed to query messages")?;
+
+        let messages: Vec<EntityMention> = result
+            .rows
+            .iter()
+            .filter_map(|row| {
+                Some(EntityMention {
+                    message_id: string_column(row, "m.id")?,
+                    role: string_column(row, "m.role")?,
+                    content: string_column(row, "m.content")?,
+                    timestamp: parse_timestamp(&string_column(row, "m.timestamp")?)?,
+                    conversation_id: string_column(row, "c.id")?,
+                    conversation_title: string_column(row, "c.title"),
+                })
+            })
+            .collect();
+
+        Ok(rank_messages(messages, query, limit))
+    }
+
+    /// Entities mentioned in the same messages as `entity`, one row per message
+    pub fn get_co_mentions(&self, session: &Session, entity: &EntityRef) -> Result<Vec<CoMention>> {
+        let result = Query::new(format!(
+            "MATCH (s:{} {{`{}`: $name}})-[:MENTIONED_IN]->(m:Message)<-[:MENTIONED_IN]-(e) \
+             WHERE e:Person OR e:Topic OR e:Task OR e:Document \
+             RETURN m.id, m.timestamp, {}",
+            entity.kind.label(),
+            entity.kind.name_property(),
+            ENTITY_COLUMNS
+        ))
+        .bind("name", &entity.name)
+        .fetch(session)
+        .context("Failed to query co-mentioned entities")?;
+
+        Ok(result
+            .rows
+            .iter()
+            .filter_map(|row| {
+                Some(CoMention {
+                    entity: entity_ref_column(row)?,
+                    message_id: string_column(row, "m.id")?,
+                    timestamp: string_column(row, "m.timestamp").and_then(|ts| parse_timestamp(&ts)),
+                })
+            })
+            .filter(|co_mention| co_mention.entity != *entity)
+            .collect())
+    }
+
+    /// Entities connected to `entity` by `relation_type` edges in either direction
+    ///
+    /// GraphLite does not report edge labels in results, so each relationship
+    /// type is queried separately.
+    pub fn get_related_by(
+        &self,
+        session: &Session,
+        entity: &EntityRef,
+        relation_type: &str,
+    ) -> Result<Vec<RelatedEntity>> {
+        let relation = normalize_relation_type(relation_type)
+            .with_context(|| format!("Invalid relationship type: {}", relation_type))?;
+
+        let node = format!("(s:{} {{`{}`: $name}})", entity.kind.label(), entity.kind.name_property());
+        let edge = format!("[r:{}]", relation);
+
+        let mut related = Vec::new();
+        for outgoing in [true, false] {
+            let pattern = if outgoing {
+                format!("{}-{}->(e)", node, edge)
+            } else {
+                format!("(e)-{}->{}", edge, node)
+            };
+            let result = Query::new(format!(
+                "MATCH {} RETURN r.message_id, {}",
+                pattern, ENTITY_COLUMNS
+            ))
+            .bind("name", &entity.name)
+            .fetch(session)
+            .with_context(|| format!("Failed to query {} relations", relation))?;
+
+            related.extend(result.rows.iter().filter_map(|row| {
+                Some(RelatedEntity {
+                    relation: relation.clone(),
+                    outgoing,
+                    entity: entity_ref_column(row)?,
+                    message_id: string_column(row, "r.message_id"),
+                })
+            }));
+        }
+
+        Ok(related)
+    }
+
+    /// Names of all nodes of one kind, sorted and without duplicates
+    pub fn list_entity_names(&self, session: &Session, kind: EntityKind) -> Result<Vec<String>> {
+        let column = format!("e.{}", kind.name_property());
+        let result = Query::new(format!("MATCH (e:{}) RETURN {}", kind.label(), column))
+            .fetch(session)
+            .with_context(|| format!("Failed to list {} nodes", kind.label()))?;
+
+        let mut names: Vec<String> = result
+            .rows
+            .iter()
+            .filter_map(|row| string_column(row, &column))
+            .collect();
+        names.sort();
+        names.dedup();
+        Ok(names)
+    }
+
+    /// Name of the existing node that `name` refers to, if any
+    ///
+    /// An exact match wins; Person and Topic names are then looked up in the
+    /// alias table and compared after [`normalize_entity_name`], so "alice"
+    /// and "Alice" resolve to the same node.
+    pub fn resolve_entity_name(&self, session: &Session, kind: EntityKind, name: &str) -> Result<Option<String>> {
+        let names = self.list_entity_names(session, kind)?;
+        let aliases = if matches!(kind, EntityKind::Person | EntityKind::Topic) {
+            self.list_aliases(session)?
+        } else {
+            Vec::new()
+        };
+        Ok(match_entity_name(&names, &aliases, kind, name))
+    }
+
+    /// All aliases, sorted by kind and alias
+    pub fn list_aliases(&self, session: &Session) -> Result<Vec<EntityAlias>> {
+        let result = Query::new("MATCH (a:Alias) RETURN a.kind, a.name, a.canonical")
+            .fetch(session)
+            .context("Failed to query aliases")?;
+
+        let mut aliases: Vec<EntityAlias> = result
+            .rows
+            .iter()
+            .filter_map(|row| {
+                Some(EntityAlias {
+                    kind: EntityKind::from_label(&string_column(row, "a.kind")?)?,
+                    alias: string_column(row, "a.name")?,
+                    canonical: string_column(row, "a.canonical")?,
+                })
+            })
+            .collect();
+        aliases.sort_by(|a, b| (a.kind.label(), &a.alias).cmp(&(b.kind.label(), &b.alias)));
+        Ok(aliases)
+    }
+
+    /// Record that `alias` refers to the node named `canonical`
+    ///
+    /// Replaces any alias with the same normalized key.
+    pub fn add_alias(&self, session: &Session, kind: EntityKind, alias: &str, canonical: &str) -> Result<()> {
+        let key = normalize_entity_name(alias);
+        if key.is_empty() {
+            anyhow::bail!("Alias '{}' is empty after normalization", alias);
+        }
+
+        Query::new("MATCH (a:Alias {kind: $kind, key: $key}) DELETE a")
+            .bind("kind", kind.label())
+            .bind("key", &key)
+            .execute(session)
+            .context("Failed to replace alias")?;
+
+        Query::insert_node(
+            "Alias",
+            vec![
+                ("kind", kind.label().into()),
+                ("key", key.into()),
+                ("name", alias.into()),
+                ("canonical", canonical.into()),
+                ("created_at", now().to_rfc3339().into()),
+            ],
+        )
+        .execute(session)
+        .context("Failed to store alias")
+    }
+
+    /// Merge the Person or Topic node `alias` into `canonical`
+    ///
+    /// `MENTIONED_IN` edges, `ASSIGNED_TO` edges and edges of the given
+    /// relationship types are recreated on the canonical node (GraphLite cannot
+    /// report edge labels, so the types must be listed). The alias node is then
+    /// deleted and its name recorded as an alias, along with any aliases that
+    /// pointed at it.
+    pub fn merge_entities(
+        &self,
+        session: &Session,
+        kind: EntityKind,
+        alias: &str,
+        canonical: &str,
+        relation_types: &[String],
+    ) -> Result<MergeReport> {
+        if !matches!(kind, EntityKind::Person | EntityKind::Topic) {
+            anyhow::bail!("Only Person and Topic nodes can be merged");
+        }
+        let names = self.list_entity_names(session, kind)?;
+        let find = |name: &str| {
+            names
+                .iter()
+                .find(|n| n.as_str() == name)
+                .or_else(|| names.iter().find(|n| normalize_entity_name(n) == normalize_entity_name(name)))
+                .cloned()
+                .with_context(|| format!("{} not found: {}", kind.label(), name))
+        };
+        let alias = find(alias)?;
+        let canonical = find(canonical)?;
+        if alias == canonical {
+            anyhow::bail!("'{}' and '{}' are already the same {}", alias, canonical, kind.label());
+        }
+
+        let alias_ref = EntityRef::new(kind, alias.as_str());
+        let canonical_ref = EntityRef::new(kind, canonical.as_str());
+        let node = |variable: &str| format!("({}:{} {{`{}`: ${}}})", variable, kind.label(), kind.name_property(), variable);
+
+        // Every write is planned and checked before any is made, so a merge
+        // that cannot be carried out leaves the graph unchanged
+        let mut mention_writes = Vec::new();
+        let mut edge_writes = Vec::new();
+
+        // Mentions the canonical node does not have yet
+        let known: Vec<String> = self
+            .get_entity_mentions(session, &canonical_ref)?
+            .into_iter()
+            .map(|mention| mention.message_id)
+            .collect();
+        for mention in self.get_entity_mentions(session, &alias_ref)? {
+            if known.contains(&mention.message_id) {
+                continue;
+            }
+            mention_writes.push(
+                Query::new(format!(
+                    "MATCH {}, (m:Message {{id: $message_id}}) INSERT (c)-[:MENTIONED_IN]->(m)",
+                    node("c")
+                ))
+                .bind("c", &canonical)
+                .bind("message_id", &mention.message_id),
+            );
+        }
+
+        // Typed relationships in both directions, except ones between the two nodes
+        let edge_labels: BTreeSet<String> = relation_types
+            .iter()
+            .filter_map(|relation_type| normalize_relation_type(relation_type))
+            .chain(std::iter::once("ASSIGNED_TO".to_string()))
+            .collect();
+
+        for edge_label in &edge_labels {
+            for outgoing in [true, false] {
+                let pattern = if outgoing {
+                    format!("{}-[r:{}]->(e)", node("a"), edge_label)
+                } else {
+                    format!("(e)-[r:{}]->{}", edge_label, node("a"))
+                };
+                let result = Query::new(format!(
+                    "MATCH {} RETURN r.message_id, r.created_at, e.id, {}",
+                    pattern, ENTITY_COLUMNS
+                ))
+                .bind("a", &alias)
+                .fetch(session)
+                .with_context(|| format!("Failed to query {} edges", edge_label))?;
+
+                for row in &result.rows {
+                    let Some(other) = entity_ref_column(row) else {
+                        continue;
+                    };
+                    if other == alias_ref || other == canonical_ref {
+                        continue;
+                    }
+
+                    // Tasks are not unique by description, so match them by id
+                    let (other_property, other_value) = match (other.kind, string_column(row, "e.id")) {
+                        (EntityKind::Task, Some(id)) => ("id", id),
+                        _ => (other.kind.name_property(), other.name.clone()),
+                    };
+                    let message_id = string_column(row, "r.message_id");
+                    let properties = if message_id.is_some() {
+                        " {message_id: $message_id, created_at: $created_at}"
+                    } else {
+                        ""
+                    };
+                    let (from, to) = if outgoing { ("c", "o") } else { ("o", "c") };
+                    let mut query = Query::new(format!(
+                        "MATCH {}, (o:{} {{`{}`: $o}}) INSERT ({})-[:{}{}]->({})",
+                        node("c"),
+                        other.kind.label(),
+                        other_property,
+                        from,
+                        edge_label,
+                        properties,
+                        to
+                    ))
+                    .bind("c", &canonical)
+                    .bind("o", other_value);
+                    if let Some(message_id) = message_id {
+                        query = query.bind("message_id", message_id).bind(
+                            "created_at",
+                            string_column(row, "r.created_at").unwrap_or_else(|| now().to_rfc3339()),
+                        );
+                    }
+                    edge_writes.push((query, edge_label));
+                }
+            }
+        }
+
+        for query in mention_writes.iter().chain(edge_writes.iter().map(|(query, _)| query)) {
+            query.build().context("Failed to plan the merge")?;
+        }
+
+        for query in &mention_writes {
+            query.execute(session).context("Failed to move mention")?;
+        }
+        for (query, edge_label) in &edge_writes {
+            query
+                .execute(session)
+                .with_context(|| format!("Failed to move {} edge", edge_label))?;
+        }
+        let mentions_moved = mention_writes.len();
+        let relations_moved = edge_writes.len();
+
+        // Aliases of the merged node now resolve to the canonical one
+        Query::new("MATCH (a:Alias {kind: $kind, canonical: $alias}) SET a.canonical = $canonical")
+            .bind("kind", kind.label())
+            .bind("alias", &alias)
+            .bind("canonical", &canonical)
+            .execute(session)
+            .context("Failed to update aliases")?;
+
+        Query::new(format!("MATCH {} DETACH DELETE a", node("a")))
+            .bind("a", &alias)
+            .execute(session)
+            .context("Failed to delete merged node")?;
+
+        self.add_alias(session, kind, &alias, &canonical)?;
+
+        Ok(MergeReport {
+            kind,
+            alias,
+            canonical,
+            mentions_moved,
+            relations_moved,
+        })
+    }
+
     /// Find entities mentioned in conversations about a topic
     ///
-    /// Returns both People (by name) and Tasks (by description) that are mentioned
-    /// in the same messages as the specified topic. Uses a CASE statement to handle
+    /// Returns People (by name), Tasks (by description) and Documents (by title)
+    /// that are mentioned in the same messages as the specified topic. Uses a CASE statement to handle
     /// different property names for different entity types.
     ///
     /// # Arguments
@@ -280,83 +1117,163 @@ impl GraphDB {
         session: &Session,
         topic_name: &str,
     ) -> Result<Vec<String>> {
-        let query = format!(
-            "MATCH (t:Topic {{name: '{}'}})-[:MENTIONED_IN]->(m:Message)<-[:MENTIONED_IN]-(e) \
-             WHERE e:Person OR e:Task \
+        let result = Query::new(
+            "MATCH (t:Topic {name: $topic_name})-[:MENTIONED_IN]->(m:Message)<-[:MENTIONED_IN]-(e) \
+             WHERE e:Person OR e:Task OR e:Document \
              RETURN DISTINCT \
                CASE \
                  WHEN e:Person THEN e.name \
                  WHEN e:Topic THEN e.name \
                  WHEN e:Task THEN e.description \
+                 WHEN e:Document THEN e.title \
                END as entity_name",
-            Self::escape_string(topic_name)
-        );
-
-        let result = session.query(&query)?;
+        )
+        .bind("topic_name", topic_name)
+        .fetch(session)?;
 
         // Parse result rows to extract entity names/descriptions
         let entities: Vec<String> = result
             .rows
             .iter()
-            .filter_map(|row| {
-                match row.get_value("entity_name") {
-                    Some(Value::String(name)) => Some(name.clone()),
-                    _ => None, // Skip rows with missing or wrong-typed values
-                }
-            })
+            .filter_map(|row| string_column(row, "entity_name"))
             .collect();
 
         Ok(entities)
     }
 
-    /// Escape special characters in strings for safe use in GQL queries
-    ///
-    /// Escapes characters that could break string context or cause injection:
-    /// - Single quotes (') -> \'
-    /// - Backslashes (\) -> \\
-    /// - Newlines (\n) -> \n
-    /// - Carriage returns (\r) -> \r
-    /// - Tabs (\t) -> \t
-    /// - Backspace (\b) -> \b
-    /// - Form feed (\f) -> \f
-    /// - NULL (\0) -> \0
-    /// - Double quotes (") -> \"
-    ///
-    /// # Arguments
-    /// * `s` - The string to escape
-    ///
-    /// # Returns
-    /// * Escaped string safe for GQL query interpolation
-    ///
-    /// # Security
-    /// This function prevents GQL injection by ensuring user input cannot
-    /// break out of string context in queries.
-    ///
-    /// # Example
-    /// ```
-    /// let escaped = GraphDB::escape_string("O'Reilly\nNewline");
-    /// // Returns: "O\\'Reilly\\nNewline"
-    /// ```
-    fn escape_string(s: &str) -> String {
-        let mut result = String::with_capacity(s.len() + 16);
-
-        for ch in s.chars() {
-            match ch {
-                '\'' => result.push_str("\\'"),   // Single quote
-                '\\' => result.push_str("\\\\"),  // Backslash
-                '\n' => result.push_str("\\n"),   // Newline
-                '\r' => result.push_str("\\r"),   // Carriage return
-                '\t' => result.push_str("\\t"),   // Tab
-                '\x08' => result.push_str("\\b"), // Backspace
-                '\x0C' => result.push_str("\\f"), // Form feed
-                '\0' => result.push_str("\\0"),   // NULL character
-                '"' => result.push_str("\\\""),   // Double quote
-                _ => result.push(ch),
+}
+
+/// Key used to resolve relation subjects/objects to linked entities
+fn relation_key(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// Combine conversations with their (conversation id, message timestamp) pairs
+/// into summaries sorted by last activity, newest first, and apply paging
+fn summarize_conversations(
+    conversations: Vec<Conversation>,
+    message_times: &[(String, String)],
+    offset: usize,
+    limit: usize,
+) -> Vec<ConversationSummary> {
+    let mut stats: HashMap<&str, (usize, Option<chrono::DateTime<chrono::Utc>>)> = HashMap::new();
+    for (conversation_id, timestamp) in message_times {
+        let entry = stats.entry(conversation_id.as_str()).or_default();
+        entry.0 += 1;
+        if let Some(ts) = parse_timestamp(timestamp) {
+            entry.1 = entry.1.max(Some(ts));
+        }
+    }
+
+    let mut summaries: Vec<ConversationSummary> = conversations
+        .into_iter()
+        .map(|conversation| {
+            let (message_count, last_message) =
+                stats.get(conversation.id.as_str()).copied().unwrap_or_default();
+            ConversationSummary {
+                last_activity: last_message.unwrap_or(conversation.started_at),
+                id: conversation.id,
+                title: conversation.title,
+                started_at: conversation.started_at,
+                message_count,
             }
+        })
+        .collect();
+
+    summaries.sort_by(|a, b| b.last_activity.cmp(&a.last_activity).then_with(|| a.id.cmp(&b.id)));
+    summaries.into_iter().skip(offset).take(limit).collect()
+}
+
+/// Pick the task whose description best matches `description`
+///
+/// Matching is case- and punctuation-insensitive: an exact match wins, then a
+/// description containing the other, then the highest word overlap (at least
+/// half of the words). Ties go to the oldest task.
+fn best_task_match<'t>(tasks: &'t [Task], description: &str) -> Option<&'t Task> {
+    fn words(text: &str) -> Vec<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect()
+    }
+
+    let wanted = words(description);
+    if wanted.is_empty() {
+        return None;
+    }
+    let wanted_text = wanted.join(" ");
+
+    let score = |task: &Task| -> f64 {
+        let candidate = words(&task.description);
+        let candidate_text = candidate.join(" ");
+        if candidate_text == wanted_text {
+            return 3.0;
+        }
+        if !candidate_text.is_empty()
+            && (candidate_text.contains(&wanted_text) || wanted_text.contains(&candidate_text))
+        {
+            return 2.0;
         }
+        let shared = wanted.iter().filter(|word| candidate.contains(word)).count();
+        let overlap = shared as f64 / wanted.len().max(candidate.len()) as f64;
+        if overlap >= 0.5 { overlap } else { 0.0 }
+    };
+
+    tasks
+        .iter()
+        .map(|task| (score(task), task))
+        .filter(|(score, _)| *score > 0.0)
+        .fold(None, |best: Option<(f64, &Task)>, (score, task)| match best {
+            Some((best_score, _)) if best_score >= score => best,
+            _ => Some((score, task)),
+        })
+        .map(|(_, task)| task)
+}
 
-        result
+/// Existing name that `name` refers to: an exact match, then an alias, then
+/// a name with the same normalized form (Person and Topic only)
+fn match_entity_name(names: &[String], aliases: &[EntityAlias], kind: EntityKind, name: &str) -> Option<String> {
+    if names.iter().any(|existing| existing == name) {
+        return Some(name.to_string());
     }
+    if !matches!(kind, EntityKind::Person | EntityKind::Topic) {
+        return None;
+    }
+
+    let key = normalize_entity_name(name);
+    if key.is_empty() {
+        return None;
+    }
+    aliases
+        .iter()
+        .find(|alias| alias.kind == kind && normalize_entity_name(&alias.alias) == key)
+        .map(|alias| alias.canonical.clone())
+        .or_else(|| names.iter().find(|existing| normalize_entity_name(existing) == key).cloned())
+}
+
+/// Keep the messages containing any word of `query`, ranked by how many
+/// words they contain and then by recency
+fn rank_messages(messages: Vec<EntityMention>, query: &str, limit: usize) -> Vec<EntityMention> {
+    let words: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    if words.is_empty() {
+        return Vec::new();
+    }
+
+    let mut ranked: Vec<(usize, EntityMention)> = messages
+        .into_iter()
+        .filter_map(|message| {
+            let content = message.content.to_lowercase();
+            let hits = words.iter().filter(|word| content.contains(word.as_str())).count();
+            (hits > 0).then_some((hits, message))
+        })
+        .collect();
+
+    ranked.sort_by_key(|(hits, message)| std::cmp::Reverse((*hits, message.timestamp)));
+    ranked.into_iter().take(limit).map(|(_, message)| message).collect()
 }
 
 #[cfg(test)]
@@ -369,7 +1286,7 @@ mod tests {
         assert_eq!(config.label(), "Person");
         assert_eq!(config.id_property(), "name");
         assert!(config.should_deduplicate());
-        assert!(config.additional_properties().is_none());
+        assert!(config.additional_properties().is_empty());
     }
 
     #[test]
@@ -378,132 +1295,248 @@ mod tests {
         assert_eq!(config.label(), "Topic");
         assert_eq!(config.id_property(), "name");
         assert!(config.should_deduplicate());
-        assert!(config.additional_properties().is_none());
+        assert!(config.additional_properties().is_empty());
     }
 
     #[test]
     fn test_entity_config_task() {
-        let config = EntityConfig::Task { description: "Test task" };
+        let config = EntityConfig::Task { id: "task-1", description: "Test task" };
         assert_eq!(config.label(), "Task");
         assert_eq!(config.id_property(), "description");
         assert!(!config.should_deduplicate());
-        assert!(config.additional_properties().is_some());
+        assert!(!config.additional_properties().is_empty());
 
         // Verify additional properties contain expected fields
-        let props = config.additional_properties().unwrap();
-        assert!(props.contains("status: 'pending'"));
-        assert!(props.contains("created_at:"));
+        let props = config.additional_properties();
+        assert!(props.contains(&("id", "task-1".into())));
+        assert!(props.contains(&("status", "pending".into())));
+        assert!(props.iter().any(|(name, _)| *name == "created_at"));
     }
 
     #[test]
-    fn test_entity_config_id_value_escaping() {
+    fn test_entity_config_id_value_is_raw() {
+        // Escaping happens when the value is bound to a Query
         let config = EntityConfig::Person { name: "O'Reilly" };
-        let escaped = config.id_value();
-        assert!(escaped.contains("\\'"));
-        assert_eq!(escaped, "O\\'Reilly");
+        assert_eq!(config.id_value(), "O'Reilly");
     }
 
     #[test]
-    fn test_entity_config_id_value_newlines() {
-        let config = EntityConfig::Task { description: "Line1\nLine2" };
-        let escaped = config.id_value();
-        assert!(escaped.contains("\\n"));
-        assert_eq!(escaped, "Line1\\nLine2");
+    fn test_entity_config_insert_escapes_values() {
+        let config = EntityConfig::Task { id: "task-1", description: "Line1\nLine2 'quoted'" };
+        let query = Query::insert_node(config.label(), config.insert_properties())
+            .build()
+            .unwrap();
+
+        assert!(query.starts_with("INSERT (:Task {`description`: 'Line1\\nLine2 \\'quoted\\'', `id`: 'task-1', `status`: 'pending', `created_at`: '"));
     }
 
-    #[tokio::test]
-    async fn test_escape_string() {
-        let input = "It's a test with 'quotes' and\nnewlines";
-        let escaped = GraphDB::escape_string(input);
+    #[test]
+    fn test_entity_config_match_key() {
+        let person = EntityConfig::Person { name: "Alice" };
+        assert_eq!(person.match_key(), ("name", "Alice"));
 
-        // Verify each escape sequence
-        assert!(escaped.contains("\\'"));
-        assert!(escaped.contains("\\n"));
+        let task = EntityConfig::Task { id: "task-1", description: "Review PR" };
+        assert_eq!(task.match_key(), ("id", "task-1"));
     }
 
     #[test]
-    fn test_escape_string_backslash() {
-        let input = "path\\to\\file";
-        let escaped = GraphDB::escape_string(input);
-        assert_eq!(escaped, "path\\\\to\\\\file");
+    fn test_entity_config_document_dedupes_by_url() {
+        let config = EntityConfig::Document {
+            title: "Q3 roadmap",
+            url: Some("https://example.com/roadmap"),
+            doc_type: "link",
+        };
+        assert_eq!(config.label(), "Document");
+        assert!(config.should_deduplicate());
+        assert_eq!(config.match_key(), ("url", "https://example.com/roadmap"));
+
+        let query = Query::insert_node(config.label(), config.insert_properties())
+            .build()
+            .unwrap();
+        assert_eq!(
+            query,
+            "INSERT (:Document {`url`: 'https://example.com/roadmap', `title`: 'Q3 roadmap', `doc_type`: 'link'})"
+        );
+    }
+
+    #[test]
+    fn test_entity_config_document_dedupes_by_title_without_url() {
+        let config = EntityConfig::Document { title: "design.md", url: None, doc_type: "file" };
+        assert_eq!(config.match_key(), ("title", "design.md"));
+        assert_eq!(
+            config.insert_properties(),
+            vec![("title", "design.md".into()), ("doc_type", "file".into())]
+        );
     }
 
     #[test]
-    fn test_escape_string_comprehensive() {
-        let input = "Test\twith\ttabs\nand\nnewlines\r\nand\rcarriage\x08backspace\x0Cformfeed";
-        let escaped = GraphDB::escape_string(input);
+    fn test_linked_entity_uses_match_key() {
+        let task = EntityConfig::Task { id: "task-1", description: "Review PR" };
+        assert_eq!(
+            LinkedEntity::from(&task),
+            LinkedEntity { label: "Task", property: "id", value: "task-1".to_string() }
+        );
+        assert_eq!(relation_key("  GraphQL Migration "), relation_key("graphql migration"));
+    }
 
-        assert!(escaped.contains("\\t"), "Tab should be escaped");
-        assert!(escaped.contains("\\n"), "Newline should be escaped");
-        assert!(escaped.contains("\\r"), "Carriage return should be escaped");
-        assert!(escaped.contains("\\b"), "Backspace should be escaped");
-        assert!(escaped.contains("\\f"), "Form feed should be escaped");
+    fn task(id: &str, description: &str, created_at: &str) -> Task {
+        Task {
+            id: id.to_string(),
+            description: description.to_string(),
+            status: TaskStatus::Pending,
+            created_at: parse_timestamp(created_at).unwrap(),
+            due_date: None,
+            assignee: None,
+        }
     }
 
     #[test]
-    fn test_escape_string_injection_prevention() {
-        // Simulated injection attempt
-        let input = "'; DROP TABLE users; --";
-        let escaped = GraphDB::escape_string(input);
+    fn test_best_task_match_exact_ignores_case_and_punctuation() {
+        let tasks = vec![
+            task("1", "Review the PR", "2024-01-01T00:00:00Z"),
+            task("2", "Update docs", "2024-01-02T00:00:00Z"),
+        ];
+        assert_eq!(best_task_match(&tasks, "update docs!").unwrap().id, "2");
+    }
 
-        // Verify quotes are escaped (prevents breaking out of string context)
-        assert!(escaped.contains("\\'"), "Quotes should be escaped");
-        assert!(escaped.starts_with("\\'"), "Should start with escaped quote");
+    #[test]
+    fn test_best_task_match_containment_and_overlap() {
+        let tasks = vec![
+            task("1", "Write the migration plan for GraphQL", "2024-01-01T00:00:00Z"),
+            task("2", "Book flights", "2024-01-02T00:00:00Z"),
+        ];
+        assert_eq!(best_task_match(&tasks, "the migration plan").unwrap().id, "1");
+        assert_eq!(best_task_match(&tasks, "book the flights").unwrap().id, "2");
     }
 
     #[test]
-    fn test_escape_string_null_character() {
-        let input = "text\0with\0nulls";
-        let escaped = GraphDB::escape_string(input);
-        assert!(escaped.contains("\\0"), "NULL characters should be escaped");
+    fn test_best_task_match_none_when_unrelated() {
+        let tasks = vec![task("1", "Review PR", "2024-01-01T00:00:00Z")];
+        assert!(best_task_match(&tasks, "Plan the offsite").is_none());
+        assert!(best_task_match(&tasks, "").is_none());
     }
 
     #[test]
-    fn test_escape_string_edge_cases() {
-        // Empty string
-        assert_eq!(GraphDB::escape_string(""), "");
+    fn test_best_task_match_prefers_oldest_on_tie() {
+        let tasks = vec![
+            task("1", "Review PR", "2024-01-01T00:00:00Z"),
+            task("2", "Review PR", "2024-01-02T00:00:00Z"),
+        ];
+        assert_eq!(best_task_match(&tasks, "review pr").unwrap().id, "1");
+    }
 
-        // Only special characters
-        assert_eq!(GraphDB::escape_string("'\n\r\t"), "\\'\\n\\r\\t");
+    fn conversation(id: &str, started_at: &str) -> Conversation {
+        Conversation {
+            id: id.to_string(),
+            started_at: parse_timestamp(started_at).unwrap(),
+            title: Some(format!("Conversation {}", id)),
+        }
+    }
 
-        // Unicode (should pass through)
-        let unicode = "Hello 世界 🌍";
-        let escaped = GraphDB::escape_string(unicode);
-        assert!(escaped.contains("世界"));
-        assert!(escaped.contains("🌍"));
+    fn message_time(conversation_id: &str, timestamp: &str) -> (String, String) {
+        (conversation_id.to_string(), timestamp.to_string())
     }
 
     #[test]
-    fn test_escape_string_double_quote() {
-        let input = "String with \"double quotes\"";
-        let escaped = GraphDB::escape_string(input);
-        assert!(escaped.contains("\\\""), "Double quotes should be escaped");
+    fn test_summarize_conversations_counts_and_orders() {
+        let conversations = vec![
+            conversation("a", "2024-01-01T00:00:00Z"),
+            conversation("b", "2024-01-02T00:00:00Z"),
+            conversation("c", "2024-01-03T00:00:00Z"),
+        ];
+        let message_times = vec![
+            message_time("a", "2024-01-05T10:00:00Z"),
+            message_time("a", "2024-01-05T09:00:00Z"),
+            message_time("b", "2024-01-02T01:00:00Z"),
+        ];
+
+        let summaries = summarize_conversations(conversations, &message_times, 0, 10);
+        let ids: Vec<&str> = summaries.iter().map(|s| s.id.as_str()).collect();
+
+        // "a" has the newest message; "c" has none but started after "b"'s last message
+        assert_eq!(ids, vec!["a", "c", "b"]);
+        assert_eq!(summaries[0].message_count, 2);
+        assert_eq!(summaries[0].last_activity, parse_timestamp("2024-01-05T10:00:00Z").unwrap());
+        assert_eq!(summaries[1].message_count, 0);
+        assert_eq!(summaries[1].last_activity, summaries[1].started_at);
     }
 
     #[test]
-    fn test_escape_string_backslash_before_quote() {
-        // Critical test: backslash before quote should escape both
-        let input = "O'Reilly\\'; malicious";
-        let escaped = GraphDB::escape_string(input);
+    fn test_summarize_conversations_paging() {
+        let conversations = vec![
+            conversation("a", "2024-01-01T00:00:00Z"),
+            conversation("b", "2024-01-02T00:00:00Z"),
+            conversation("c", "2024-01-03T00:00:00Z"),
+        ];
 
-        // Both backslash and quote should be escaped
-        assert!(escaped.contains("\\\\"), "Backslash should be escaped");
-        assert!(escaped.contains("\\'"), "Quote should be escaped");
+        let page = summarize_conversations(conversations.clone(), &[], 1, 1);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].id, "b");
 
-        // Verify the backslash doesn't neutralize the quote escape
-        assert!(escaped.contains("O\\'Reilly\\\\"), "Should have escaped quote then escaped backslash");
+        assert!(summarize_conversations(conversations, &[], 3, 10).is_empty());
     }
 
     // Note: Query result parsing tests (get_conversation_messages, find_related_entities)
     // require integration testing with a real GraphLite database instance.
     // These functions:
-    // - Parse QueryResult.rows using row.get_value(column_name)
-    // - Match on Value::String variants
+    // - Parse QueryResult.rows using string_column(row, column_name)
+    // - Match on Value::String variants (unescaping stored literals)
     // - Use filter_map to skip rows with missing/wrong-typed values
     // - Return empty Vec when no results match
     //
     // Manual testing should verify:
     // 1. Column names match RETURN clause (e.g., "m.role", "entity_name")
     // 2. Messages are ordered DESC by timestamp
-    // 3. CASE statement correctly handles Person (name) vs Task (description)
+    // 3. CASE statement correctly handles Person (name), Task (description) and Document (title)
+
+    fn message(content: &str, day: u32) -> EntityMention {
+        EntityMention {
+            message_id: format!("m{}", day),
+            role: "user".to_string(),
+            content: content.to_string(),
+            timestamp: parse_timestamp(&format!("2024-05-{:02}T10:00:00Z", day)).unwrap(),
+            conversation_id: "c1".to_string(),
+            conversation_title: None,
+        }
+    }
+
+    #[test]
+    fn test_rank_messages() {
+        let messages = vec![
+            message("Alice is migrating the billing API", 1),
+            message("The GraphQL schema needs review", 2),
+            message("Alice reviewed the GraphQL schema", 3),
+            message("Lunch at noon", 4),
+        ];
+
+        let ranked = rank_messages(messages.clone(), "GraphQL, alice?", 10);
+        let ids: Vec<&str> = ranked.iter().map(|m| m.message_id.as_str()).collect();
+        assert_eq!(ids, vec!["m3", "m2", "m1"]);
+
+        assert_eq!(rank_messages(messages.clone(), "graphql", 1)[0].message_id, "m3");
+        assert!(rank_messages(messages, " ?! ", 10).is_empty());
+    }
+
+    #[test]
+    fn test_match_entity_name() {
+        let names = vec!["Alice Chen".to_string(), "Bob".to_string(), "Rust".to_string()];
+        let aliases = vec![EntityAlias {
+            kind: EntityKind::Person,
+            alias: "A. Chen".to_string(),
+            canonical: "Alice Chen".to_string(),
+        }];
+        let person = |name| match_entity_name(&names, &aliases, EntityKind::Person, name);
+
+        assert_eq!(person("Bob").as_deref(), Some("Bob"));
+        assert_eq!(person("  bob ").as_deref(), Some("Bob"));
+        assert_eq!(person("alice  chen").as_deref(), Some("Alice Chen"));
+        assert_eq!(person("a. chen").as_deref(), Some("Alice Chen"));
+        assert_eq!(person("Carol"), None);
+
+        // Aliases only apply to their own kind
+        assert_eq!(match_entity_name(&names, &aliases, EntityKind::Topic, "A. Chen"), None);
+        assert_eq!(match_entity_name(&names, &aliases, EntityKind::Topic, "RUST").as_deref(), Some("Rust"));
+        // Tasks and documents are matched exactly
+        assert_eq!(match_entity_name(&names, &aliases, EntityKind::Task, "bob"), None);
+    }
 }
@@ -0,0 +1,494 @@
+use anyhow::{Context, Result};
+use graphlite_sdk::{QueryResult, Row, Session, Value};
+
+/// A value bound to a `$name` placeholder in a [`Query`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryParam {
+    String(String),
+    Integer(i64),
+}
+
+impl QueryParam {
+    /// Render the value as a GQL literal
+    fn to_literal(&self) -> String {
+        match self {
+            QueryParam::String(s) => format!("'{}'", escape_string(s)),
+            QueryParam::Integer(n) => n.to_string(),
+        }
+    }
+}
+
+impl From<&str> for QueryParam {
+    fn from(value: &str) -> Self {
+        QueryParam::String(value.to_string())
+    }
+}
+
+impl From<String> for QueryParam {
+    fn from(value: String) -> Self {
+        QueryParam::String(value)
+    }
+}
+
+impl From<&String> for QueryParam {
+    fn from(value: &String) -> Self {
+        QueryParam::String(value.clone())
+    }
+}
+
+impl From<i64> for QueryParam {
+    fn from(value: i64) -> Self {
+        QueryParam::Integer(value)
+    }
+}
+
+impl From<usize> for QueryParam {
+    fn from(value: usize) -> Self {
+        QueryParam::Integer(value as i64)
+    }
+}
+
+/// GQL query with named `$name` parameters
+///
+/// GraphLite has no server-side parameter binding, so bound values are
+/// rendered as escaped literals when the query is built. Call sites only write
+/// placeholders, which keeps all escaping in this module.
+///
+/// # Example
+/// ```ignore
+/// Query::new("MATCH (p:Person {name: $name}) RETURN p.name")
+///     .bind("name", "O'Reilly")
+///     .fetch(&session)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct Query {
+    template: String,
+    params: Vec<(String, QueryParam)>,
+}
+
+impl Query {
+    /// Create a query from a template containing `$name` placeholders
+    ///
+    /// The template itself must never contain user input; labels and property
+    /// names interpolated into it must come from fixed strings.
+    pub fn new(template: impl Into<String>) -> Self {
+        Self {
+            template: template.into(),
+            params: Vec::new(),
+        }
+    }
+
+    /// Create an `INSERT` query for a single node with bound properties
+    ///
+    /// Property names are backtick-quoted because some common names
+    /// (e.g. `description`) are reserved words in GraphLite's parser.
+    pub fn insert_node(label: &str, properties: Vec<(&str, QueryParam)>) -> Self {
+        let placeholders = properties
+            .iter()
+            .map(|(name, _)| format!("`{}`: ${}", name, name))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        properties.into_iter().fold(
+            Self::new(format!("INSERT (:{} {{{}}})", label, placeholders)),
+            |query, (name, value)| query.bind(name, value),
+        )
+    }
+
+    /// Bind a value to the `$name` placeholder
+    pub fn bind(mut self, name: &str, value: impl Into<QueryParam>) -> Self {
+        self.params.push((name.to_string(), value.into()));
+        self
+    }
+
+    /// Render the final query text
+    ///
+    /// Fails if the template references an unbound parameter or a bound
+    /// parameter is never used, which usually means a typo in the template.
+    pub fn build(&self) -> Result<String> {
+        let mut output = String::with_capacity(self.template.len() + 32);
+        let mut used = vec![false; self.params.len()];
+        let mut chars = self.template.char_indices().peekable();
+
+        while let Some((start, ch)) = chars.next() {
+            if ch != '$' {
+                output.push(ch);
+                continue;
+            }
+
+            let mut end = start + 1;
+            while let Some(&(i, c)) = chars.peek() {
+                if !(c.is_ascii_alphanumeric() || c == '_') {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+
+            let name = &self.template[start + 1..end];
+            if name.is_empty() {
+                anyhow::bail!("Empty query parameter name in: {}", self.template);
+            }
+
+            let index = self
+                .params
+                .iter()
+                .position(|(param, _)| param == name)
+                .with_context(|| format!("Unbound query parameter: ${}", name))?;
+            used[index] = true;
+            output.push_str(&self.params[index].1.to_literal());
+        }
+
+        if let Some(index) = used.iter().position(|&u| !u) {
+            anyhow::bail!("Unused query parameter: ${}", self.params[index].0);
+        }
+
+        Ok(output)
+    }
+
+    /// Build and execute the query, discarding any result rows
+    pub fn execute(&self, session: &Session) -> Result<()> {
+        session.execute(&self.build()?)?;
+        Ok(())
+    }
+
+    /// Build and run the query, returning its result rows
+    pub fn fetch(&self, session: &Session) -> Result<QueryResult> {
+        Ok(session.query(&self.build()?)?)
+    }
+}
+
+/// Read a string column from a result row
+///
+/// GraphLite stores string literals without processing escape sequences, so
+/// values written through [`Query`] are unescaped again here.
+pub fn string_column(row: &Row, column: &str) -> Option<String> {
+    match row.get_value(column) {
+        Some(Value::String(s)) => Some(unescape_string(s)),
+        _ => None, // Missing or wrong-typed value
+    }
+}
+
+/// Read a numeric column (e.g. a `count(...)`) from a result row
+pub fn number_column(row: &Row, column: &str) -> Option<f64> {
+    match row.get_value(column) {
+        Some(Value::Number(n)) => Some(*n),
+        _ => None,
+    }
+}
+
+/// Read the value of a `RETURN count(...)` query from its single result row
+///
+/// GraphLite ignores `AS` aliases on aggregates and names the column itself
+/// (e.g. `COUNT_e`), so the first numeric value in the row is used.
+pub fn count_column(row: &Row) -> Option<f64> {
+    row.values.values().find_map(|value| match value {
+        Value::Number(n) => Some(*n),
+        _ => None,
+    })
+}
+
+/// Escape special characters in strings for safe use in GQL queries
+///
+/// Escapes characters that could break string context or cause injection:
+/// - Single quotes (') -> \'
+/// - Backslashes (\) -> \\
+/// - Newlines (\n) -> \n
+/// - Carriage returns (\r) -> \r
+/// - Tabs (\t) -> \t
+/// - Backspace (\b) -> \b
+/// - Form feed (\f) -> \f
+/// - NULL (\0) -> \0
+/// - Double quotes (") -> \"
+///
+/// # Arguments
+/// * `s` - The string to escape
+///
+/// # Returns
+/// * Escaped string safe for GQL query interpolation
+///
+/// # Security
+/// This function prevents GQL injection by ensuring user input cannot
+/// break out of string context in queries. Prefer binding values with
+/// [`Query::bind`] over calling it directly.
+///
+/// # Example
+/// ```ignore
+/// let escaped = escape_string("O'Reilly\nNewline");
+/// // Returns: "O\\'Reilly\\nNewline"
+/// ```
+pub(crate) fn escape_string(s: &str) -> String {
+    let mut result = String::with_capacity(s.len() + 16);
+
+    for ch in s.chars() {
+        match ch {
+            '\'' => result.push_str("\\'"),   // Single quote
+            '\\' => result.push_str("\\\\"),  // Backslash
+            '\n' => result.push_str("\\n"),   // Newline
+            '\r' => result.push_str("\\r"),   // Carriage return
+            '\t' => result.push_str("\\t"),   // Tab
+            '\x08' => result.push_str("\\b"), // Backspace
+            '\x0C' => result.push_str("\\f"), // Form feed
+            '\0' => result.push_str("\\0"),   // NULL character
+            '"' => result.push_str("\\\""),   // Double quote
+            _ => result.push(ch),
+        }
+    }
+
+    result
+}
+
+/// Reverse [`escape_string`]; unknown escape sequences are kept as-is
+pub(crate) fn unescape_string(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars();
+
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            result.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('\'') => result.push('\''),
+            Some('\\') => result.push('\\'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('t') => result.push('\t'),
+            Some('b') => result.push('\x08'),
+            Some('f') => result.push('\x0C'),
+            Some('0') => result.push('\0'),
+            Some('"') => result.push('"'),
+            Some(other) => {
+                result.push('\\');
+                result.push(other);
+            }
+            None => result.push('\\'),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Split a rendered query into (text outside literals, literal contents),
+    /// honouring backslash escapes the way GraphLite's lexer does
+    fn split_literals(query: &str) -> (String, Vec<String>) {
+        let mut outside = String::new();
+        let mut literals = Vec::new();
+        let mut chars = query.chars();
+
+        while let Some(ch) = chars.next() {
+            if ch != '\'' {
+                outside.push(ch);
+                continue;
+            }
+            let mut literal = String::new();
+            loop {
+                match chars.next() {
+                    Some('\\') => {
+                        literal.push('\\');
+                        literal.push(chars.next().expect("dangling escape"));
+                    }
+                    Some('\'') => break,
+                    Some(c) => literal.push(c),
+                    None => panic!("unterminated string literal in {}", query),
+                }
+            }
+            outside.push('?');
+            literals.push(unescape_string(&literal));
+        }
+
+        (outside, literals)
+    }
+
+    #[test]
+    fn test_query_binds_string_and_integer() {
+        let query = Query::new("MATCH (p:Person {name: $name}) RETURN p.name LIMIT $limit")
+            .bind("name", "Alice")
+            .bind("limit", 5usize);
+
+        assert_eq!(
+            query.build().unwrap(),
+            "MATCH (p:Person {name: 'Alice'}) RETURN p.name LIMIT 5"
+        );
+    }
+
+    #[test]
+    fn test_query_reuses_parameter() {
+        let query = Query::new("MATCH (a {id: $id}), (b {id: $id}) RETURN a").bind("id", "x");
+        assert_eq!(query.build().unwrap(), "MATCH (a {id: 'x'}), (b {id: 'x'}) RETURN a");
+    }
+
+    #[test]
+    fn test_query_unbound_parameter_fails() {
+        let err = Query::new("MATCH (p {name: $name}) RETURN p").build().unwrap_err();
+        assert!(err.to_string().contains("$name"));
+    }
+
+    #[test]
+    fn test_query_unused_parameter_fails() {
+        let err = Query::new("MATCH (p {name: $name}) RETURN p")
+            .bind("name", "Alice")
+            .bind("nmae", "typo")
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("$nmae"));
+    }
+
+    #[test]
+    fn test_query_value_placeholders_are_not_expanded() {
+        let query = Query::new("INSERT (:Message {content: $content, role: $role})")
+            .bind("content", "costs $role")
+            .bind("role", "user");
+
+        let (_, literals) = split_literals(&query.build().unwrap());
+        assert_eq!(literals, vec!["costs $role", "user"]);
+    }
+
+    #[test]
+    fn test_query_title_with_quotes_stays_in_literal() {
+        let title = "Bob's plan'}) MATCH (n) DETACH DELETE n //";
+        let query = Query::new("INSERT (:Conversation {id: $id, title: $title})")
+            .bind("id", "c1")
+            .bind("title", title);
+
+        let (outside, literals) = split_literals(&query.build().unwrap());
+        assert_eq!(outside, "INSERT (:Conversation {id: ?, title: ?})");
+        assert_eq!(literals, vec!["c1", title]);
+    }
+
+    #[test]
+    fn test_query_role_with_quotes_stays_in_literal() {
+        let role = "user', content: 'injected";
+        let query = Query::new("INSERT (:Message {role: $role, content: $content})")
+            .bind("role", role)
+            .bind("content", "hello");
+
+        let (outside, literals) = split_literals(&query.build().unwrap());
+        assert_eq!(outside, "INSERT (:Message {role: ?, content: ?})");
+        assert_eq!(literals, vec![role, "hello"]);
+    }
+
+    #[test]
+    fn test_query_backslash_before_quote_stays_in_literal() {
+        let value = "O'Reilly\\'; malicious";
+        let query = Query::new("MATCH (p:Person {name: $name}) RETURN p").bind("name", value);
+
+        let (outside, literals) = split_literals(&query.build().unwrap());
+        assert_eq!(outside, "MATCH (p:Person {name: ?}) RETURN p");
+        assert_eq!(literals, vec![value]);
+    }
+
+    #[test]
+    fn test_insert_node() {
+        let query = Query::insert_node(
+            "Task",
+            vec![
+                ("description", "Ship it".into()),
+                ("status", "pending".into()),
+            ],
+        );
+
+        assert_eq!(
+            query.build().unwrap(),
+            "INSERT (:Task {`description`: 'Ship it', `status`: 'pending'})"
+        );
+    }
+
+    #[test]
+    fn test_unescape_round_trip() {
+        let input = "It's a \"test\"\nwith\ttabs\\slashes\0and\x08\x0C世界";
+        assert_eq!(unescape_string(&escape_string(input)), input);
+    }
+
+    #[test]
+    fn test_unescape_keeps_unknown_sequences() {
+        assert_eq!(unescape_string("a\\xb\\"), "a\\xb\\");
+    }
+
+    #[tokio::test]
+    async fn test_escape_string() {
+        let input = "It's a test with 'quotes' and\nnewlines";
+        let escaped = escape_string(input);
+
+        // Verify each escape sequence
+        assert!(escaped.contains("\\'"));
+        assert!(escaped.contains("\\n"));
+    }
+
+    #[test]
+    fn test_escape_string_backslash() {
+        let input = "path\\to\\file";
+        let escaped = escape_string(input);
+        assert_eq!(escaped, "path\\\\to\\\\file");
+    }
+
+    #[test]
+    fn test_escape_string_comprehensive() {
+        let input = "Test\twith\ttabs\nand\nnewlines\r\nand\rcarriage\x08backspace\x0Cformfeed";
+        let escaped = escape_string(input);
+
+        assert!(escaped.contains("\\t"), "Tab should be escaped");
+        assert!(escaped.contains("\\n"), "Newline should be escaped");
+        assert!(escaped.contains("\\r"), "Carriage return should be escaped");
+        assert!(escaped.contains("\\b"), "Backspace should be escaped");
+        assert!(escaped.contains("\\f"), "Form feed should be escaped");
+    }
+
+    #[test]
+    fn test_escape_string_injection_prevention() {
+        // Simulated injection attempt
+        let input = "'; DROP TABLE users; --";
+        let escaped = escape_string(input);
+
+        // Verify quotes are escaped (prevents breaking out of string context)
+        assert!(escaped.contains("\\'"), "Quotes should be escaped");
+        assert!(escaped.starts_with("\\'"), "Should start with escaped quote");
+    }
+
+    #[test]
+    fn test_escape_string_null_character() {
+        let input = "text\0with\0nulls";
+        let escaped = escape_string(input);
+        assert!(escaped.contains("\\0"), "NULL characters should be escaped");
+    }
+
+    #[test]
+    fn test_escape_string_edge_cases() {
+        // Empty string
+        assert_eq!(escape_string(""), "");
+
+        // Only special characters
+        assert_eq!(escape_string("'\n\r\t"), "\\'\\n\\r\\t");
+
+        // Unicode (should pass through)
+        let unicode = "Hello 世界 🌍";
+        let escaped = escape_string(unicode);
+        assert!(escaped.contains("世界"));
+        assert!(escaped.contains("🌍"));
+    }
+
+    #[test]
+    fn test_escape_string_double_quote() {
+        let input = "String with \"double quotes\"";
+        let escaped = escape_string(input);
+        assert!(escaped.contains("\\\""), "Double quotes should be escaped");
+    }
+
+    #[test]
+    fn test_escape_string_backslash_before_quote() {
+        // Critical test: backslash before quote should escape both
+        let input = "O'Reilly\\'; malicious";
+        let escaped = escape_string(input);
+
+        // Both backslash and quote should be escaped
+        assert!(escaped.contains("\\\\"), "Backslash should be escaped");
+        assert!(escaped.contains("\\'"), "Quote should be escaped");
+
+        // Verify the backslash doesn't neutralize the quote escape
+        assert!(escaped.contains("O\\'Reilly\\\\"), "Should have escaped quote then escaped backslash");
+    }
+}
@@ -1,9 +1,12 @@
 use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Utc};
+use serde_json::json;
+use chrono::{DateTime, NaiveDate, Utc};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
 use uuid::Uuid;
 
-/// Graph schema initialization queries for GraphLite
-/// This defines the Context Graph structure for agentic memory
+// Graph schema initialization queries for GraphLite
+// This defines the Context Graph structure for agentic memory
 
 /// Node type: Conversation
 /// Represents a conversation session with the agent
@@ -14,6 +17,17 @@ pub struct Conversation {
     pub title: Option<String>,
 }
 
+/// Summary of a conversation for listings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationSummary {
+    pub id: String,
+    pub title: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub message_count: usize,
+    /// Timestamp of the newest message, or `started_at` if there are none
+    pub last_activity: DateTime<Utc>,
+}
+
 /// Node type: Message
 /// Represents a single message in a conversation
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,13 +54,52 @@ pub struct Topic {
     pub category: Option<String>,
 }
 
+/// Lifecycle state of a task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Pending,
+    InProgress,
+    Completed,
+}
+
+impl TaskStatus {
+    /// Value stored in the `status` property
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskStatus::Pending => "pending",
+            TaskStatus::InProgress => "in_progress",
+            TaskStatus::Completed => "completed",
+        }
+    }
+
+    /// Parse a stored or user-typed status ("in-progress" is accepted too)
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().replace('-', "_").as_str() {
+            "pending" => Some(TaskStatus::Pending),
+            "in_progress" => Some(TaskStatus::InProgress),
+            "completed" | "done" => Some(TaskStatus::Completed),
+            _ => None,
+        }
+    }
+
+    /// Whether the task still needs work
+    pub fn is_open(&self) -> bool {
+        !matches!(self, TaskStatus::Completed)
+    }
+}
+
 /// Node type: Task
 /// Represents an action item or work task
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
+    pub id: String,
     pub description: String,
-    pub status: String, // "pending", "in_progress", "completed"
+    pub status: TaskStatus,
     pub created_at: DateTime<Utc>,
+    pub due_date: Option<NaiveDate>,
+    /// Name of the `Person` linked through an `ASSIGNED_TO` edge
+    pub assignee: Option<String>,
 }
 
 /// Node type: Document
@@ -58,41 +111,296 @@ pub struct Document {
     pub doc_type: String, // "file", "link", "reference"
 }
 
-/// Extracted entities from a message
+impl Document {
+    /// Build a document from an extracted mention such as
+    /// `"Q3 roadmap (https://example.com/roadmap)"` or `"design.md"`
+    ///
+    /// A URL anywhere in the mention becomes `url` and makes this a "link";
+    /// the remaining text (or the URL itself) is the title. Mentions that look
+    /// like a file name are "file", anything else is a "reference".
+    pub fn from_mention(mention: &str) -> Self {
+        let mention = mention.trim();
+
+        let url = mention
+            .split_whitespace()
+            .map(|token| token.trim_matches(|c: char| "()[]<>\"',;".contains(c)))
+            .find(|token| token.starts_with("http://") || token.starts_with("https://") || token.starts_with("www."))
+            .map(|token| token.trim_end_matches(['.', ':']).to_string());
+
+        if let Some(url) = url {
+            let title = mention
+                .replace(&url, "")
+                .trim_matches(|c: char| c.is_whitespace() || "()[]<>\"',;:.-".contains(c))
+                .to_string();
+            return Self {
+                title: if title.is_empty() { url.clone() } else { title },
+                url: Some(url),
+                doc_type: "link".to_string(),
+            };
+        }
+
+        let doc_type = if looks_like_file_name(mention) { "file" } else { "reference" };
+        Self {
+            title: mention.to_string(),
+            url: None,
+            doc_type: doc_type.to_string(),
+        }
+    }
+}
+
+/// Whether the last word of `text` has a short file extension, e.g. `notes.md`
+fn looks_like_file_name(text: &str) -> bool {
+    let Some(last_word) = text.split_whitespace().last() else {
+        return false;
+    };
+    match last_word.rsplit_once('.') {
+        Some((stem, extension)) => {
+            !stem.is_empty()
+                && (1..=5).contains(&extension.len())
+                && extension.chars().all(|c| c.is_ascii_alphanumeric())
+                && extension.chars().any(|c| c.is_ascii_alphabetic())
+        }
+        None => false,
+    }
+}
+
+/// Kinds of entity node extracted from messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EntityKind {
+    Person,
+    Topic,
+    Task,
+    Document,
+}
+
+impl EntityKind {
+    /// Node label in the graph
+    pub fn label(&self) -> &'static str {
+        match self {
+            EntityKind::Person => "Person",
+            EntityKind::Topic => "Topic",
+            EntityKind::Task => "Task",
+            EntityKind::Document => "Document",
+        }
+    }
+
+    /// Property holding the entity's display name
+    pub fn name_property(&self) -> &'static str {
+        match self {
+            EntityKind::Person | EntityKind::Topic => "name",
+            EntityKind::Task => "description",
+            EntityKind::Document => "title",
+        }
+    }
+
+    /// Parse a node label
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label {
+            "Person" => Some(EntityKind::Person),
+            "Topic" => Some(EntityKind::Topic),
+            "Task" => Some(EntityKind::Task),
+            "Document" => Some(EntityKind::Document),
+            _ => None,
+        }
+    }
+
+    /// Parse a user-typed kind, singular or plural ("person", "Topics")
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "person" | "people" => Some(EntityKind::Person),
+            "topic" | "topics" => Some(EntityKind::Topic),
+            "task" | "tasks" => Some(EntityKind::Task),
+            "document" | "documents" => Some(EntityKind::Document),
+            _ => None,
+        }
+    }
+}
+
+/// Normalize a Person or Topic name for matching
+///
+/// Case-folds, strips accents and compatibility forms (`"José"` matches
+/// `"jose"`, `"Ｒｕｓｔ"` matches `"rust"`), collapses whitespace and trims
+/// surrounding punctuation. Names normalizing to the same key are the same entity.
+pub fn normalize_entity_name(name: &str) -> String {
+    let folded: String = name
+        .nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .collect::<String>()
+        .to_lowercase();
+
+    folded
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_matches(|c: char| "\"'`.,;:!?()[]{}".contains(c))
+        .trim()
+        .to_string()
+}
+
+/// Node type: Alias
+/// Alternative name resolving to a canonical Person or Topic
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntityAlias {
+    pub kind: EntityKind,
+    /// Name as it was written when the alias was created
+    pub alias: String,
+    /// `name` of the node the alias resolves to
+    pub canonical: String,
+}
+
+/// Outcome of merging one entity node into another
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MergeReport {
+    pub kind: EntityKind,
+    pub alias: String,
+    pub canonical: String,
+    /// `MENTIONED_IN` edges moved to the canonical node
+    pub mentions_moved: usize,
+    /// Relationship edges (`WORKS_ON`, `ASSIGNED_TO`, ...) moved to the canonical node
+    pub relations_moved: usize,
+}
+
+/// Suggested merge of several names into one canonical entity, pending confirmation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MergeProposal {
+    pub kind: EntityKind,
+    pub canonical: String,
+    pub aliases: Vec<String>,
+}
+
+/// Reference to an entity node by kind and display name
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct EntityRef {
+    pub kind: EntityKind,
+    pub name: String,
+}
+
+impl EntityRef {
+    pub fn new(kind: EntityKind, name: impl Into<String>) -> Self {
+        Self { kind, name: name.into() }
+    }
+}
+
+/// A message that mentions an entity, with the conversation it belongs to
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityMention {
+    pub message_id: String,
+    pub role: String,
+    pub content: String,
+    pub timestamp: DateTime<Utc>,
+    pub conversation_id: String,
+    pub conversation_title: Option<String>,
+}
+
+/// Entity mentioned in the same message as another entity
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoMention {
+    pub entity: EntityRef,
+    pub message_id: String,
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+/// Entity connected to another through a typed relationship edge
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelatedEntity {
+    /// Edge label, e.g. `WORKS_ON`
+    pub relation: String,
+    /// Whether the edge points away from the entity that was queried
+    pub outgoing: bool,
+    pub entity: EntityRef,
+    /// Message the relation was extracted from
+    pub message_id: Option<String>,
+}
+
+/// Relationship types extracted between entities unless configured otherwise
+pub const DEFAULT_RELATION_TYPES: [&str; 3] = ["WORKS_ON", "KNOWS", "DEPENDS_ON"];
+
+/// Typed relationship between two extracted entities,
+/// e.g. `Alice WORKS_ON GraphQL migration`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExtractedRelation {
+    pub subject: String,
+    /// Relationship type, one of the configured vocabulary (e.g. `WORKS_ON`)
+    pub predicate: String,
+    pub object: String,
+}
+
+/// Normalize a relationship type to an edge label like `WORKS_ON`
+///
+/// Returns `None` unless the result is a plain identifier (letters, digits
+/// and underscores, starting with a letter), since edge labels cannot be
+/// bound as query parameters.
+pub fn normalize_relation_type(value: &str) -> Option<String> {
+    let normalized = value.trim().to_uppercase().replace([' ', '-'], "_");
+    let mut chars = normalized.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    valid.then_some(normalized)
+}
+
+/// Extracted entities from a message
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ExtractedEntities {
     pub people: Vec<String>,
     pub topics: Vec<String>,
     pub tasks: Vec<String>,
     pub documents: Vec<String>,
+    /// Existing tasks the message reports as finished
+    #[serde(default)]
+    pub completed_tasks: Vec<String>,
+    /// Typed relationships between the entities above
+    #[serde(default)]
+    pub relations: Vec<ExtractedRelation>,
 }
 
-impl Default for ExtractedEntities {
-    fn default() -> Self {
-        Self {
-            people: Vec::new(),
-            topics: Vec::new(),
-            tasks: Vec::new(),
-            documents: Vec::new(),
-        }
-    }
-}
-
-/// Schema initialization for GraphLite database
-/// Creates node labels and constraints
-pub fn get_schema_init_queries() -> Vec<String> {
-    vec![
-        // Note: GraphLite uses GQL standard, which may have different syntax
-        // These are conceptual queries that may need adjustment based on actual GraphLite GQL support
+impl ExtractedEntities {
+    /// JSON schema of the extraction result, used as the input schema of the
+    /// extraction tool
+    ///
+    /// Relation predicates are limited to `relation_types`; with no types,
+    /// relations must be empty.
+    pub fn json_schema(relation_types: &[String]) -> serde_json::Value {
+        let names = |description: &str| {
+            json!({
+                "type": "array",
+                "items": {"type": "string"},
+                "description": description
+            })
+        };
 
-        // Create unique constraints (if supported)
-        // In GQL, we typically just use INSERT to create nodes
-        // Constraints might be handled differently
+        let relations = if relation_types.is_empty() {
+            json!({"type": "array", "maxItems": 0})
+        } else {
+            json!({
+                "type": "array",
+                "description": "Typed relationships stated in the message, between the entities above",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "subject": {"type": "string"},
+                        "predicate": {"type": "string", "enum": relation_types},
+                        "object": {"type": "string"}
+                    },
+                    "required": ["subject", "predicate", "object"],
+                    "additionalProperties": false
+                }
+            })
+        };
 
-        String::from("// Schema initialization"),
-        String::from("// Nodes will be created dynamically during operation"),
-        String::from("// GraphLite uses ISO GQL standard for queries"),
-    ]
+        json!({
+            "type": "object",
+            "properties": {
+                "people": names("Names of individuals mentioned"),
+                "topics": names("Subjects, concepts, technologies, projects, or areas of interest"),
+                "tasks": names("Action items or work still to be done, in imperative form"),
+                "documents": names("Files, links, or references, with URLs and file names kept verbatim"),
+                "completed_tasks": names("Work the user reports as finished, in imperative form"),
+                "relations": relations
+            },
+            "required": ["people", "topics", "tasks", "documents", "completed_tasks", "relations"],
+            "additionalProperties": false
+        })
+    }
 }
 
 /// Helper to generate a new UUID as string
@@ -104,3 +412,108 @@ pub fn new_id() -> String {
 pub fn now() -> DateTime<Utc> {
     Utc::now()
 }
+
+/// Helper to parse an RFC 3339 timestamp as stored on nodes
+pub fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|ts| ts.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document_from_mention_url_with_title() {
+        let doc = Document::from_mention("Q3 roadmap (https://example.com/roadmap)");
+        assert_eq!(doc.title, "Q3 roadmap");
+        assert_eq!(doc.url.as_deref(), Some("https://example.com/roadmap"));
+        assert_eq!(doc.doc_type, "link");
+    }
+
+    #[test]
+    fn test_document_from_mention_bare_url() {
+        let doc = Document::from_mention("https://example.com/spec.pdf.");
+        assert_eq!(doc.title, "https://example.com/spec.pdf");
+        assert_eq!(doc.url.as_deref(), Some("https://example.com/spec.pdf"));
+        assert_eq!(doc.doc_type, "link");
+    }
+
+    #[test]
+    fn test_document_from_mention_file_and_reference() {
+        let file = Document::from_mention("design.md");
+        assert_eq!(file.title, "design.md");
+        assert_eq!(file.url, None);
+        assert_eq!(file.doc_type, "file");
+
+        let reference = Document::from_mention("The Pragmatic Programmer");
+        assert_eq!(reference.doc_type, "reference");
+
+        // Version numbers are not file extensions
+        assert_eq!(Document::from_mention("RFC v2.0").doc_type, "reference");
+    }
+
+    #[test]
+    fn test_extracted_entities_schema_matches_struct() {
+        let schema = ExtractedEntities::json_schema(&["WORKS_ON".to_string()]);
+
+        let fields = serde_json::to_value(ExtractedEntities::default()).unwrap();
+        let mut expected: Vec<&String> = fields.as_object().unwrap().keys().collect();
+        let mut properties: Vec<&String> = schema["properties"].as_object().unwrap().keys().collect();
+        expected.sort();
+        properties.sort();
+        assert_eq!(properties, expected);
+
+        let relation = serde_json::to_value(ExtractedRelation {
+            subject: String::new(),
+            predicate: String::new(),
+            object: String::new(),
+        })
+        .unwrap();
+        let relation_properties = &schema["properties"]["relations"]["items"]["properties"];
+        for field in relation.as_object().unwrap().keys() {
+            assert!(relation_properties.get(field).is_some(), "missing {}", field);
+        }
+        assert_eq!(relation_properties["predicate"]["enum"], json!(["WORKS_ON"]));
+    }
+
+    #[test]
+    fn test_extracted_entities_schema_without_relation_types() {
+        let schema = ExtractedEntities::json_schema(&[]);
+        assert_eq!(schema["properties"]["relations"]["maxItems"], 0);
+    }
+
+    #[test]
+    fn test_normalize_entity_name() {
+        assert_eq!(normalize_entity_name("Alice"), "alice");
+        assert_eq!(normalize_entity_name("  alice\t"), "alice");
+        assert_eq!(normalize_entity_name("Alice   Chen"), "alice chen");
+        assert_eq!(normalize_entity_name("José Núñez"), "jose nunez");
+        assert_eq!(normalize_entity_name("Ｒｕｓｔ"), "rust");
+        assert_eq!(normalize_entity_name("\"GraphQL\"."), "graphql");
+        // Inner punctuation is meaningful
+        assert_eq!(normalize_entity_name("C++"), "c++");
+        assert_ne!(normalize_entity_name("C#"), normalize_entity_name("C"));
+        assert_eq!(normalize_entity_name(" ?! "), "");
+    }
+
+    #[test]
+    fn test_normalize_relation_type() {
+        assert_eq!(normalize_relation_type("works on").as_deref(), Some("WORKS_ON"));
+        assert_eq!(normalize_relation_type("depends-on").as_deref(), Some("DEPENDS_ON"));
+        assert_eq!(normalize_relation_type(" KNOWS ").as_deref(), Some("KNOWS"));
+        assert_eq!(normalize_relation_type("KNOWS]->(x"), None);
+        assert_eq!(normalize_relation_type("2FA"), None);
+        assert_eq!(normalize_relation_type(""), None);
+    }
+
+    #[test]
+    fn test_parse_entity_kind() {
+        assert_eq!(EntityKind::parse("person"), Some(EntityKind::Person));
+        assert_eq!(EntityKind::parse("People"), Some(EntityKind::Person));
+        assert_eq!(EntityKind::parse(" topics "), Some(EntityKind::Topic));
+        assert_eq!(EntityKind::parse("document"), Some(EntityKind::Document));
+        assert_eq!(EntityKind::parse("alias"), None);
+    }
+}
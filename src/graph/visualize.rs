@@ -0,0 +1,317 @@
+//! Rendering of the memory graph for Graphviz (DOT) and Gephi (GraphML)
+
+use anyhow::Result;
+use std::collections::{HashSet, VecDeque};
+use std::str::FromStr;
+
+use super::export::{GraphSnapshot, SnapshotNode};
+
+/// Longest message excerpt used as a node label
+const MESSAGE_LABEL_CHARS: usize = 40;
+
+/// Output format for [`render_graph`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Dot,
+    GraphMl,
+}
+
+impl FromStr for GraphFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "dot" | "gv" => Ok(GraphFormat::Dot),
+            "graphml" => Ok(GraphFormat::GraphMl),
+            other => anyhow::bail!("Unknown graph format '{}' (expected dot or graphml)", other),
+        }
+    }
+}
+
+impl GraphSnapshot {
+    /// Nodes within `depth` hops of `start` (in either direction) and the
+    /// edges between them
+    pub fn neighborhood(&self, start: &str, depth: usize) -> Result<GraphSnapshot> {
+        if !self.nodes.iter().any(|node| node.id == start) {
+            anyhow::bail!("No node '{}' in the graph", start);
+        }
+
+        let mut included: HashSet<&str> = HashSet::from([start]);
+        let mut queue = VecDeque::from([(start, 0)]);
+        while let Some((id, hops)) = queue.pop_front() {
+            if hops == depth {
+                continue;
+            }
+            for edge in &self.edges {
+                let neighbor = if edge.from == id {
+                    edge.to.as_str()
+                } else if edge.to == id {
+                    edge.from.as_str()
+                } else {
+                    continue;
+                };
+                if included.insert(neighbor) {
+                    queue.push_back((neighbor, hops + 1));
+                }
+            }
+        }
+
+        Ok(GraphSnapshot {
+            nodes: self
+                .nodes
+                .iter()
+                .filter(|node| included.contains(node.id.as_str()))
+                .cloned()
+                .collect(),
+            edges: self
+                .edges
+                .iter()
+                .filter(|edge| included.contains(edge.from.as_str()) && included.contains(edge.to.as_str()))
+                .cloned()
+                .collect(),
+        })
+    }
+}
+
+/// Render a snapshot in the given format
+///
+/// Alias nodes are bookkeeping rather than memory and are left out.
+pub fn render_graph(snapshot: &GraphSnapshot, format: GraphFormat) -> String {
+    match format {
+        GraphFormat::Dot => render_dot(snapshot),
+        GraphFormat::GraphMl => render_graphml(snapshot),
+    }
+}
+
+/// Graphviz `digraph`, one shape per node label
+fn render_dot(snapshot: &GraphSnapshot) -> String {
+    let mut output = String::from("digraph memory {\n    rankdir=LR;\n    node [fontname=\"Helvetica\"];\n");
+
+    for node in visible_nodes(snapshot) {
+        let shape = match node.label.as_str() {
+            "Person" => "ellipse",
+            "Topic" => "box",
+            "Task" => "note",
+            "Document" => "folder",
+            "Conversation" => "doubleoctagon",
+            _ => "plaintext",
+        };
+        output.push_str(&format!(
+            "    \"{}\" [label=\"{}\", shape={}, type=\"{}\"];\n",
+            dot_escape(&node.id),
+            dot_escape(&display_label(node)),
+            shape,
+            node.label
+        ));
+    }
+    for edge in &snapshot.edges {
+        output.push_str(&format!(
+            "    \"{}\" -> \"{}\" [label=\"{}\"];\n",
+            dot_escape(&edge.from),
+            dot_escape(&edge.to),
+            edge.label
+        ));
+    }
+
+    output.push_str("}\n");
+    output
+}
+
+/// GraphML document with `label` and `type` attributes on nodes and a
+/// `label` attribute on edges, as Gephi expects
+fn render_graphml(snapshot: &GraphSnapshot) -> String {
+    let mut output = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n  \
+         <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n  \
+         <key id=\"type\" for=\"node\" attr.name=\"type\" attr.type=\"string\"/>\n  \
+         <key id=\"edge_label\" for=\"edge\" attr.name=\"label\" attr.type=\"string\"/>\n  \
+         <graph id=\"memory\" edgedefault=\"directed\">\n",
+    );
+
+    for node in visible_nodes(snapshot) {
+        output.push_str(&format!(
+            "    <node id=\"{}\"><data key=\"label\">{}</data><data key=\"type\">{}</data></node>\n",
+            xml_escape(&node.id),
+            xml_escape(&display_label(node)),
+            node.label
+        ));
+    }
+    for (index, edge) in snapshot.edges.iter().enumerate() {
+        output.push_str(&format!(
+            "    <edge id=\"e{}\" source=\"{}\" target=\"{}\"><data key=\"edge_label\">{}</data></edge>\n",
+            index,
+            xml_escape(&edge.from),
+            xml_escape(&edge.to),
+            edge.label
+        ));
+    }
+
+    output.push_str("  </graph>\n</graphml>\n");
+    output
+}
+
+/// Nodes worth drawing
+fn visible_nodes(snapshot: &GraphSnapshot) -> impl Iterator<Item = &SnapshotNode> {
+    snapshot.nodes.iter().filter(|node| node.label != "Alias")
+}
+
+/// Text shown on a node: the name of people and topics, a task's
+/// description, a document or conversation title, or a message excerpt
+fn display_label(node: &SnapshotNode) -> String {
+    let text = |property: &str| node.properties.get(property).and_then(|value| value.as_str());
+
+    let label = match node.label.as_str() {
+        "Person" | "Topic" => text("name"),
+        "Task" => text("description"),
+        "Document" => text("title").or_else(|| text("url")),
+        "Conversation" => text("title"),
+        "Message" => {
+            return match (text("role"), text("content")) {
+                (Some(role), Some(content)) => format!("{}: {}", role, excerpt(content)),
+                _ => node.id.clone(),
+            }
+        }
+        _ => None,
+    };
+    label.map(String::from).unwrap_or_else(|| node.id.clone())
+}
+
+/// First line of `text`, shortened to [`MESSAGE_LABEL_CHARS`]
+fn excerpt(text: &str) -> String {
+    let line = text.lines().next().unwrap_or_default().trim();
+    if line.chars().count() <= MESSAGE_LABEL_CHARS {
+        return line.to_string();
+    }
+    let short: String = line.chars().take(MESSAGE_LABEL_CHARS).collect();
+    format!("{}...", short.trim_end())
+}
+
+/// Escape a DOT double-quoted string
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Escape XML text and attribute values, dropping characters XML 1.0 forbids
+fn xml_escape(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&apos;"),
+            '\t' | '\n' | '\r' => output.push(ch),
+            c if c.is_control() => {}
+            c => output.push(c),
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::export::SnapshotEdge;
+    use serde_json::json;
+
+    fn node(id: &str, label: &str, properties: serde_json::Value) -> SnapshotNode {
+        SnapshotNode {
+            id: id.to_string(),
+            label: label.to_string(),
+            properties: properties.as_object().unwrap().clone(),
+        }
+    }
+
+    fn edge(label: &str, from: &str, to: &str) -> SnapshotEdge {
+        SnapshotEdge {
+            label: label.to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+            properties: Default::default(),
+        }
+    }
+
+    fn sample() -> GraphSnapshot {
+        GraphSnapshot {
+            nodes: vec![
+                node("conversation:c1", "Conversation", json!({"id": "c1", "title": "Planning"})),
+                node("message:m1", "Message", json!({"id": "m1", "role": "user", "content": "Alice \"Al\" works on Rust"})),
+                node("person:Alice", "Person", json!({"name": "Alice"})),
+                node("topic:Rust", "Topic", json!({"name": "Rust"})),
+                node("task:t1", "Task", json!({"id": "t1", "description": "Review <schema> & docs"})),
+                node("alias:Person:al", "Alias", json!({"kind": "Person", "key": "al", "canonical": "Alice"})),
+            ],
+            edges: vec![
+                edge("PART_OF", "message:m1", "conversation:c1"),
+                edge("MENTIONED_IN", "person:Alice", "message:m1"),
+                edge("MENTIONED_IN", "topic:Rust", "message:m1"),
+                edge("WORKS_ON", "person:Alice", "topic:Rust"),
+                edge("ASSIGNED_TO", "task:t1", "person:Alice"),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_render_dot() {
+        let dot = render_graph(&sample(), GraphFormat::Dot);
+
+        assert!(dot.starts_with("digraph memory {\n"));
+        assert!(dot.contains("    \"person:Alice\" [label=\"Alice\", shape=ellipse, type=\"Person\"];\n"));
+        assert!(dot.contains("[label=\"user: Alice \\\"Al\\\" works on Rust\", shape=plaintext, type=\"Message\"]"));
+        assert!(dot.contains("[label=\"Review <schema> & docs\", shape=note, type=\"Task\"]"));
+        assert!(dot.contains("    \"person:Alice\" -> \"topic:Rust\" [label=\"WORKS_ON\"];\n"));
+        assert!(!dot.contains("alias:"));
+        assert!(dot.ends_with("}\n"));
+    }
+
+    #[test]
+    fn test_render_graphml() {
+        let graphml = render_graph(&sample(), GraphFormat::GraphMl);
+
+        assert!(graphml.contains(
+            "<node id=\"task:t1\"><data key=\"label\">Review &lt;schema&gt; &amp; docs</data><data key=\"type\">Task</data></node>"
+        ));
+        assert!(graphml.contains(
+            "<edge id=\"e3\" source=\"person:Alice\" target=\"topic:Rust\"><data key=\"edge_label\">WORKS_ON</data></edge>"
+        ));
+        assert!(graphml.contains("user: Alice &quot;Al&quot; works on Rust"));
+        assert!(graphml.trim_end().ends_with("</graphml>"));
+    }
+
+    #[test]
+    fn test_neighborhood() {
+        let snapshot = sample();
+        let ids = |subgraph: &GraphSnapshot| subgraph.nodes.iter().map(|n| n.id.clone()).collect::<Vec<_>>();
+
+        let around_rust = snapshot.neighborhood("topic:Rust", 1).unwrap();
+        assert_eq!(ids(&around_rust), vec!["message:m1", "person:Alice", "topic:Rust"]);
+        assert_eq!(around_rust.edges.len(), 3);
+
+        // Entities are two hops from their conversation, through messages
+        let conversation = snapshot.neighborhood("conversation:c1", 2).unwrap();
+        assert_eq!(
+            ids(&conversation),
+            vec!["conversation:c1", "message:m1", "person:Alice", "topic:Rust"]
+        );
+
+        assert!(snapshot.neighborhood("person:Nobody", 1).is_err());
+    }
+
+    #[test]
+    fn test_excerpt() {
+        assert_eq!(excerpt("Short\nsecond line"), "Short");
+        assert_eq!(
+            excerpt("This message is definitely longer than forty characters"),
+            "This message is definitely longer than f..."
+        );
+    }
+
+    #[test]
+    fn test_parse_graph_format() {
+        assert_eq!("DOT".parse::<GraphFormat>().unwrap(), GraphFormat::Dot);
+        assert_eq!("graphml".parse::<GraphFormat>().unwrap(), GraphFormat::GraphMl);
+        assert!("svg".parse::<GraphFormat>().is_err());
+    }
+}
@@ -0,0 +1,55 @@
+//! Agentic Memory: a context graph memory for LLM assistants, backed by GraphLite
+//!
+//! Messages are stored as a graph of conversations, people, topics, tasks and
+//! documents. [`AgenticMemory`] ties the pieces together: it extracts entities
+//! from each user message with an [`EntityExtractor`], records them in the
+//! [`GraphDB`], and answers with a [`ChatModel`] whose prompt carries context
+//! gathered by the [`ContextRetriever`]. [`LLMClient`] is the built-in
+//! [`ChatModel`] for hosted and local providers; [`ScriptedModel`] answers
+//! with canned replies for deterministic tests.
+//!
+//! ```no_run
+//! use agentic_memory::{AgenticMemory, LLMClient, LLMProvider};
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let client = LLMClient::new(LLMProvider::Anthropic {
+//!     api_key: std::env::var("ANTHROPIC_API_KEY")?,
+//!     model: "claude-3-5-sonnet-20241022".to_string(),
+//! });
+//! let mut memory = AgenticMemory::new("./data/memory.db", "admin", "admin123", client.clone(), client).await?;
+//!
+//! let session = memory.session("admin", "admin123")?;
+//! memory.start_conversation(&session, Some("Planning".to_string()))?;
+//! let (_, entities) = memory.process_user_message(&session, "Alice owns the GraphLite migration").await?;
+//! let reply = memory.generate_response(&session, "Who owns the migration?", &entities).await?;
+//! memory.store_assistant_message(&session, &reply)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Features
+//!
+//! - `cli`: the `agentic-memory` binary with its REPL and subcommands
+//! - `server`: [`server`], the HTTP JSON API and OpenAI-compatible endpoint
+//! - `mcp`: [`mcp`], the Model Context Protocol server on stdio
+//! - `anthropic`, `openai`, `openrouter`, `local`: the [`LLMProvider`] variants
+//!
+//! All are enabled by default. At least one provider must be enabled.
+
+#[cfg(not(any(feature = "anthropic", feature = "openai", feature = "openrouter", feature = "local")))]
+compile_error!("enable at least one LLM provider feature: anthropic, openai, openrouter or local");
+
+pub mod agent;
+pub mod graph;
+pub mod llm;
+#[cfg(feature = "mcp")]
+pub mod mcp;
+#[cfg(feature = "server")]
+pub mod server;
+
+pub use agent::{AgenticMemory, ContextRetriever};
+pub use graph::{ExtractedEntities, GraphDB};
+pub use llm::{
+    ChatMessage, ChatModel, ChatRole, EntityExtractor, LLMClient, LLMError, LLMProvider, RetryPolicy, ScriptedModel,
+    ScriptedReply,
+};
@@ -1,19 +1,156 @@
 use anyhow::{Context, Result};
+use futures::stream::{self, Stream, StreamExt};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::time::Duration;
+
+use super::error::LLMError;
+use super::retry::{parse_retry_after, RetryPolicy};
+
+/// Default time allowed for a response (or, when streaming, for its headers)
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Time allowed to establish a connection
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// LLM provider type
 #[derive(Debug, Clone)]
 pub enum LLMProvider {
+    #[cfg(feature = "openai")]
     OpenAI { api_key: String, model: String },
+    #[cfg(feature = "anthropic")]
     Anthropic { api_key: String, model: String },
+    #[cfg(feature = "openrouter")]
     OpenRouter {
         api_key: String,
         model: String,
         app_name: Option<String>,
         site_url: Option<String>,
     },
+    /// Any server speaking the OpenAI chat completions API, such as Ollama,
+    /// llama.cpp or vLLM running locally
+    #[cfg(feature = "local")]
+    OpenAICompatible {
+        /// API root, e.g. `http://localhost:11434/v1`
+        base_url: String,
+        api_key: Option<String>,
+        model: String,
+    },
+}
+
+impl LLMProvider {
+    /// Model requests are sent to
+    pub fn model(&self) -> &str {
+        match self {
+            #[cfg(feature = "openai")]
+            LLMProvider::OpenAI { model, .. } => model,
+            #[cfg(feature = "anthropic")]
+            LLMProvider::Anthropic { model, .. } => model,
+            #[cfg(feature = "openrouter")]
+            LLMProvider::OpenRouter { model, .. } => model,
+            #[cfg(feature = "local")]
+            LLMProvider::OpenAICompatible { model, .. } => model,
+        }
+    }
+}
+
+/// Author of a turn in a chat-style request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatRole {
+    User,
+    Assistant,
+}
+
+impl ChatRole {
+    /// Parse a role as stored on `Message` nodes ("user" or "assistant")
+    pub fn parse(role: &str) -> Option<Self> {
+        match role {
+            "user" => Some(ChatRole::User),
+            "assistant" => Some(ChatRole::Assistant),
+            _ => None,
+        }
+    }
+}
+
+/// A single turn in a chat-style request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: ChatRole,
+    pub content: String,
+}
+
+impl ChatMessage {
+    /// Create a user turn
+    pub fn user(content: impl Into<String>) -> Self {
+        Self { role: ChatRole::User, content: content.into() }
+    }
+
+    /// Create an assistant turn
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self { role: ChatRole::Assistant, content: content.into() }
+    }
+}
+
+/// Stream of text deltas produced by a streaming completion
+pub type TextStream = Pin<Box<dyn Stream<Item = Result<String>> + Send>>;
+
+/// Tool the model can call, described by a JSON schema for its input
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolDefinition {
+    pub name: String,
+    pub description: String,
+    /// JSON schema of the tool's input object
+    pub input_schema: serde_json::Value,
+}
+
+/// Tool call requested by the model
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolCall {
+    /// Provider-assigned id that the result must refer to
+    pub id: String,
+    pub name: String,
+    pub arguments: serde_json::Value,
+}
+
+/// Output of a tool call, sent back to the model
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolResult {
+    pub call_id: String,
+    pub content: String,
+    /// Whether the tool failed; `content` then describes the error
+    pub is_error: bool,
+}
+
+/// A turn of a conversation in which the model may call tools
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToolTurn {
+    Message(ChatMessage),
+    /// Assistant turn requesting tool calls, with any text written alongside
+    ToolCalls { text: String, calls: Vec<ToolCall> },
+    /// Results of the calls in the preceding `ToolCalls` turn
+    ToolResults(Vec<ToolResult>),
+}
+
+/// Reply to a request that offered tools
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToolReply {
+    /// Final answer
+    Text(String),
+    /// The model wants these tools run before it continues
+    ToolCalls { text: String, calls: Vec<ToolCall> },
+}
+
+/// Reply to a request that asked the model to call a tool
+#[derive(Debug, Clone, PartialEq)]
+pub enum StructuredOutput {
+    /// Arguments the model passed to the tool
+    Json(serde_json::Value),
+    /// The model answered in prose instead of calling the tool
+    Text(String),
 }
 
 /// LLM client for making API calls
@@ -21,54 +158,117 @@ pub enum LLMProvider {
 pub struct LLMClient {
     provider: LLMProvider,
     http_client: Client,
+    retry_policy: RetryPolicy,
+    timeout: Duration,
 }
 
+#[cfg(feature = "anthropic")]
 #[derive(Debug, Serialize, Deserialize)]
-struct AnthropicRequest {
-    model: String,
-    max_tokens: u32,
-    messages: Vec<AnthropicMessage>,
+struct AnthropicResponse {
+    content: Vec<AnthropicContent>,
 }
 
+/// Content block of an Anthropic response: `text` or `tool_use`
+#[cfg(feature = "anthropic")]
 #[derive(Debug, Serialize, Deserialize)]
-struct AnthropicMessage {
-    role: String,
-    content: String,
+struct AnthropicContent {
+    #[serde(rename = "type", default)]
+    kind: Option<String>,
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    input: Option<serde_json::Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct AnthropicResponse {
-    content: Vec<AnthropicContent>,
+/// Anthropic server-sent event payload (the `type` field selects the variant)
+#[cfg(feature = "anthropic")]
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AnthropicStreamEvent {
+    ContentBlockDelta { delta: AnthropicDelta },
+    MessageStop,
+    Error { error: AnthropicError },
+    #[serde(other)]
+    Other,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct AnthropicContent {
-    text: String,
+#[cfg(feature = "anthropic")]
+#[derive(Debug, Deserialize)]
+struct AnthropicDelta {
+    #[serde(default)]
+    text: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct OpenAIRequest {
-    model: String,
-    messages: Vec<OpenAIMessage>,
-    temperature: f32,
+#[cfg(feature = "anthropic")]
+#[derive(Debug, Deserialize)]
+struct AnthropicError {
+    message: String,
 }
 
+#[cfg(any(feature = "openai", feature = "openrouter", feature = "local"))]
 #[derive(Debug, Serialize, Deserialize)]
 struct OpenAIMessage {
     role: String,
-    content: String,
+    /// `null` when the model answers with tool calls only
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<OpenAIToolCall>,
+}
+
+#[cfg(any(feature = "openai", feature = "openrouter", feature = "local"))]
+#[derive(Debug, Serialize, Deserialize)]
+struct OpenAIToolCall {
+    #[serde(default)]
+    id: String,
+    function: OpenAIFunctionCall,
 }
 
+#[cfg(any(feature = "openai", feature = "openrouter", feature = "local"))]
+#[derive(Debug, Serialize, Deserialize)]
+struct OpenAIFunctionCall {
+    name: String,
+    /// JSON-encoded arguments
+    arguments: String,
+}
+
+#[cfg(any(feature = "openai", feature = "openrouter", feature = "local"))]
 #[derive(Debug, Serialize, Deserialize)]
 struct OpenAIResponse {
     choices: Vec<OpenAIChoice>,
 }
 
+#[cfg(any(feature = "openai", feature = "openrouter", feature = "local"))]
 #[derive(Debug, Serialize, Deserialize)]
 struct OpenAIChoice {
     message: OpenAIMessage,
 }
 
+/// OpenAI `stream: true` chunk (also used by OpenRouter)
+#[cfg(any(feature = "openai", feature = "openrouter", feature = "local"))]
+#[derive(Debug, Deserialize)]
+struct OpenAIStreamChunk {
+    #[serde(default)]
+    choices: Vec<OpenAIStreamChoice>,
+}
+
+#[cfg(any(feature = "openai", feature = "openrouter", feature = "local"))]
+#[derive(Debug, Deserialize)]
+struct OpenAIStreamChoice {
+    delta: OpenAIDelta,
+}
+
+#[cfg(any(feature = "openai", feature = "openrouter", feature = "local"))]
+#[derive(Debug, Deserialize)]
+struct OpenAIDelta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
 /// Common structure for LLM API requests
 struct LLMRequest {
     url: String,
@@ -94,187 +294,775 @@ impl LLMRequest {
         self.body = body;
         self
     }
+
+    /// Ask the provider to stream the response as server-sent events
+    fn stream(mut self) -> Self {
+        self.body["stream"] = json!(true);
+        self
+    }
+}
+
+/// A single decoded event from a streaming response
+#[derive(Debug, PartialEq)]
+enum StreamEvent {
+    /// A chunk of generated text
+    Delta(String),
+    /// The provider signalled the end of the stream
+    Done,
+    /// An event that carries no text (pings, block boundaries, usage, ...)
+    Ignore,
 }
 
 /// Trait for provider-specific response parsing
-trait ResponseParser {
+trait ResponseParser: Send + Sync {
     fn parse_completion(&self, response_text: &str) -> Result<String>;
+
+    /// Parse a response to a request that forced a call to `tool_name`
+    fn parse_structured(&self, response_text: &str, tool_name: &str) -> Result<StructuredOutput>;
+
+    /// Parse a response to a request that offered tools
+    fn parse_tool_reply(&self, response_text: &str) -> Result<ToolReply>;
+
+    /// Parse the `data` payload of one server-sent event
+    fn parse_stream_event(&self, data: &str) -> Result<StreamEvent>;
 }
 
 /// Anthropic response parser
+#[cfg(feature = "anthropic")]
 struct AnthropicParser;
 
+#[cfg(feature = "anthropic")]
 impl ResponseParser for AnthropicParser {
     fn parse_completion(&self, response_text: &str) -> Result<String> {
         let response: AnthropicResponse = serde_json::from_str(response_text)
             .context("Failed to parse Anthropic response")?;
         Ok(response
             .content
-            .first()
-            .map(|c| c.text.clone())
+            .into_iter()
+            .find_map(|c| c.text)
             .unwrap_or_default())
     }
+
+    fn parse_structured(&self, response_text: &str, tool_name: &str) -> Result<StructuredOutput> {
+        let response: AnthropicResponse = serde_json::from_str(response_text)
+            .context("Failed to parse Anthropic response")?;
+
+        let mut text = String::new();
+        for block in response.content {
+            match block.kind.as_deref() {
+                Some("tool_use") if block.name.as_deref() == Some(tool_name) => {
+                    return Ok(StructuredOutput::Json(block.input.unwrap_or_default()));
+                }
+                _ => text.push_str(block.text.as_deref().unwrap_or_default()),
+            }
+        }
+        Ok(StructuredOutput::Text(text))
+    }
+
+    fn parse_tool_reply(&self, response_text: &str) -> Result<ToolReply> {
+        let response: AnthropicResponse = serde_json::from_str(response_text)
+            .context("Failed to parse Anthropic response")?;
+
+        let mut text = String::new();
+        let mut calls = Vec::new();
+        for block in response.content {
+            match block.kind.as_deref() {
+                Some("tool_use") => calls.push(ToolCall {
+                    id: block.id.unwrap_or_default(),
+                    name: block.name.unwrap_or_default(),
+                    arguments: block.input.unwrap_or_default(),
+                }),
+                _ => text.push_str(block.text.as_deref().unwrap_or_default()),
+            }
+        }
+        Ok(tool_reply(text, calls))
+    }
+
+    fn parse_stream_event(&self, data: &str) -> Result<StreamEvent> {
+        let event: AnthropicStreamEvent = serde_json::from_str(data)
+            .context("Failed to parse Anthropic stream event")?;
+        match event {
+            AnthropicStreamEvent::ContentBlockDelta { delta } => Ok(delta
+                .text
+                .map(StreamEvent::Delta)
+                .unwrap_or(StreamEvent::Ignore)),
+            AnthropicStreamEvent::MessageStop => Ok(StreamEvent::Done),
+            AnthropicStreamEvent::Error { error } => {
+                anyhow::bail!("Anthropic stream error: {}", error.message)
+            }
+            AnthropicStreamEvent::Other => Ok(StreamEvent::Ignore),
+        }
+    }
 }
 
 /// OpenAI response parser (also used for OpenRouter)
+#[cfg(any(feature = "openai", feature = "openrouter", feature = "local"))]
 struct OpenAIParser;
 
+#[cfg(any(feature = "openai", feature = "openrouter", feature = "local"))]
 impl ResponseParser for OpenAIParser {
     fn parse_completion(&self, response_text: &str) -> Result<String> {
         let response: OpenAIResponse = serde_json::from_str(response_text)
             .context("Failed to parse OpenAI response")?;
         Ok(response
             .choices
-            .first()
-            .map(|c| c.message.content.clone())
+            .into_iter()
+            .next()
+            .and_then(|c| c.message.content)
             .unwrap_or_default())
     }
+
+    fn parse_structured(&self, response_text: &str, tool_name: &str) -> Result<StructuredOutput> {
+        let response: OpenAIResponse = serde_json::from_str(response_text)
+            .context("Failed to parse OpenAI response")?;
+        let Some(choice) = response.choices.into_iter().next() else {
+            return Ok(StructuredOutput::Text(String::new()));
+        };
+
+        let call = choice
+            .message
+            .tool_calls
+            .into_iter()
+            .find(|call| call.function.name == tool_name);
+        Ok(match call {
+            // Arguments that are not valid JSON are left for the caller to salvage
+            Some(call) => serde_json::from_str(&call.function.arguments)
+                .map(StructuredOutput::Json)
+                .unwrap_or(StructuredOutput::Text(call.function.arguments)),
+            None => StructuredOutput::Text(choice.message.content.unwrap_or_default()),
+        })
+    }
+
+    fn parse_tool_reply(&self, response_text: &str) -> Result<ToolReply> {
+        let response: OpenAIResponse = serde_json::from_str(response_text)
+            .context("Failed to parse OpenAI response")?;
+        let Some(choice) = response.choices.into_iter().next() else {
+            return Ok(ToolReply::Text(String::new()));
+        };
+
+        let calls = choice
+            .message
+            .tool_calls
+            .into_iter()
+            .map(|call| ToolCall {
+                id: call.id,
+                // Malformed arguments are passed on as a string for the tool to reject
+                arguments: serde_json::from_str(&call.function.arguments)
+                    .unwrap_or(serde_json::Value::String(call.function.arguments)),
+                name: call.function.name,
+            })
+            .collect();
+        Ok(tool_reply(choice.message.content.unwrap_or_default(), calls))
+    }
+
+    fn parse_stream_event(&self, data: &str) -> Result<StreamEvent> {
+        if data.trim() == "[DONE]" {
+            return Ok(StreamEvent::Done);
+        }
+        let chunk: OpenAIStreamChunk = serde_json::from_str(data)
+            .context("Failed to parse OpenAI stream chunk")?;
+        Ok(chunk
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|c| c.delta.content)
+            .filter(|text| !text.is_empty())
+            .map(StreamEvent::Delta)
+            .unwrap_or(StreamEvent::Ignore))
+    }
+}
+
+/// Tool reply from the text and tool calls of a response
+fn tool_reply(text: String, calls: Vec<ToolCall>) -> ToolReply {
+    if calls.is_empty() {
+        ToolReply::Text(text)
+    } else {
+        ToolReply::ToolCalls { text, calls }
+    }
+}
+
+/// Incremental decoder for `text/event-stream` response bodies
+///
+/// Network chunks can split events (and UTF-8 characters) anywhere, so raw
+/// bytes are buffered until a blank line terminates an event.
+#[derive(Default)]
+struct SseDecoder {
+    buffer: Vec<u8>,
+}
+
+impl SseDecoder {
+    /// Feed raw bytes and return the data payloads of every completed event
+    fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut events = Vec::new();
+        while let Some((end, separator_len)) = Self::find_event_boundary(&self.buffer) {
+            let block: Vec<u8> = self.buffer.drain(..end + separator_len).take(end).collect();
+            if let Some(data) = Self::parse_block(&String::from_utf8_lossy(&block)) {
+                events.push(data);
+            }
+        }
+        events
+    }
+
+    /// Flush a trailing event that was not terminated by a blank line
+    fn finish(&mut self) -> Option<String> {
+        let block = std::mem::take(&mut self.buffer);
+        Self::parse_block(&String::from_utf8_lossy(&block))
+    }
+
+    /// Locate the first blank line (`\n\n` or `\n\r\n`), returning its offset and length
+    fn find_event_boundary(buffer: &[u8]) -> Option<(usize, usize)> {
+        buffer.iter().enumerate().find_map(|(i, &byte)| {
+            if byte != b'\n' {
+                return None;
+            }
+            match &buffer[i + 1..] {
+                [b'\n', ..] => Some((i, 2)),
+                [b'\r', b'\n', ..] => Some((i, 3)),
+                _ => None,
+            }
+        })
+    }
+
+    /// Join the `data:` lines of one event, ignoring comments and other fields
+    fn parse_block(block: &str) -> Option<String> {
+        let data: Vec<&str> = block
+            .lines()
+            .filter_map(|line| line.trim_end_matches('\r').strip_prefix("data:"))
+            .map(|value| value.strip_prefix(' ').unwrap_or(value))
+            .collect();
+
+        if data.is_empty() {
+            None
+        } else {
+            Some(data.join("\n"))
+        }
+    }
+}
+
+/// State threaded through the SSE stream adapter
+struct SseStreamState<S> {
+    bytes: S,
+    decoder: SseDecoder,
+    pending: VecDeque<String>,
+    finished: bool,
+}
+
+/// Turn a raw SSE byte stream into a stream of text deltas
+fn decode_sse_stream<S, B, E>(
+    bytes: S,
+    parser: &'static dyn ResponseParser,
+    provider_name: &'static str,
+) -> TextStream
+where
+    S: Stream<Item = std::result::Result<B, E>> + Send + Unpin + 'static,
+    B: AsRef<[u8]>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let state = SseStreamState {
+        bytes,
+        decoder: SseDecoder::default(),
+        pending: VecDeque::new(),
+        finished: false,
+    };
+
+    Box::pin(stream::unfold(state, move |mut state| async move {
+        loop {
+            if let Some(data) = state.pending.pop_front() {
+                match parser.parse_stream_event(&data) {
+                    Ok(StreamEvent::Delta(text)) => return Some((Ok(text), state)),
+                    Ok(StreamEvent::Done) => return None,
+                    Ok(StreamEvent::Ignore) => continue,
+                    Err(e) => {
+                        state.pending.clear();
+                        state.finished = true;
+                        return Some((Err(e), state));
+                    }
+                }
+            }
+
+            if state.finished {
+                return None;
+            }
+
+            match state.bytes.next().await {
+                Some(Ok(chunk)) => {
+                    let events = state.decoder.push(chunk.as_ref());
+                    state.pending.extend(events);
+                }
+                Some(Err(e)) => {
+                    state.finished = true;
+                    let error = anyhow::Error::new(e)
+                        .context(format!("Failed to read {} response stream", provider_name));
+                    return Some((Err(error), state));
+                }
+                None => {
+                    state.finished = true;
+                    state.pending.extend(state.decoder.finish());
+                }
+            }
+        }
+    }))
 }
 
 impl LLMClient {
     /// Create a new LLM client
     pub fn new(provider: LLMProvider) -> Self {
+        let http_client = Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+
         Self {
             provider,
-            http_client: Client::new(),
+            http_client,
+            retry_policy: RetryPolicy::default(),
+            timeout: DEFAULT_REQUEST_TIMEOUT,
         }
     }
 
+    /// Model requests are sent to
+    pub fn model(&self) -> &str {
+        self.provider.model()
+    }
+
+    /// Set how failed requests are retried
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Set the per-request timeout
+    ///
+    /// For streaming requests it bounds the wait for the response to start,
+    /// not the whole stream.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
     /// Send a completion request to the LLM
     pub async fn complete(&self, system: &str, user_message: &str) -> Result<String> {
+        self.chat(system, &[ChatMessage::user(user_message)]).await
+    }
+
+    /// Send a completion request and stream the reply as text deltas
+    ///
+    /// Concatenating every item of the returned stream yields the same text
+    /// that [`LLMClient::complete`] would have returned.
+    pub async fn complete_stream(&self, system: &str, user_message: &str) -> Result<TextStream> {
+        self.chat_stream(system, &[ChatMessage::user(user_message)])
+            .await
+    }
+
+    /// Send a multi-turn conversation to the LLM
+    ///
+    /// `messages` must be in chronological order and should end with a user turn.
+    pub async fn chat(&self, system: &str, messages: &[ChatMessage]) -> Result<String> {
+        let (request, parser, provider_name) = self.build_request(system, messages);
+        self.execute_request(request, parser, provider_name).await
+    }
+
+    /// Ask the model to answer by calling `tool`, returning the tool input
+    ///
+    /// Uses Anthropic tool use or OpenAI function calling with the tool forced
+    /// through `tool_choice`. A model that replies in prose anyway yields
+    /// [`StructuredOutput::Text`]. Providers or models without tool support
+    /// usually reject the request with [`LLMError::BadRequest`].
+    pub async fn complete_structured(
+        &self,
+        system: &str,
+        user_message: &str,
+        tool: &ToolDefinition,
+    ) -> Result<StructuredOutput> {
+        let (request, parser, provider_name) = self.build_request(system, &[ChatMessage::user(user_message)]);
+        let request = match &self.provider {
+            #[cfg(feature = "anthropic")]
+            LLMProvider::Anthropic { .. } => Self::anthropic_forced_tool(request, tool),
+            #[cfg(any(feature = "openai", feature = "openrouter", feature = "local"))]
+            _ => Self::openai_forced_tool(request, tool),
+        };
+
+        let response_text = self.fetch_response_text(&request, provider_name).await?;
+        parser.parse_structured(&response_text, &tool.name)
+    }
+
+    /// Send a conversation that may include tool calls, offering `tools`
+    ///
+    /// With `allow_calls` false the tools stay declared (providers require
+    /// that once the conversation contains tool calls) but the model is told
+    /// not to call them, forcing a final answer.
+    pub async fn chat_with_tools(
+        &self,
+        system: &str,
+        turns: &[ToolTurn],
+        tools: &[ToolDefinition],
+        allow_calls: bool,
+    ) -> Result<ToolReply> {
+        let (mut request, parser, provider_name) = self.build_request(system, &[]);
         match &self.provider {
-            LLMProvider::Anthropic { api_key, model } => {
-                self.anthropic_complete(api_key, model, system, user_message)
-                    .await
-            }
-            LLMProvider::OpenAI { api_key, model } => {
-                self.openai_complete(api_key, model, system, user_message)
-                    .await
+            #[cfg(feature = "anthropic")]
+            LLMProvider::Anthropic { .. } => {
+                request.body["messages"] = json!(Self::anthropic_tool_messages(turns));
+                request.body["tools"] = json!(tools
+                    .iter()
+                    .map(|tool| json!({
+                        "name": tool.name,
+                        "description": tool.description,
+                        "input_schema": tool.input_schema
+                    }))
+                    .collect::<Vec<_>>());
+                request.body["tool_choice"] = json!({"type": if allow_calls { "auto" } else { "none" }});
             }
-            LLMProvider::OpenRouter { api_key, model, app_name, site_url } => {
-                self.openrouter_complete(api_key, model, app_name.as_deref(), site_url.as_deref(), system, user_message)
-                    .await
+            #[cfg(any(feature = "openai", feature = "openrouter", feature = "local"))]
+            _ => {
+                request.body["messages"] = json!(Self::openai_tool_messages(system, turns));
+                request.body["tools"] = json!(tools
+                    .iter()
+                    .map(|tool| json!({
+                        "type": "function",
+                        "function": {
+                            "name": tool.name,
+                            "description": tool.description,
+                            "parameters": tool.input_schema
+                        }
+                    }))
+                    .collect::<Vec<_>>());
+                request.body["tool_choice"] = json!(if allow_calls { "auto" } else { "none" });
             }
         }
+
+        let response_text = self.fetch_response_text(&request, provider_name).await?;
+        parser.parse_tool_reply(&response_text)
     }
 
-    /// Execute an LLM request and parse the response
-    async fn execute_request(
+    /// Send a multi-turn conversation and stream the reply as text deltas
+    pub async fn chat_stream(&self, system: &str, messages: &[ChatMessage]) -> Result<TextStream> {
+        let (request, parser, provider_name) = self.build_request(system, messages);
+        self.execute_stream(request.stream(), parser, provider_name)
+            .await
+    }
+
+    /// Build the provider-specific request along with its response parser
+    fn build_request(
         &self,
-        request: LLMRequest,
-        parser: &dyn ResponseParser,
+        system: &str,
+        messages: &[ChatMessage],
+    ) -> (LLMRequest, &'static dyn ResponseParser, &'static str) {
+        match &self.provider {
+            #[cfg(feature = "anthropic")]
+            LLMProvider::Anthropic { api_key, model } => (
+                Self::anthropic_request(api_key, model, system, messages),
+                &AnthropicParser,
+                "Anthropic",
+            ),
+            #[cfg(feature = "openai")]
+            LLMProvider::OpenAI { api_key, model } => (
+                Self::openai_request(api_key, model, system, messages),
+                &OpenAIParser,
+                "OpenAI",
+            ),
+            #[cfg(feature = "openrouter")]
+            LLMProvider::OpenRouter { api_key, model, app_name, site_url } => (
+                Self::openrouter_request(api_key, model, app_name.as_deref(), site_url.as_deref(), system, messages),
+                &OpenAIParser,
+                "OpenRouter",
+            ),
+            #[cfg(feature = "local")]
+            LLMProvider::OpenAICompatible { base_url, api_key, model } => (
+                Self::openai_compatible_request(base_url, api_key.as_deref(), model, system, messages),
+                &OpenAIParser,
+                "OpenAI-compatible",
+            ),
+        }
+    }
+
+    /// Send an LLM request once, returning the response if it succeeded
+    async fn send_request(
+        &self,
+        request: &LLMRequest,
         provider_name: &str,
-    ) -> Result<String> {
+    ) -> std::result::Result<reqwest::Response, LLMError> {
         let mut http_request = self
             .http_client
             .post(&request.url)
             .header("content-type", "application/json");
 
         // Add custom headers
-        for (key, value) in request.headers {
+        for (key, value) in &request.headers {
             http_request = http_request.header(key, value);
         }
 
-        // Send request
-        let response = http_request
-            .json(&request.body)
-            .send()
+        // Send request; the timeout covers the response headers only, so
+        // streams may run longer
+        let response = tokio::time::timeout(self.timeout, http_request.json(&request.body).send())
             .await
-            .context(format!("Failed to send request to {} API", provider_name))?;
+            .map_err(|_| LLMError::Timeout { provider: provider_name.to_string() })?
+            .map_err(|e| LLMError::from_reqwest(provider_name, &e))?;
 
-        // Check status
         let status = response.status();
-        let response_text = response.text().await?;
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        // Errors are reported as a regular JSON body, even for streams
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| parse_retry_after(value, chrono::Utc::now()));
+        let response_text = response.text().await.unwrap_or_default();
+
+        Err(LLMError::from_status(provider_name, status.as_u16(), retry_after, response_text))
+    }
 
-        if !status.is_success() {
-            anyhow::bail!("{} API error ({}): {}", provider_name, status, response_text);
+    /// Send an LLM request, retrying rate-limited and failed attempts
+    /// according to the retry policy
+    async fn send_with_retry(&self, request: &LLMRequest, provider_name: &str) -> Result<reqwest::Response> {
+        let mut attempt = 0;
+        loop {
+            let error = match self.send_request(request, provider_name).await {
+                Ok(response) => return Ok(response),
+                Err(error) => error,
+            };
+
+            match self.retry_policy.next_delay(attempt, &error) {
+                Some(delay) => {
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                None => return Err(error.into()),
+            }
         }
+    }
+
+    /// Execute an LLM request and parse the response
+    async fn execute_request(
+        &self,
+        request: LLMRequest,
+        parser: &dyn ResponseParser,
+        provider_name: &str,
+    ) -> Result<String> {
+        let response_text = self.fetch_response_text(&request, provider_name).await?;
 
         // Parse response
         parser.parse_completion(&response_text)
     }
 
-    /// Anthropic API completion
-    async fn anthropic_complete(
+    /// Send an LLM request with retries and read the whole response body
+    async fn fetch_response_text(&self, request: &LLMRequest, provider_name: &str) -> Result<String> {
+        let response = self.send_with_retry(request, provider_name).await?;
+
+        let response_text = tokio::time::timeout(self.timeout, response.text())
+            .await
+            .map_err(|_| LLMError::Timeout { provider: provider_name.to_string() })?
+            .map_err(|e| LLMError::from_reqwest(provider_name, &e))?;
+
+        Ok(response_text)
+    }
+
+    /// Execute a streaming LLM request and decode its server-sent events
+    async fn execute_stream(
         &self,
+        request: LLMRequest,
+        parser: &'static dyn ResponseParser,
+        provider_name: &'static str,
+    ) -> Result<TextStream> {
+        // Only the initial request is retried; a stream that fails midway
+        // reports the error to the caller
+        let response = self.send_with_retry(&request, provider_name).await?;
+
+        Ok(decode_sse_stream(
+            Box::pin(response.bytes_stream()),
+            parser,
+            provider_name,
+        ))
+    }
+
+    /// Anthropic API request
+    #[cfg(feature = "anthropic")]
+    fn anthropic_request(
         api_key: &str,
         model: &str,
         system: &str,
-        user_message: &str,
-    ) -> Result<String> {
-        let request = LLMRequest::new("https://api.anthropic.com/v1/messages")
+        messages: &[ChatMessage],
+    ) -> LLMRequest {
+        // The Messages API requires the conversation to open with a user turn
+        let first_user = messages
+            .iter()
+            .position(|m| m.role == ChatRole::User)
+            .unwrap_or(messages.len());
+
+        LLMRequest::new("https://api.anthropic.com/v1/messages")
             .header("x-api-key", api_key)
             .header("anthropic-version", "2023-06-01")
             .body(json!({
                 "model": model,
                 "max_tokens": 4096,
                 "system": system,
-                "messages": [
-                    {
-                        "role": "user",
-                        "content": user_message
-                    }
-                ]
-            }));
+                "messages": &messages[first_user..]
+            }))
+    }
 
-        self.execute_request(request, &AnthropicParser, "Anthropic")
-            .await
+    /// Anthropic message list for a conversation with tool calls
+    ///
+    /// Tool calls become `tool_use` blocks on assistant turns and their
+    /// results `tool_result` blocks on user turns.
+    #[cfg(feature = "anthropic")]
+    fn anthropic_tool_messages(turns: &[ToolTurn]) -> Vec<serde_json::Value> {
+        // The Messages API requires the conversation to open with a user turn
+        let first_user = turns
+            .iter()
+            .position(|turn| matches!(turn, ToolTurn::Message(m) if m.role == ChatRole::User))
+            .unwrap_or(turns.len());
+
+        turns[first_user..]
+            .iter()
+            .map(|turn| match turn {
+                ToolTurn::Message(message) => json!(message),
+                ToolTurn::ToolCalls { text, calls } => {
+                    let text_block = (!text.is_empty()).then(|| json!({"type": "text", "text": text}));
+                    let content: Vec<serde_json::Value> = text_block
+                        .into_iter()
+                        .chain(calls.iter().map(|call| {
+                            json!({
+                                "type": "tool_use",
+                                "id": call.id,
+                                "name": call.name,
+                                "input": call.arguments
+                            })
+                        }))
+                        .collect();
+                    json!({"role": "assistant", "content": content})
+                }
+                ToolTurn::ToolResults(results) => {
+                    let content: Vec<serde_json::Value> = results
+                        .iter()
+                        .map(|result| {
+                            json!({
+                                "type": "tool_result",
+                                "tool_use_id": result.call_id,
+                                "content": result.content,
+                                "is_error": result.is_error
+                            })
+                        })
+                        .collect();
+                    json!({"role": "user", "content": content})
+                }
+            })
+            .collect()
     }
 
-    /// OpenAI API completion
-    async fn openai_complete(
-        &self,
+    /// OpenAI-format message list for a conversation with tool calls
+    ///
+    /// Tool calls become `tool_calls` on assistant messages (arguments
+    /// JSON-encoded) and each result a `tool` message.
+    #[cfg(any(feature = "openai", feature = "openrouter", feature = "local"))]
+    fn openai_tool_messages(system: &str, turns: &[ToolTurn]) -> Vec<serde_json::Value> {
+        let mut messages = Self::openai_messages(system, &[]);
+        for turn in turns {
+            match turn {
+                ToolTurn::Message(message) => messages.push(json!(message)),
+                ToolTurn::ToolCalls { text, calls } => messages.push(json!({
+                    "role": "assistant",
+                    "content": if text.is_empty() { serde_json::Value::Null } else { json!(text) },
+                    "tool_calls": calls
+                        .iter()
+                        .map(|call| json!({
+                            "id": call.id,
+                            "type": "function",
+                            "function": {
+                                "name": call.name,
+                                "arguments": call.arguments.to_string()
+                            }
+                        }))
+                        .collect::<Vec<_>>()
+                })),
+                ToolTurn::ToolResults(results) => messages.extend(results.iter().map(|result| {
+                    json!({
+                        "role": "tool",
+                        "tool_call_id": result.call_id,
+                        "content": result.content
+                    })
+                })),
+            }
+        }
+        messages
+    }
+
+    /// Offer `tool` on an Anthropic request and require the model to call it
+    #[cfg(feature = "anthropic")]
+    fn anthropic_forced_tool(mut request: LLMRequest, tool: &ToolDefinition) -> LLMRequest {
+        request.body["tools"] = json!([{
+            "name": tool.name,
+            "description": tool.description,
+            "input_schema": tool.input_schema
+        }]);
+        request.body["tool_choice"] = json!({"type": "tool", "name": tool.name});
+        request
+    }
+
+    /// Offer `tool` as a function on an OpenAI-format request and require
+    /// the model to call it
+    #[cfg(any(feature = "openai", feature = "openrouter", feature = "local"))]
+    fn openai_forced_tool(mut request: LLMRequest, tool: &ToolDefinition) -> LLMRequest {
+        request.body["tools"] = json!([{
+            "type": "function",
+            "function": {
+                "name": tool.name,
+                "description": tool.description,
+                "parameters": tool.input_schema
+            }
+        }]);
+        request.body["tool_choice"] = json!({"type": "function", "function": {"name": tool.name}});
+        request
+    }
+
+    /// OpenAI-format message list with the system prompt as the first entry
+    #[cfg(any(feature = "openai", feature = "openrouter", feature = "local"))]
+    fn openai_messages(system: &str, messages: &[ChatMessage]) -> Vec<serde_json::Value> {
+        std::iter::once(json!({
+            "role": "system",
+            "content": system
+        }))
+        .chain(messages.iter().map(|m| json!(m)))
+        .collect()
+    }
+
+    /// OpenAI API request
+    #[cfg(feature = "openai")]
+    fn openai_request(
         api_key: &str,
         model: &str,
         system: &str,
-        user_message: &str,
-    ) -> Result<String> {
-        let request = LLMRequest::new("https://api.openai.com/v1/chat/completions")
+        messages: &[ChatMessage],
+    ) -> LLMRequest {
+        LLMRequest::new("https://api.openai.com/v1/chat/completions")
             .header("Authorization", format!("Bearer {}", api_key))
             .body(json!({
                 "model": model,
                 "temperature": 0.7,
-                "messages": [
-                    {
-                        "role": "system",
-                        "content": system
-                    },
-                    {
-                        "role": "user",
-                        "content": user_message
-                    }
-                ]
-            }));
-
-        self.execute_request(request, &OpenAIParser, "OpenAI")
-            .await
+                "messages": Self::openai_messages(system, messages)
+            }))
     }
 
-    /// OpenRouter API completion (OpenAI-compatible format)
-    async fn openrouter_complete(
-        &self,
+    /// OpenRouter API request (OpenAI-compatible format)
+    #[cfg(feature = "openrouter")]
+    fn openrouter_request(
         api_key: &str,
         model: &str,
         app_name: Option<&str>,
         site_url: Option<&str>,
         system: &str,
-        user_message: &str,
-    ) -> Result<String> {
+        messages: &[ChatMessage],
+    ) -> LLMRequest {
         let mut request = LLMRequest::new("https://openrouter.ai/api/v1/chat/completions")
             .header("Authorization", format!("Bearer {}", api_key))
             .body(json!({
                 "model": model,
                 "temperature": 0.7,
-                "messages": [
-                    {
-                        "role": "system",
-                        "content": system
-                    },
-                    {
-                        "role": "user",
-                        "content": user_message
-                    }
-                ]
+                "messages": Self::openai_messages(system, messages)
             }));
 
         // Add optional headers for app tracking
@@ -285,14 +1073,53 @@ impl LLMClient {
             request = request.header("HTTP-Referer", url);
         }
 
-        self.execute_request(request, &OpenAIParser, "OpenRouter")
-            .await
+        request
+    }
+
+    /// Request to a self-hosted OpenAI-compatible server
+    ///
+    /// Local servers usually need no API key, so the `Authorization` header
+    /// is only sent when one is configured.
+    #[cfg(feature = "local")]
+    fn openai_compatible_request(
+        base_url: &str,
+        api_key: Option<&str>,
+        model: &str,
+        system: &str,
+        messages: &[ChatMessage],
+    ) -> LLMRequest {
+        let mut request = LLMRequest::new(chat_completions_url(base_url)).body(json!({
+            "model": model,
+            "temperature": 0.7,
+            "messages": Self::openai_messages(system, messages)
+        }));
+
+        if let Some(api_key) = api_key {
+            request = request.header("Authorization", format!("Bearer {}", api_key));
+        }
+
+        request
+    }
+}
+
+/// Chat completions endpoint under an OpenAI-style API root
+///
+/// Accepts the root with or without a trailing slash, or the full endpoint.
+#[cfg(feature = "local")]
+fn chat_completions_url(base_url: &str) -> String {
+    let base_url = base_url.trim().trim_end_matches('/');
+    if base_url.ends_with("/chat/completions") {
+        base_url.to_string()
+    } else {
+        format!("{}/chat/completions", base_url)
     }
 }
 
-#[cfg(test)]
+// The tests compare the wire formats side by side, so they need every provider
+#[cfg(all(test, feature = "anthropic", feature = "openai", feature = "openrouter", feature = "local"))]
 mod tests {
     use super::*;
+    use crate::llm::mock_server::{http_response, json_response, MockServer};
 
     #[test]
     fn test_llm_request_builder() {
@@ -366,4 +1193,638 @@ mod tests {
         assert_eq!(request.headers.len(), 2);
         assert!(request.body["test"].as_bool().unwrap());
     }
+
+    #[test]
+    fn test_llm_request_stream_flag() {
+        let request = LLMRequest::new("https://test.com")
+            .body(json!({"model": "test"}))
+            .stream();
+
+        assert_eq!(request.body["stream"], true);
+        assert_eq!(request.body["model"], "test");
+    }
+
+    #[test]
+    fn test_sse_decoder_single_event() {
+        let mut decoder = SseDecoder::default();
+        let events = decoder.push(b"event: ping\ndata: {\"type\": \"ping\"}\n\n");
+        assert_eq!(events, vec![r#"{"type": "ping"}"#]);
+    }
+
+    #[test]
+    fn test_sse_decoder_split_across_chunks() {
+        let mut decoder = SseDecoder::default();
+        assert!(decoder.push(b"data: hel").is_empty());
+        assert!(decoder.push(b"lo\n").is_empty());
+        assert_eq!(decoder.push(b"\ndata: world\n\n"), vec!["hello", "world"]);
+    }
+
+    #[test]
+    fn test_sse_decoder_split_utf8_character() {
+        let mut decoder = SseDecoder::default();
+        let bytes = "data: 世界\n\n".as_bytes();
+        assert!(decoder.push(&bytes[..7]).is_empty());
+        assert_eq!(decoder.push(&bytes[7..]), vec!["世界"]);
+    }
+
+    #[test]
+    fn test_sse_decoder_crlf_and_comments() {
+        let mut decoder = SseDecoder::default();
+        let events = decoder.push(b": OPENROUTER PROCESSING\r\n\r\ndata: [DONE]\r\n\r\n");
+        assert_eq!(events, vec!["[DONE]"]);
+    }
+
+    #[test]
+    fn test_sse_decoder_finish_flushes_trailing_event() {
+        let mut decoder = SseDecoder::default();
+        assert!(decoder.push(b"data: last").is_empty());
+        assert_eq!(decoder.finish(), Some("last".to_string()));
+        assert_eq!(decoder.finish(), None);
+    }
+
+    #[test]
+    fn test_anthropic_stream_events() {
+        let parser = AnthropicParser;
+        assert_eq!(
+            parser
+                .parse_stream_event(r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hi"}}"#)
+                .unwrap(),
+            StreamEvent::Delta("Hi".to_string())
+        );
+        assert_eq!(
+            parser
+                .parse_stream_event(r#"{"type":"message_start","message":{"id":"msg_1"}}"#)
+                .unwrap(),
+            StreamEvent::Ignore
+        );
+        assert_eq!(
+            parser.parse_stream_event(r#"{"type":"message_stop"}"#).unwrap(),
+            StreamEvent::Done
+        );
+    }
+
+    #[test]
+    fn test_anthropic_stream_error_event() {
+        let parser = AnthropicParser;
+        let result = parser.parse_stream_event(
+            r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#,
+        );
+        assert!(result.unwrap_err().to_string().contains("Overloaded"));
+    }
+
+    #[test]
+    fn test_openai_stream_events() {
+        let parser = OpenAIParser;
+        assert_eq!(
+            parser
+                .parse_stream_event(r#"{"choices":[{"index":0,"delta":{"content":"Hi"}}]}"#)
+                .unwrap(),
+            StreamEvent::Delta("Hi".to_string())
+        );
+        assert_eq!(
+            parser
+                .parse_stream_event(r#"{"choices":[{"index":0,"delta":{"role":"assistant"}}]}"#)
+                .unwrap(),
+            StreamEvent::Ignore
+        );
+        assert_eq!(parser.parse_stream_event("[DONE]").unwrap(), StreamEvent::Done);
+        assert!(parser.parse_stream_event("not valid json").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_decode_sse_stream_anthropic() {
+        let chunks: Vec<std::result::Result<&[u8], std::io::Error>> = vec![
+            Ok(b"event: message_start\ndata: {\"type\":\"message_start\"}\n\n"),
+            Ok(b"event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"delta\":{\"text\":\"Hel\"}}\n\nevent: content_"),
+            Ok(b"block_delta\ndata: {\"type\":\"content_block_delta\",\"delta\":{\"text\":\"lo\"}}\n\n"),
+            Ok(b"event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n"),
+        ];
+        let deltas: Vec<String> = decode_sse_stream(stream::iter(chunks), &AnthropicParser, "Anthropic")
+            .map(|item| item.unwrap())
+            .collect()
+            .await;
+        assert_eq!(deltas, vec!["Hel", "lo"]);
+    }
+
+    #[tokio::test]
+    async fn test_decode_sse_stream_openai_stops_at_done() {
+        let chunks: Vec<std::result::Result<&[u8], std::io::Error>> = vec![
+            Ok(b"data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\n"),
+            Ok(b"data: {\"choices\":[{\"delta\":{\"content\":\"Hello\"}}]}\n\n"),
+            Ok(b"data: [DONE]\n\ndata: {\"choices\":[{\"delta\":{\"content\":\"ignored\"}}]}\n\n"),
+        ];
+        let deltas: Vec<String> = decode_sse_stream(stream::iter(chunks), &OpenAIParser, "OpenAI")
+            .map(|item| item.unwrap())
+            .collect()
+            .await;
+        assert_eq!(deltas, vec!["Hello"]);
+    }
+
+    #[tokio::test]
+    async fn test_decode_sse_stream_surfaces_transport_error() {
+        let chunks: Vec<std::result::Result<&[u8], std::io::Error>> = vec![
+            Ok(b"data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n"),
+            Err(std::io::Error::other("connection reset")),
+        ];
+        let items: Vec<Result<String>> = decode_sse_stream(stream::iter(chunks), &OpenAIParser, "OpenAI")
+            .collect()
+            .await;
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].as_ref().unwrap(), "Hi");
+        assert!(items[1].is_err());
+    }
+
+    #[test]
+    fn test_chat_role_parse() {
+        assert_eq!(ChatRole::parse("user"), Some(ChatRole::User));
+        assert_eq!(ChatRole::parse("assistant"), Some(ChatRole::Assistant));
+        assert_eq!(ChatRole::parse("system"), None);
+    }
+
+    #[test]
+    fn test_anthropic_request_multi_turn() {
+        let messages = vec![
+            ChatMessage::user("My name is Alice"),
+            ChatMessage::assistant("Nice to meet you, Alice!"),
+            ChatMessage::user("What did I just say?"),
+        ];
+        let request = LLMClient::anthropic_request("key", "model", "system prompt", &messages);
+
+        assert_eq!(request.body["system"], "system prompt");
+        let sent = request.body["messages"].as_array().unwrap();
+        assert_eq!(sent.len(), 3);
+        assert_eq!(sent[0], json!({"role": "user", "content": "My name is Alice"}));
+        assert_eq!(sent[1]["role"], "assistant");
+        assert_eq!(sent[2]["content"], "What did I just say?");
+    }
+
+    #[test]
+    fn test_anthropic_request_drops_leading_assistant_turns() {
+        let messages = vec![
+            ChatMessage::assistant("Hello!"),
+            ChatMessage::user("Hi"),
+        ];
+        let request = LLMClient::anthropic_request("key", "model", "system", &messages);
+
+        let sent = request.body["messages"].as_array().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0]["role"], "user");
+    }
+
+    #[test]
+    fn test_openai_request_multi_turn() {
+        let messages = vec![
+            ChatMessage::user("My name is Alice"),
+            ChatMessage::assistant("Nice to meet you, Alice!"),
+            ChatMessage::user("What did I just say?"),
+        ];
+        let request = LLMClient::openai_request("key", "model", "system prompt", &messages);
+
+        let sent = request.body["messages"].as_array().unwrap();
+        assert_eq!(sent.len(), 4);
+        assert_eq!(sent[0], json!({"role": "system", "content": "system prompt"}));
+        assert_eq!(sent[1]["role"], "user");
+        assert_eq!(sent[2]["role"], "assistant");
+        assert_eq!(sent[3]["content"], "What did I just say?");
+    }
+
+    fn fast_retries(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_secs(1),
+            ..Default::default()
+        }
+    }
+
+    fn test_client() -> LLMClient {
+        LLMClient::new(LLMProvider::OpenAI {
+            api_key: "test".to_string(),
+            model: "test".to_string(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_execute_request_retries_rate_limit() {
+        let server = MockServer::start(vec![
+            http_response("429 Too Many Requests", "retry-after: 0\r\n", "busy"),
+            http_response("529 Overloaded", "", "overloaded"),
+            json_response(r#"{"choices":[{"message":{"role":"assistant","content":"Hello"}}]}"#),
+        ])
+        .await;
+        let client = test_client().with_retry_policy(fast_retries(2));
+
+        let text = client
+            .execute_request(LLMRequest::new(chat_completions_url(&server.base_url)), &OpenAIParser, "Test")
+            .await
+            .unwrap();
+
+        assert_eq!(text, "Hello");
+    }
+
+    #[tokio::test]
+    async fn test_execute_request_returns_typed_error() {
+        let server = MockServer::start(vec![
+            http_response("401 Unauthorized", "", "bad key"),
+        ])
+        .await;
+        let client = test_client().with_retry_policy(fast_retries(3));
+
+        let error = client
+            .execute_request(LLMRequest::new(chat_completions_url(&server.base_url)), &OpenAIParser, "Test")
+            .await
+            .unwrap_err();
+
+        // Auth failures are not retried
+        assert_eq!(
+            error.downcast_ref::<LLMError>(),
+            Some(&LLMError::Auth {
+                provider: "Test".to_string(),
+                status: 401,
+                message: "bad key".to_string(),
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_request_gives_up_after_max_retries() {
+        let server = MockServer::start(vec![
+            http_response("503 Service Unavailable", "", ""),
+            http_response("503 Service Unavailable", "", ""),
+        ])
+        .await;
+        let client = test_client().with_retry_policy(fast_retries(1));
+
+        let error = client
+            .execute_request(LLMRequest::new(chat_completions_url(&server.base_url)), &OpenAIParser, "Test")
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error.downcast_ref::<LLMError>(),
+            Some(LLMError::ServerError { status: 503, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_execute_request_timeout() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        // Accept the connection but never answer
+        tokio::spawn(async move {
+            let _connection = listener.accept().await;
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        });
+        let client = test_client()
+            .with_retry_policy(RetryPolicy::none())
+            .with_timeout(Duration::from_millis(50));
+
+        let error = client
+            .execute_request(LLMRequest::new(url), &OpenAIParser, "Test")
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error.downcast_ref::<LLMError>(), Some(LLMError::Timeout { .. })));
+    }
+
+    #[test]
+    fn test_chat_completions_url() {
+        assert_eq!(chat_completions_url("http://localhost:11434/v1"), "http://localhost:11434/v1/chat/completions");
+        assert_eq!(chat_completions_url("http://localhost:8080/v1/"), "http://localhost:8080/v1/chat/completions");
+        assert_eq!(
+            chat_completions_url("http://gpu-box:8000/v1/chat/completions"),
+            "http://gpu-box:8000/v1/chat/completions"
+        );
+    }
+
+    #[test]
+    fn test_openai_compatible_request_auth_header() {
+        let messages = [ChatMessage::user("Hi")];
+
+        let keyless = LLMClient::openai_compatible_request("http://localhost:11434/v1", None, "llama3.1", "sys", &messages);
+        assert_eq!(keyless.url, "http://localhost:11434/v1/chat/completions");
+        assert!(keyless.headers.is_empty());
+        assert_eq!(keyless.body["model"], "llama3.1");
+        assert_eq!(keyless.body["messages"][0]["role"], "system");
+
+        let keyed = LLMClient::openai_compatible_request("http://localhost:8000/v1", Some("secret"), "m", "sys", &messages);
+        assert_eq!(keyed.headers, vec![("Authorization".to_string(), "Bearer secret".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn test_openai_compatible_provider_against_mock() {
+        let server = MockServer::start(vec![json_response(
+            r#"{"choices":[{"message":{"role":"assistant","content":"Hi from llama"}}]}"#,
+        )])
+        .await;
+        let client = LLMClient::new(LLMProvider::OpenAICompatible {
+            base_url: server.base_url.clone(),
+            api_key: None,
+            model: "llama3.1".to_string(),
+        });
+
+        let text = client.complete("You are helpful", "Hello").await.unwrap();
+
+        assert_eq!(text, "Hi from llama");
+    }
+
+    /// Anthropic Messages API response to a forced `tool_choice`
+    const ANTHROPIC_TOOL_USE_RESPONSE: &str = r#"{
+  "id": "msg_01XFDUDYJgAACzvnptvVoYEL",
+  "type": "message",
+  "role": "assistant",
+  "model": "claude-3-5-sonnet-20241022",
+  "content": [
+    {
+      "type": "tool_use",
+      "id": "toolu_01A09q90qw90lq917835lq9",
+      "name": "record_entities",
+      "input": {"people": ["Alice"], "topics": ["GraphQL {federation}"], "tasks": [], "documents": [], "completed_tasks": [], "relations": []}
+    }
+  ],
+  "stop_reason": "tool_use",
+  "stop_sequence": null,
+  "usage": {"input_tokens": 612, "output_tokens": 87}
+}"#;
+
+    /// OpenAI chat completion response with a forced function call
+    const OPENAI_TOOL_CALL_RESPONSE: &str = r#"{
+  "id": "chatcmpl-9pLdQ2nJ7xGd1c0a8Fq3kTQbXy7Zr",
+  "object": "chat.completion",
+  "created": 1722000000,
+  "model": "gpt-4o-mini-2024-07-18",
+  "choices": [
+    {
+      "index": 0,
+      "message": {
+        "role": "assistant",
+        "content": null,
+        "tool_calls": [
+          {
+            "id": "call_Vq1mN0Z3bYk2Xo8uJ4sT6rPd",
+            "type": "function",
+            "function": {
+              "name": "record_entities",
+              "arguments": "{\"people\":[\"Alice\"],\"topics\":[\"GraphQL {federation}\"],\"tasks\":[],\"documents\":[],\"completed_tasks\":[],\"relations\":[]}"
+            }
+          }
+        ],
+        "refusal": null
+      },
+      "logprobs": null,
+      "finish_reason": "stop"
+    }
+  ],
+  "usage": {"prompt_tokens": 540, "completion_tokens": 41, "total_tokens": 581}
+}"#;
+
+    fn test_tool() -> ToolDefinition {
+        ToolDefinition {
+            name: "record_entities".to_string(),
+            description: "Record entities".to_string(),
+            input_schema: json!({"type": "object", "properties": {"people": {"type": "array"}}}),
+        }
+    }
+
+    #[test]
+    fn test_anthropic_parse_structured_tool_use() {
+        let output = AnthropicParser
+            .parse_structured(ANTHROPIC_TOOL_USE_RESPONSE, "record_entities")
+            .unwrap();
+
+        let StructuredOutput::Json(input) = output else {
+            panic!("expected tool input, got {:?}", output);
+        };
+        assert_eq!(input["people"], json!(["Alice"]));
+        assert_eq!(input["topics"], json!(["GraphQL {federation}"]));
+    }
+
+    #[test]
+    fn test_anthropic_parse_structured_text_reply() {
+        let response = r#"{"content": [{"type": "text", "text": "Sure! {\"people\": []}"}]}"#;
+
+        assert_eq!(
+            AnthropicParser.parse_structured(response, "record_entities").unwrap(),
+            StructuredOutput::Text("Sure! {\"people\": []}".to_string())
+        );
+        // A response with only a tool call has no text
+        assert_eq!(AnthropicParser.parse_completion(ANTHROPIC_TOOL_USE_RESPONSE).unwrap(), "");
+    }
+
+    #[test]
+    fn test_openai_parse_structured_tool_call() {
+        let output = OpenAIParser
+            .parse_structured(OPENAI_TOOL_CALL_RESPONSE, "record_entities")
+            .unwrap();
+
+        let StructuredOutput::Json(arguments) = output else {
+            panic!("expected tool arguments, got {:?}", output);
+        };
+        assert_eq!(arguments["people"], json!(["Alice"]));
+        assert_eq!(arguments["topics"], json!(["GraphQL {federation}"]));
+        // A null content does not break plain completion parsing
+        assert_eq!(OpenAIParser.parse_completion(OPENAI_TOOL_CALL_RESPONSE).unwrap(), "");
+    }
+
+    #[test]
+    fn test_openai_parse_structured_fallbacks() {
+        let text_reply = r#"{"choices": [{"message": {"role": "assistant", "content": "{\"people\": []}"}}]}"#;
+        assert_eq!(
+            OpenAIParser.parse_structured(text_reply, "record_entities").unwrap(),
+            StructuredOutput::Text("{\"people\": []}".to_string())
+        );
+
+        let broken_arguments = r#"{"choices": [{"message": {"role": "assistant", "content": null,
+            "tool_calls": [{"function": {"name": "record_entities", "arguments": "{\"people\": [\"Al"}}]}}]}"#;
+        assert_eq!(
+            OpenAIParser.parse_structured(broken_arguments, "record_entities").unwrap(),
+            StructuredOutput::Text("{\"people\": [\"Al".to_string())
+        );
+    }
+
+    #[test]
+    fn test_forced_tool_requests() {
+        let request = LLMClient::anthropic_forced_tool(LLMRequest::new("https://api.anthropic.com"), &test_tool());
+        assert_eq!(request.body["tools"][0]["name"], "record_entities");
+        assert_eq!(request.body["tools"][0]["input_schema"]["type"], "object");
+        assert_eq!(request.body["tool_choice"], json!({"type": "tool", "name": "record_entities"}));
+
+        let request = LLMClient::openai_forced_tool(LLMRequest::new("https://api.openai.com"), &test_tool());
+        assert_eq!(request.body["tools"][0]["type"], "function");
+        assert_eq!(request.body["tools"][0]["function"]["parameters"]["type"], "object");
+        assert_eq!(
+            request.body["tool_choice"],
+            json!({"type": "function", "function": {"name": "record_entities"}})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_complete_structured_against_mock() {
+        let server = MockServer::start(vec![json_response(OPENAI_TOOL_CALL_RESPONSE)]).await;
+        let client = LLMClient::new(LLMProvider::OpenAICompatible {
+            base_url: server.base_url.clone(),
+            api_key: None,
+            model: "llama3.1".to_string(),
+        });
+
+        let output = client
+            .complete_structured("Extract entities", "Alice is looking at GraphQL", &test_tool())
+            .await
+            .unwrap();
+
+        assert!(matches!(output, StructuredOutput::Json(_)));
+        let request = &server.requests()[0];
+        assert_eq!(request["tool_choice"]["function"]["name"], "record_entities");
+        assert_eq!(request["messages"][1]["content"], "Alice is looking at GraphQL");
+    }
+
+    fn tool_conversation() -> Vec<ToolTurn> {
+        vec![
+            ToolTurn::Message(ChatMessage::user("What is Alice working on?")),
+            ToolTurn::ToolCalls {
+                text: "Let me check.".to_string(),
+                calls: vec![ToolCall {
+                    id: "toolu_01".to_string(),
+                    name: "get_person".to_string(),
+                    arguments: json!({"name": "Alice"}),
+                }],
+            },
+            ToolTurn::ToolResults(vec![ToolResult {
+                call_id: "toolu_01".to_string(),
+                content: "Alice works on GraphQL".to_string(),
+                is_error: false,
+            }]),
+        ]
+    }
+
+    #[test]
+    fn test_anthropic_tool_messages() {
+        let mut turns = vec![ToolTurn::Message(ChatMessage::assistant("Earlier reply"))];
+        turns.extend(tool_conversation());
+
+        let messages = LLMClient::anthropic_tool_messages(&turns);
+
+        // The leading assistant turn is dropped
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0], json!({"role": "user", "content": "What is Alice working on?"}));
+        assert_eq!(
+            messages[1],
+            json!({"role": "assistant", "content": [
+                {"type": "text", "text": "Let me check."},
+                {"type": "tool_use", "id": "toolu_01", "name": "get_person", "input": {"name": "Alice"}}
+            ]})
+        );
+        assert_eq!(
+            messages[2],
+            json!({"role": "user", "content": [
+                {"type": "tool_result", "tool_use_id": "toolu_01", "content": "Alice works on GraphQL", "is_error": false}
+            ]})
+        );
+    }
+
+    #[test]
+    fn test_openai_tool_messages() {
+        let messages = LLMClient::openai_tool_messages("sys", &tool_conversation());
+
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[0], json!({"role": "system", "content": "sys"}));
+        assert_eq!(
+            messages[2],
+            json!({"role": "assistant", "content": "Let me check.", "tool_calls": [
+                {"id": "toolu_01", "type": "function", "function": {"name": "get_person", "arguments": "{\"name\":\"Alice\"}"}}
+            ]})
+        );
+        assert_eq!(
+            messages[3],
+            json!({"role": "tool", "tool_call_id": "toolu_01", "content": "Alice works on GraphQL"})
+        );
+    }
+
+    #[test]
+    fn test_anthropic_parse_tool_reply() {
+        // Recorded reply with text alongside two parallel tool calls
+        let response = r#"{
+  "id": "msg_01Aq9w938a90dw8q",
+  "type": "message",
+  "role": "assistant",
+  "model": "claude-3-5-sonnet-20241022",
+  "content": [
+    {"type": "text", "text": "I'll look that up."},
+    {"type": "tool_use", "id": "toolu_01T1x1fJ34qAmk2tNTrN7Up6", "name": "get_person", "input": {"name": "Alice"}},
+    {"type": "tool_use", "id": "toolu_01BxM9bCyFh6KQh5iYbGv6pq", "name": "list_tasks", "input": {}}
+  ],
+  "stop_reason": "tool_use",
+  "stop_sequence": null,
+  "usage": {"input_tokens": 1021, "output_tokens": 94}
+}"#;
+
+        let ToolReply::ToolCalls { text, calls } = AnthropicParser.parse_tool_reply(response).unwrap() else {
+            panic!("expected tool calls");
+        };
+        assert_eq!(text, "I'll look that up.");
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].id, "toolu_01T1x1fJ34qAmk2tNTrN7Up6");
+        assert_eq!(calls[0].arguments, json!({"name": "Alice"}));
+        assert_eq!(calls[1].name, "list_tasks");
+
+        let final_answer = r#"{"content": [{"type": "text", "text": "Alice works on GraphQL."}], "stop_reason": "end_turn"}"#;
+        assert_eq!(
+            AnthropicParser.parse_tool_reply(final_answer).unwrap(),
+            ToolReply::Text("Alice works on GraphQL.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_openai_parse_tool_reply() {
+        let response = r#"{
+  "id": "chatcmpl-9pMfX8kq2Lw0aZc7Rt3Yb1NvE5sHd",
+  "object": "chat.completion",
+  "model": "gpt-4o-2024-08-06",
+  "choices": [{
+    "index": 0,
+    "message": {
+      "role": "assistant",
+      "content": null,
+      "tool_calls": [
+        {"id": "call_8fVw2nJr5tK1xQ0zL7mB3cYd", "type": "function", "function": {"name": "search_memory", "arguments": "{\"query\":\"billing\"}"}},
+        {"id": "call_Hc4bN9pS2wE6rT1yU8iO0aZx", "type": "function", "function": {"name": "get_person", "arguments": "{\"name\": \"Al"}}
+      ]
+    },
+    "finish_reason": "tool_calls"
+  }]
+}"#;
+
+        let ToolReply::ToolCalls { text, calls } = OpenAIParser.parse_tool_reply(response).unwrap() else {
+            panic!("expected tool calls");
+        };
+        assert_eq!(text, "");
+        assert_eq!(calls[0].id, "call_8fVw2nJr5tK1xQ0zL7mB3cYd");
+        assert_eq!(calls[0].arguments, json!({"query": "billing"}));
+        // Truncated arguments are passed through as a string
+        assert_eq!(calls[1].arguments, json!("{\"name\": \"Al"));
+    }
+
+    #[tokio::test]
+    async fn test_chat_with_tools_against_mock() {
+        let server = MockServer::start(vec![json_response(
+            r#"{"choices":[{"index":0,"message":{"role":"assistant","content":"Alice works on GraphQL."},"finish_reason":"stop"}]}"#,
+        )])
+        .await;
+        let client = LLMClient::new(LLMProvider::OpenAICompatible {
+            base_url: server.base_url.clone(),
+            api_key: None,
+            model: "llama3.1".to_string(),
+        });
+
+        let reply = client
+            .chat_with_tools("sys", &tool_conversation(), &[test_tool()], false)
+            .await
+            .unwrap();
+
+        assert_eq!(reply, ToolReply::Text("Alice works on GraphQL.".to_string()));
+        let request = &server.requests()[0];
+        assert_eq!(request["tool_choice"], "none");
+        assert_eq!(request["tools"][0]["function"]["name"], "record_entities");
+        assert_eq!(request["messages"][3]["role"], "tool");
+    }
 }
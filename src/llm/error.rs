@@ -0,0 +1,165 @@
+use std::fmt;
+use std::time::Duration;
+
+/// Failure of an LLM API call, classified so callers can react to each case
+///
+/// Returned inside [`anyhow::Error`]; use `error.downcast_ref::<LLMError>()`
+/// to inspect it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LLMError {
+    /// HTTP 429: too many requests or tokens
+    RateLimited {
+        provider: String,
+        retry_after: Option<Duration>,
+        message: String,
+    },
+    /// HTTP 401/403: missing, invalid or unauthorized API key
+    Auth {
+        provider: String,
+        status: u16,
+        message: String,
+    },
+    /// Any other 4xx: the request itself is wrong (model name, payload, ...)
+    BadRequest {
+        provider: String,
+        status: u16,
+        message: String,
+    },
+    /// HTTP 5xx, including Anthropic's 529 "overloaded"
+    ServerError {
+        provider: String,
+        status: u16,
+        retry_after: Option<Duration>,
+        message: String,
+    },
+    /// No response within the request timeout
+    Timeout { provider: String },
+    /// Connection could not be established or was dropped
+    Network { provider: String, message: String },
+}
+
+impl LLMError {
+    /// Classify a non-success HTTP response
+    pub fn from_status(
+        provider: &str,
+        status: u16,
+        retry_after: Option<Duration>,
+        message: impl Into<String>,
+    ) -> Self {
+        let provider = provider.to_string();
+        let message = message.into();
+
+        match status {
+            429 => LLMError::RateLimited { provider, retry_after, message },
+            401 | 403 => LLMError::Auth { provider, status, message },
+            408 => LLMError::Timeout { provider },
+            400..=499 => LLMError::BadRequest { provider, status, message },
+            _ => LLMError::ServerError { provider, status, retry_after, message },
+        }
+    }
+
+    /// Classify a transport error from `reqwest`
+    pub fn from_reqwest(provider: &str, error: &reqwest::Error) -> Self {
+        if error.is_timeout() {
+            LLMError::Timeout { provider: provider.to_string() }
+        } else {
+            LLMError::Network {
+                provider: provider.to_string(),
+                message: error.to_string(),
+            }
+        }
+    }
+
+    /// Whether the same request may succeed if sent again later
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            LLMError::RateLimited { .. }
+                | LLMError::ServerError { .. }
+                | LLMError::Timeout { .. }
+                | LLMError::Network { .. }
+        )
+    }
+
+    /// Delay requested by the server's `retry-after` header, if any
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            LLMError::RateLimited { retry_after, .. } | LLMError::ServerError { retry_after, .. } => {
+                *retry_after
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for LLMError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LLMError::RateLimited { provider, message, .. } => {
+                write!(f, "{} API rate limit exceeded (429): {}", provider, message)
+            }
+            LLMError::Auth { provider, status, message } => {
+                write!(f, "{} API authentication failed ({}): {}", provider, status, message)
+            }
+            LLMError::BadRequest { provider, status, message } => {
+                write!(f, "{} API rejected the request ({}): {}", provider, status, message)
+            }
+            LLMError::ServerError { provider, status, message, .. } => {
+                write!(f, "{} API server error ({}): {}", provider, status, message)
+            }
+            LLMError::Timeout { provider } => write!(f, "{} API request timed out", provider),
+            LLMError::Network { provider, message } => {
+                write!(f, "Failed to reach {} API: {}", provider, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LLMError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_status_classification() {
+        let wait = Some(Duration::from_secs(3));
+
+        assert_eq!(
+            LLMError::from_status("Anthropic", 429, wait, "slow down"),
+            LLMError::RateLimited {
+                provider: "Anthropic".to_string(),
+                retry_after: wait,
+                message: "slow down".to_string(),
+            }
+        );
+        assert!(matches!(LLMError::from_status("OpenAI", 401, None, ""), LLMError::Auth { status: 401, .. }));
+        assert!(matches!(LLMError::from_status("OpenAI", 403, None, ""), LLMError::Auth { status: 403, .. }));
+        assert!(matches!(LLMError::from_status("OpenAI", 404, None, ""), LLMError::BadRequest { status: 404, .. }));
+        assert!(matches!(LLMError::from_status("OpenAI", 408, None, ""), LLMError::Timeout { .. }));
+        assert!(matches!(
+            LLMError::from_status("Anthropic", 529, None, "overloaded"),
+            LLMError::ServerError { status: 529, .. }
+        ));
+    }
+
+    #[test]
+    fn test_retryable() {
+        assert!(LLMError::from_status("X", 429, None, "").is_retryable());
+        assert!(LLMError::from_status("X", 503, None, "").is_retryable());
+        assert!(!LLMError::from_status("X", 400, None, "").is_retryable());
+        assert!(!LLMError::from_status("X", 401, None, "").is_retryable());
+    }
+
+    #[test]
+    fn test_downcast_through_anyhow() {
+        let error: anyhow::Error = LLMError::from_status("OpenAI", 401, None, "bad key").into();
+        let error = error.context("Failed to extract entities");
+
+        assert!(matches!(error.downcast_ref::<LLMError>(), Some(LLMError::Auth { .. })));
+        assert_eq!(
+            error.root_cause().to_string(),
+            "OpenAI API authentication failed (401): bad key"
+        );
+    }
+}
@@ -1,20 +1,74 @@
 use anyhow::{Context, Result};
-use serde_json::json;
-use super::client::LLMClient;
-use crate::graph::schema::ExtractedEntities;
+use std::sync::Arc;
+
+use super::client::{StructuredOutput, ToolDefinition};
+use super::error::LLMError;
+use super::model::ChatModel;
+use crate::graph::schema::{
+    normalize_entity_name, normalize_relation_type, EntityKind, ExtractedEntities, ExtractedRelation,
+    MergeProposal, DEFAULT_RELATION_TYPES,
+};
+
+/// Name of the tool the model calls with the extracted entities
+pub const EXTRACTION_TOOL_NAME: &str = "record_entities";
+
+/// Name of the tool the model calls with proposed entity merges
+pub const MERGE_TOOL_NAME: &str = "propose_merges";
 
 /// Entity extractor using LLM
 pub struct EntityExtractor {
-    llm_client: LLMClient,
+    llm_client: Arc<dyn ChatModel>,
+    relation_types: Vec<String>,
 }
 
 impl EntityExtractor {
-    /// Create a new entity extractor
-    pub fn new(llm_client: LLMClient) -> Self {
-        Self { llm_client }
+    /// Create a new entity extractor using [`DEFAULT_RELATION_TYPES`]
+    pub fn new(llm_client: impl ChatModel + 'static) -> Self {
+        Self {
+            llm_client: Arc::new(llm_client),
+            relation_types: DEFAULT_RELATION_TYPES.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    /// Set the relationship types the extractor may return (e.g. `WORKS_ON`)
+    ///
+    /// Types are normalized to edge labels; invalid ones are ignored. An empty
+    /// list disables relation extraction.
+    pub fn set_relation_types<I, S>(&mut self, relation_types: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.relation_types = relation_types
+            .into_iter()
+            .filter_map(|t| normalize_relation_type(t.as_ref()))
+            .collect();
+    }
+
+    /// Relationship types the extractor may return
+    pub fn relation_types(&self) -> &[String] {
+        &self.relation_types
+    }
+
+    /// Tool the model is asked to call with the extracted entities
+    fn extraction_tool(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: EXTRACTION_TOOL_NAME.to_string(),
+            description: "Record the entities and relations extracted from the user's message".to_string(),
+            input_schema: ExtractedEntities::json_schema(&self.relation_types),
+        }
     }
 
     /// Extract entities from a user message
+    ///
+    /// The model returns the entities as a forced tool call (Anthropic tool
+    /// use, OpenAI function calling). If the provider rejects tools, or the
+    /// model answers in prose, the JSON object is scraped from the text.
+    ///
+    /// Extraction is best-effort: if the LLM is still rate limited or
+    /// unavailable after retries, no entities are returned so the message can
+    /// be stored and answered anyway. Authentication failures and rejected
+    /// requests are configuration problems and are returned as errors.
     pub async fn extract(&self, message: &str) -> Result<ExtractedEntities> {
         let system_prompt = r#"You are an expert entity extractor for an AI agent's memory system.
 Extract the following types of entities from the user's message:
@@ -23,34 +77,124 @@ Extract the following types of entities from the user's message:
 2. TOPICS: Subjects, concepts, technologies, projects, or areas of interest
 3. TASKS: Action items, todos, or work that needs to be done
 4. DOCUMENTS: Files, links, resources, or references mentioned
+5. COMPLETED_TASKS: Work the user reports as already finished or done
+6. RELATIONS: How the entities above relate, as subject-predicate-object triples.
+   The predicate must be one of: {relation_types}
 
 Return your response as a JSON object with this exact structure:
 {
   "people": ["name1", "name2"],
   "topics": ["topic1", "topic2"],
   "tasks": ["task1", "task2"],
-  "documents": ["doc1", "doc2"]
+  "documents": ["doc1", "doc2"],
+  "completed_tasks": ["task3"],
+  "relations": [{"subject": "name1", "predicate": "WORKS_ON", "object": "topic1"}]
 }
 
 Guidelines:
 - Only extract entities that are explicitly mentioned or clearly implied
 - For topics, include both specific technologies and general concepts
 - For tasks, extract actionable items in imperative form
+- For documents, keep any URL or file name exactly as written, e.g. "Q3 roadmap (https://...)"
+- When the user says they finished something ("I finished X", "X is done"), put X in
+  completed_tasks in imperative form and do NOT also list it under tasks
+- A relation's subject and object must be written exactly as in the lists above; only
+  include relations the message states, not ones that merely co-occur
 - If a category has no entities, use an empty array []
 - Be precise and avoid over-extraction
 
-Return ONLY the JSON object, no additional text."#;
+Return ONLY the JSON object, no additional text."#
+            .replace("{relation_types}", &self.relation_types.join(", "));
+
+        let output = match self
+            .llm_client
+            .complete_structured(&system_prompt, message, &self.extraction_tool())
+            .await
+        {
+            Ok(output) => output,
+            // Servers and models without tool support reject the request
+            Err(error) if matches!(error.downcast_ref::<LLMError>(), Some(LLMError::BadRequest { .. })) => {
+                match self.llm_client.complete(&system_prompt, message).await {
+                    Ok(response) => StructuredOutput::Text(response),
+                    Err(error) => return Self::extraction_failed(error),
+                }
+            }
+            Err(error) => return Self::extraction_failed(error),
+        };
+
+        match output {
+            StructuredOutput::Json(value) => Ok(self.entities_from_value(&value)),
+            StructuredOutput::Text(response) => self.parse_extraction_response(&response),
+        }
+    }
+
+    /// Ask the model which of the given names refer to the same entity
+    ///
+    /// Proposals are only suggestions: they name existing entities and must be
+    /// confirmed before being applied with `GraphDB::merge_entities`.
+    pub async fn propose_merges(&self, kind: EntityKind, names: &[String]) -> Result<Vec<MergeProposal>> {
+        if names.len() < 2 {
+            return Ok(Vec::new());
+        }
 
-        let response = self
+        let system_prompt = format!(
+            r#"You deduplicate the {label} entities in an AI agent's memory graph.
+Given a list of {label} names, group the names that refer to the same {label}
+(nicknames, abbreviations, misspellings, different capitalization or word order).
+
+For each group, pick the most complete name as "canonical" and list the others as "aliases".
+Only group names you are confident about; names without duplicates are left out.
+Every name must be copied exactly from the list."#,
+            label = kind.label().to_lowercase()
+        );
+        let tool = ToolDefinition {
+            name: MERGE_TOOL_NAME.to_string(),
+            description: "Record groups of names that refer to the same entity".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "merges": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "canonical": {"type": "string"},
+                                "aliases": {"type": "array", "items": {"type": "string"}}
+                            },
+                            "required": ["canonical", "aliases"]
+                        }
+                    }
+                },
+                "required": ["merges"]
+            }),
+        };
+
+        let output = self
             .llm_client
-            .complete(system_prompt, message)
+            .complete_structured(&system_prompt, &names.join("\n"), &tool)
             .await
-            .context("Failed to extract entities from message")?;
+            .context("Failed to ask for entity merges")?;
+        let value = match output {
+            StructuredOutput::Json(value) => value,
+            StructuredOutput::Text(response) => {
+                let start = response.find('{').unwrap_or(0);
+                let end = response.rfind('}').map_or(response.len(), |end| end + 1);
+                serde_json::from_str(response.get(start..end).unwrap_or_default())
+                    .context("Failed to parse merge proposals")?
+            }
+        };
 
-        // Parse JSON response
-        let entities = self.parse_extraction_response(&response)?;
+        Ok(merge_proposals_from_value(kind, names, &value))
+    }
 
-        Ok(entities)
+    /// Result of an extraction whose LLM call failed: no entities if the
+    /// failure was transient, otherwise the error
+    fn extraction_failed(error: anyhow::Error) -> Result<ExtractedEntities> {
+        if error.downcast_ref::<LLMError>().is_some_and(LLMError::is_retryable) {
+            Ok(ExtractedEntities::default())
+        } else {
+            Err(error.context("Failed to extract entities from message"))
+        }
     }
 
     /// Extract a string array from a JSON value, returning empty vec if not present
@@ -65,36 +209,111 @@ Return ONLY the JSON object, no additional text."#;
             .unwrap_or_default()
     }
 
-    /// Parse the LLM's extraction response
+    /// Extract subject-predicate-object relations whose predicate is in the
+    /// configured vocabulary, skipping malformed entries
+    fn extract_relations(&self, value: &serde_json::Value) -> Vec<ExtractedRelation> {
+        let Some(relations) = value["relations"].as_array() else {
+            return Vec::new();
+        };
+
+        relations
+            .iter()
+            .filter_map(|relation| {
+                let subject = relation["subject"].as_str()?.trim();
+                let object = relation["object"].as_str()?.trim();
+                let predicate = normalize_relation_type(relation["predicate"].as_str()?)?;
+                if subject.is_empty() || object.is_empty() || !self.relation_types.contains(&predicate) {
+                    return None;
+                }
+                Some(ExtractedRelation {
+                    subject: subject.to_string(),
+                    predicate,
+                    object: object.to_string(),
+                })
+            })
+            .collect()
+    }
+
+    /// Parse a plain-text extraction response by scraping the JSON object out of it
+    ///
+    /// Only used when the model did not return the entities as a tool call.
     fn parse_extraction_response(&self, response: &str) -> Result<ExtractedEntities> {
         // Try to extract JSON from the response (handle cases where LLM adds extra text)
-        let json_str = if let Some(start) = response.find('{') {
-            if let Some(end) = response.rfind('}') {
-                &response[start..=end]
-            } else {
-                response
-            }
-        } else {
-            response
+        // Prose may put a '}' before the first '{', so the slice can be empty
+        let json_str = match (response.find('{'), response.rfind('}')) {
+            (Some(start), Some(end)) => response.get(start..=end).unwrap_or(response),
+            _ => response,
         };
 
         let parsed: serde_json::Value = serde_json::from_str(json_str)
             .context("Failed to parse entity extraction JSON")?;
 
-        let entities = ExtractedEntities {
-            people: Self::extract_string_array(&parsed, "people"),
-            topics: Self::extract_string_array(&parsed, "topics"),
-            tasks: Self::extract_string_array(&parsed, "tasks"),
-            documents: Self::extract_string_array(&parsed, "documents"),
+        Ok(self.entities_from_value(&parsed))
+    }
+
+    /// Read extracted entities from a JSON object, skipping malformed entries
+    fn entities_from_value(&self, value: &serde_json::Value) -> ExtractedEntities {
+        ExtractedEntities {
+            people: Self::extract_string_array(value, "people"),
+            topics: Self::extract_string_array(value, "topics"),
+            tasks: Self::extract_string_array(value, "tasks"),
+            documents: Self::extract_string_array(value, "documents"),
+            completed_tasks: Self::extract_string_array(value, "completed_tasks"),
+            relations: self.extract_relations(value),
+        }
+    }
+}
+
+/// Read merge proposals, keeping only names from `names` (matched exactly or
+/// after normalization) and using each name at most once
+fn merge_proposals_from_value(kind: EntityKind, names: &[String], value: &serde_json::Value) -> Vec<MergeProposal> {
+    let known = |name: &str| {
+        names
+            .iter()
+            .find(|known| known.as_str() == name)
+            .or_else(|| names.iter().find(|known| normalize_entity_name(known) == normalize_entity_name(name)))
+            .cloned()
+    };
+    let Some(merges) = value["merges"].as_array() else {
+        return Vec::new();
+    };
+
+    let mut used: Vec<String> = Vec::new();
+    let mut proposals = Vec::new();
+    for merge in merges {
+        let Some(canonical) = merge["canonical"].as_str().and_then(known) else {
+            continue;
         };
+        if used.contains(&canonical) {
+            continue;
+        }
 
-        Ok(entities)
+        let mut aliases: Vec<String> = Vec::new();
+        for alias in EntityExtractor::extract_string_array(merge, "aliases") {
+            let Some(alias) = known(&alias) else {
+                continue;
+            };
+            if alias != canonical && !aliases.contains(&alias) && !used.contains(&alias) {
+                aliases.push(alias);
+            }
+        }
+        if aliases.is_empty() {
+            continue;
+        }
+
+        used.push(canonical.clone());
+        used.extend(aliases.iter().cloned());
+        proposals.push(MergeProposal { kind, canonical, aliases });
     }
+    proposals
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(feature = "local")]
+    use crate::llm::mock_server::{http_response, json_response, MockServer};
+    use crate::llm::{ScriptedModel, ScriptedReply};
     use serde_json::json;
 
     #[test]
@@ -141,14 +360,13 @@ mod tests {
         assert_eq!(result, vec!["string", "another"]);
     }
 
+    fn test_extractor() -> EntityExtractor {
+        EntityExtractor::new(ScriptedModel::new([]))
+    }
+
     #[test]
     fn test_parse_extraction_response() {
-        let extractor = EntityExtractor {
-            llm_client: LLMClient::new(crate::llm::LLMProvider::Anthropic {
-                api_key: "test".to_string(),
-                model: "test".to_string(),
-            }),
-        };
+        let extractor = test_extractor();
 
         let response = r#"{
   "people": ["Alice", "Bob"],
@@ -163,5 +381,229 @@ mod tests {
         assert_eq!(entities.topics.len(), 2);
         assert_eq!(entities.tasks.len(), 2);
         assert_eq!(entities.documents.len(), 0);
+        assert_eq!(entities.completed_tasks.len(), 0);
+    }
+
+    #[test]
+    fn test_parse_extraction_response_stray_braces() {
+        let extractor = test_extractor();
+
+        // A closing brace before the only opening one fails instead of panicking
+        assert!(extractor.parse_extraction_response("Use } then {").is_err());
+
+        let entities = extractor
+            .parse_extraction_response(r#"Use } then {"people": ["Alice"]}"#)
+            .unwrap();
+        assert_eq!(entities.people, ["Alice"]);
+    }
+
+    #[test]
+    fn test_parse_extraction_response_completed_tasks() {
+        let extractor = test_extractor();
+
+        let response = r#"{
+  "people": [],
+  "topics": ["GraphQL"],
+  "tasks": [],
+  "documents": [],
+  "completed_tasks": ["Write the GraphQL migration plan"]
+}"#;
+
+        let entities = extractor.parse_extraction_response(response).unwrap();
+
+        assert!(entities.tasks.is_empty());
+        assert_eq!(entities.completed_tasks, vec!["Write the GraphQL migration plan"]);
+    }
+
+    #[test]
+    fn test_parse_extraction_response_relations() {
+        let extractor = test_extractor();
+
+        let response = r#"{
+  "people": ["Alice"],
+  "topics": ["GraphQL migration"],
+  "tasks": [],
+  "documents": [],
+  "relations": [
+    {"subject": "Alice", "predicate": "works on", "object": "GraphQL migration"},
+    {"subject": "Alice", "predicate": "HATES", "object": "GraphQL migration"},
+    {"subject": "Alice", "predicate": "KNOWS"}
+  ]
+}"#;
+
+        let entities = extractor.parse_extraction_response(response).unwrap();
+
+        // Unknown predicates and incomplete triples are dropped
+        assert_eq!(
+            entities.relations,
+            vec![ExtractedRelation {
+                subject: "Alice".to_string(),
+                predicate: "WORKS_ON".to_string(),
+                object: "GraphQL migration".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_set_relation_types_normalizes_vocabulary() {
+        let mut extractor = test_extractor();
+        extractor.set_relation_types(["reports to", "KNOWS", "bad label!"]);
+        assert_eq!(extractor.relation_types(), ["REPORTS_TO", "KNOWS"]);
+
+        let response = r#"{"relations": [{"subject": "Bob", "predicate": "REPORTS_TO", "object": "Alice"}]}"#;
+        let entities = extractor.parse_extraction_response(response).unwrap();
+        assert_eq!(entities.relations.len(), 1);
+    }
+
+    #[cfg(feature = "local")]
+    fn mock_extractor(server: &MockServer) -> EntityExtractor {
+        EntityExtractor::new(crate::llm::LLMClient::new(crate::llm::LLMProvider::OpenAICompatible {
+            base_url: server.base_url.clone(),
+            api_key: None,
+            model: "test".to_string(),
+        }))
+    }
+
+    #[cfg(feature = "local")]
+    #[tokio::test]
+    async fn test_extract_uses_tool_call() {
+        // Recorded function-calling reply; braces inside values are harmless here
+        let server = MockServer::start(vec![json_response(r#"{
+  "id": "chatcmpl-9pLe7c3Yh0sVJqk2a1mZ8tXnW4bDf",
+  "object": "chat.completion",
+  "model": "gpt-4o-mini-2024-07-18",
+  "choices": [{
+    "index": 0,
+    "message": {
+      "role": "assistant",
+      "content": null,
+      "tool_calls": [{
+        "id": "call_2kQ9rT1vXb8nM3pL6sJ0wYcE",
+        "type": "function",
+        "function": {
+          "name": "record_entities",
+          "arguments": "{\"people\":[\"Alice\"],\"topics\":[\"config {json}\"],\"tasks\":[\"Review the schema\"],\"documents\":[],\"completed_tasks\":[],\"relations\":[{\"subject\":\"Alice\",\"predicate\":\"WORKS_ON\",\"object\":\"config {json}\"}]}"
+        }
+      }]
+    },
+    "finish_reason": "stop"
+  }]
+}"#)])
+        .await;
+
+        let entities = mock_extractor(&server)
+            .extract("Alice works on the config {json} format; review the schema")
+            .await
+            .unwrap();
+
+        assert_eq!(entities.people, vec!["Alice"]);
+        assert_eq!(entities.topics, vec!["config {json}"]);
+        assert_eq!(entities.tasks, vec!["Review the schema"]);
+        assert_eq!(entities.relations.len(), 1);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0]["tools"][0]["function"]["name"], EXTRACTION_TOOL_NAME);
+        assert_eq!(
+            requests[0]["tools"][0]["function"]["parameters"]["properties"]["relations"]["items"]["properties"]["predicate"]["enum"],
+            json!(["WORKS_ON", "KNOWS", "DEPENDS_ON"])
+        );
+    }
+
+    #[cfg(feature = "local")]
+    #[tokio::test]
+    async fn test_extract_falls_back_to_text_without_tool_support() {
+        let server = MockServer::start(vec![
+            // Recorded rejection from a local server whose model has no tool support
+            http_response(
+                "400 Bad Request",
+                "content-type: application/json\r\n",
+                r#"{"error":{"message":"registry.ollama.ai/library/gemma:2b does not support tools","type":"api_error","param":null,"code":null}}"#,
+            ),
+            json_response(r#"{"choices":[{"index":0,"message":{"role":"assistant","content":"Here you go:\n{\"people\": [\"Bob\"], \"topics\": [], \"tasks\": [], \"documents\": []}"},"finish_reason":"stop"}]}"#),
+        ])
+        .await;
+
+        let entities = mock_extractor(&server).extract("Ask Bob").await.unwrap();
+
+        assert_eq!(entities.people, vec!["Bob"]);
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].get("tools").is_none());
+    }
+
+    #[cfg(feature = "local")]
+    #[tokio::test]
+    async fn test_extract_scrapes_prose_reply() {
+        let server = MockServer::start(vec![json_response(
+            r#"{"choices":[{"index":0,"message":{"role":"assistant","content":"```json\n{\"people\": [], \"topics\": [\"Rust\"], \"tasks\": [], \"documents\": []}\n```"},"finish_reason":"stop"}]}"#,
+        )])
+        .await;
+
+        let entities = mock_extractor(&server).extract("I like Rust").await.unwrap();
+
+        assert_eq!(entities.topics, vec!["Rust"]);
+    }
+
+    #[tokio::test]
+    async fn test_propose_merges_with_scripted_model() {
+        let model = std::sync::Arc::new(ScriptedModel::new([ScriptedReply::Json(json!({
+            "merges": [{"canonical": "Alice Chen", "aliases": ["alice"]}]
+        }))]));
+        let extractor = EntityExtractor::new(model.clone());
+
+        let names = vec!["Alice Chen".to_string(), "alice".to_string(), "Bob".to_string()];
+        let proposals = extractor.propose_merges(EntityKind::Person, &names).await.unwrap();
+
+        assert_eq!(proposals.len(), 1);
+        assert_eq!(proposals[0].canonical, "Alice Chen");
+        let requests = model.requests();
+        assert_eq!(requests[0].tools, [MERGE_TOOL_NAME]);
+        assert_eq!(requests[0].messages[0].content, "Alice Chen\nalice\nBob");
+    }
+
+    #[test]
+    fn test_extraction_tool_schema() {
+        let mut extractor = test_extractor();
+        extractor.set_relation_types(["KNOWS"]);
+
+        let tool = extractor.extraction_tool();
+
+        assert_eq!(tool.name, EXTRACTION_TOOL_NAME);
+        assert_eq!(tool.input_schema, ExtractedEntities::json_schema(&["KNOWS".to_string()]));
+    }
+
+    #[test]
+    fn test_merge_proposals_from_value() {
+        let names: Vec<String> = ["Alice Chen", "alice", "Bob", "Robert", "Carol"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        let value = json!({
+            "merges": [
+                {"canonical": "Alice Chen", "aliases": ["Alice", "Alice Chen", "Dave"]},
+                {"canonical": "Robert", "aliases": ["Bob", "alice"]},
+                {"canonical": "Carol", "aliases": []},
+                {"canonical": "Eve", "aliases": ["Carol"]}
+            ]
+        });
+
+        // Unknown names, self-aliases and names already used are dropped
+        assert_eq!(
+            merge_proposals_from_value(EntityKind::Person, &names, &value),
+            vec![
+                MergeProposal {
+                    kind: EntityKind::Person,
+                    canonical: "Alice Chen".to_string(),
+                    aliases: vec!["alice".to_string()],
+                },
+                MergeProposal {
+                    kind: EntityKind::Person,
+                    canonical: "Robert".to_string(),
+                    aliases: vec!["Bob".to_string()],
+                },
+            ]
+        );
+        assert!(merge_proposals_from_value(EntityKind::Person, &names, &json!({})).is_empty());
     }
 }
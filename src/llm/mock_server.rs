@@ -0,0 +1,87 @@
+//! Minimal HTTP server replaying canned responses, for testing the LLM client
+//! against recorded provider payloads
+
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Local server answering each connection with the next canned response
+pub struct MockServer {
+    /// API root to use as a provider base URL, e.g. `http://127.0.0.1:1234/v1`
+    pub base_url: String,
+    requests: Arc<Mutex<Vec<serde_json::Value>>>,
+}
+
+impl MockServer {
+    /// Serve the given raw HTTP responses, one per connection
+    pub async fn start(responses: Vec<String>) -> Self {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let body = read_request_body(&mut socket).await;
+                recorded
+                    .lock()
+                    .unwrap()
+                    .push(serde_json::from_slice(&body).unwrap_or_default());
+                socket.write_all(response.as_bytes()).await.unwrap();
+                socket.shutdown().await.unwrap();
+            }
+        });
+
+        Self {
+            base_url: format!("http://{}/v1", address),
+            requests,
+        }
+    }
+
+    /// JSON bodies of the requests received so far
+    pub fn requests(&self) -> Vec<serde_json::Value> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// Raw HTTP/1.1 response with the given status line, extra headers and body
+pub fn http_response(status: &str, headers: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\n{}content-length: {}\r\nconnection: close\r\n\r\n{}",
+        status,
+        headers,
+        body.len(),
+        body
+    )
+}
+
+/// `200 OK` response with a JSON body
+pub fn json_response(body: &str) -> String {
+    http_response("200 OK", "content-type: application/json\r\n", body)
+}
+
+/// Read one request and return its body, using `content-length`
+async fn read_request_body(socket: &mut tokio::net::TcpStream) -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut buffer = [0; 8192];
+
+    loop {
+        if let Some(header_end) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            let headers = String::from_utf8_lossy(&data[..header_end]).to_lowercase();
+            let length = headers
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length:"))
+                .and_then(|value| value.trim().parse::<usize>().ok())
+                .unwrap_or(0);
+            let body_start = header_end + 4;
+            if data.len() >= body_start + length {
+                return data[body_start..body_start + length].to_vec();
+            }
+        }
+
+        match socket.read(&mut buffer).await {
+            Ok(0) | Err(_) => return Vec::new(),
+            Ok(n) => data.extend_from_slice(&buffer[..n]),
+        }
+    }
+}
@@ -1,5 +1,15 @@
 pub mod client;
+pub mod error;
 pub mod extraction;
+#[cfg(all(test, feature = "local"))]
+pub(crate) mod mock_server;
+pub mod model;
+pub mod retry;
+pub mod scripted;
 
 pub use client::*;
+pub use error::*;
 pub use extraction::*;
+pub use model::*;
+pub use retry::*;
+pub use scripted::*;
@@ -0,0 +1,150 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::stream;
+use std::sync::Arc;
+
+use super::client::{ChatMessage, LLMClient, StructuredOutput, TextStream, ToolDefinition, ToolReply, ToolTurn};
+
+/// A chat model the memory can talk to
+///
+/// [`LLMClient`] implements it for the built-in providers. Implement it to
+/// route requests through another gateway, or use
+/// [`ScriptedModel`](super::ScriptedModel) in tests. Only [`ChatModel::model`]
+/// and [`ChatModel::chat`] are required; the other methods fall back to
+/// plain chat, so a model without streaming or tool support still works.
+#[async_trait]
+pub trait ChatModel: Send + Sync {
+    /// Model name, as reported to API clients
+    fn model(&self) -> &str;
+
+    /// Send a multi-turn conversation
+    ///
+    /// `messages` are in chronological order and end with a user turn.
+    async fn chat(&self, system: &str, messages: &[ChatMessage]) -> Result<String>;
+
+    /// Send a single user message
+    async fn complete(&self, system: &str, user_message: &str) -> Result<String> {
+        self.chat(system, &[ChatMessage::user(user_message)]).await
+    }
+
+    /// Send a conversation and stream the reply as text deltas
+    ///
+    /// By default the whole reply arrives as one delta.
+    async fn chat_stream(&self, system: &str, messages: &[ChatMessage]) -> Result<TextStream> {
+        let reply = self.chat(system, messages).await?;
+        Ok(Box::pin(stream::iter([Ok(reply)])))
+    }
+
+    /// Ask the model to answer by calling `tool`
+    ///
+    /// By default the request is sent as plain chat and the reply returned as
+    /// [`StructuredOutput::Text`], which callers parse as JSON.
+    async fn complete_structured(
+        &self,
+        system: &str,
+        user_message: &str,
+        tool: &ToolDefinition,
+    ) -> Result<StructuredOutput> {
+        let _ = tool;
+        Ok(StructuredOutput::Text(self.complete(system, user_message).await?))
+    }
+
+    /// Send a conversation that may include tool calls, offering `tools`
+    ///
+    /// By default the tools are not offered: the message turns are sent as
+    /// plain chat and the reply is a final answer.
+    async fn chat_with_tools(
+        &self,
+        system: &str,
+        turns: &[ToolTurn],
+        tools: &[ToolDefinition],
+        allow_calls: bool,
+    ) -> Result<ToolReply> {
+        let _ = (tools, allow_calls);
+        let messages: Vec<ChatMessage> = turns
+            .iter()
+            .filter_map(|turn| match turn {
+                ToolTurn::Message(message) => Some(message.clone()),
+                _ => None,
+            })
+            .collect();
+        Ok(ToolReply::Text(self.chat(system, &messages).await?))
+    }
+}
+
+#[async_trait]
+impl ChatModel for LLMClient {
+    fn model(&self) -> &str {
+        LLMClient::model(self)
+    }
+
+    async fn chat(&self, system: &str, messages: &[ChatMessage]) -> Result<String> {
+        LLMClient::chat(self, system, messages).await
+    }
+
+    async fn complete(&self, system: &str, user_message: &str) -> Result<String> {
+        LLMClient::complete(self, system, user_message).await
+    }
+
+    async fn chat_stream(&self, system: &str, messages: &[ChatMessage]) -> Result<TextStream> {
+        LLMClient::chat_stream(self, system, messages).await
+    }
+
+    async fn complete_structured(
+        &self,
+        system: &str,
+        user_message: &str,
+        tool: &ToolDefinition,
+    ) -> Result<StructuredOutput> {
+        LLMClient::complete_structured(self, system, user_message, tool).await
+    }
+
+    async fn chat_with_tools(
+        &self,
+        system: &str,
+        turns: &[ToolTurn],
+        tools: &[ToolDefinition],
+        allow_calls: bool,
+    ) -> Result<ToolReply> {
+        LLMClient::chat_with_tools(self, system, turns, tools, allow_calls).await
+    }
+}
+
+/// Share one model between the reply and extraction sides
+#[async_trait]
+impl<M: ChatModel + ?Sized> ChatModel for Arc<M> {
+    fn model(&self) -> &str {
+        (**self).model()
+    }
+
+    async fn chat(&self, system: &str, messages: &[ChatMessage]) -> Result<String> {
+        (**self).chat(system, messages).await
+    }
+
+    async fn complete(&self, system: &str, user_message: &str) -> Result<String> {
+        (**self).complete(system, user_message).await
+    }
+
+    async fn chat_stream(&self, system: &str, messages: &[ChatMessage]) -> Result<TextStream> {
+        (**self).chat_stream(system, messages).await
+    }
+
+    async fn complete_structured(
+        &self,
+        system: &str,
+        user_message: &str,
+        tool: &ToolDefinition,
+    ) -> Result<StructuredOutput> {
+        (**self).complete_structured(system, user_message, tool).await
+    }
+
+    async fn chat_with_tools(
+        &self,
+        system: &str,
+        turns: &[ToolTurn],
+        tools: &[ToolDefinition],
+        allow_calls: bool,
+    ) -> Result<ToolReply> {
+        (**self).chat_with_tools(system, turns, tools, allow_calls).await
+    }
+}
@@ -0,0 +1,146 @@
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+
+use super::error::LLMError;
+
+/// How failed LLM requests are retried
+///
+/// Delays grow exponentially from `initial_backoff` up to `max_backoff`,
+/// randomized by ±`jitter` (a fraction of the delay). A `retry-after` header
+/// from the server replaces the computed delay; if it asks for longer than
+/// `max_backoff` the request is not retried.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt (0 disables retrying)
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    /// Random spread applied to each delay, between 0.0 and 1.0
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: 0.25,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy that never retries
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Default::default()
+        }
+    }
+
+    /// Delay before retry number `attempt` (0-based) after `error`, or `None`
+    /// if the request should not be retried
+    pub fn next_delay(&self, attempt: u32, error: &LLMError) -> Option<Duration> {
+        if attempt >= self.max_retries || !error.is_retryable() {
+            return None;
+        }
+        self.delay(attempt, error.retry_after(), fastrand::f64())
+    }
+
+    /// Delay for retry `attempt` given a `retry-after` value and a random
+    /// `sample` in [0, 1)
+    fn delay(&self, attempt: u32, retry_after: Option<Duration>, sample: f64) -> Option<Duration> {
+        if let Some(retry_after) = retry_after {
+            return (retry_after <= self.max_backoff).then_some(retry_after);
+        }
+
+        let backoff = self.initial_backoff.as_secs_f64() * self.multiplier.powi(attempt as i32);
+        let backoff = backoff.min(self.max_backoff.as_secs_f64());
+        let spread = self.jitter.clamp(0.0, 1.0) * (2.0 * sample - 1.0);
+
+        Some(Duration::from_secs_f64((backoff * (1.0 + spread)).max(0.0)))
+    }
+}
+
+/// Parse a `retry-after` header value: delay seconds or an HTTP date
+pub fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<f64>() {
+        return (seconds >= 0.0 && seconds.is_finite()).then(|| Duration::from_secs_f64(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?.with_timezone(&Utc);
+    Some((date - now).to_std().unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rate_limited(retry_after: Option<Duration>) -> LLMError {
+        LLMError::from_status("Test", 429, retry_after, "")
+    }
+
+    #[test]
+    fn test_exponential_backoff_without_jitter() {
+        let policy = RetryPolicy { jitter: 0.0, ..Default::default() };
+
+        assert_eq!(policy.delay(0, None, 0.9), Some(Duration::from_millis(500)));
+        assert_eq!(policy.delay(1, None, 0.9), Some(Duration::from_secs(1)));
+        assert_eq!(policy.delay(2, None, 0.9), Some(Duration::from_secs(2)));
+        // Capped at max_backoff
+        assert_eq!(policy.delay(10, None, 0.9), Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn test_jitter_spreads_delay() {
+        let policy = RetryPolicy { jitter: 0.5, ..Default::default() };
+
+        assert_eq!(policy.delay(1, None, 0.0), Some(Duration::from_millis(500)));
+        assert_eq!(policy.delay(1, None, 0.5), Some(Duration::from_secs(1)));
+        assert_eq!(policy.delay(1, None, 1.0), Some(Duration::from_millis(1500)));
+    }
+
+    #[test]
+    fn test_retry_after_overrides_backoff() {
+        let policy = RetryPolicy::default();
+
+        assert_eq!(
+            policy.delay(0, Some(Duration::from_secs(7)), 0.3),
+            Some(Duration::from_secs(7))
+        );
+        // Longer than we are willing to wait
+        assert_eq!(policy.delay(0, Some(Duration::from_secs(120)), 0.3), None);
+    }
+
+    #[test]
+    fn test_next_delay_limits() {
+        let policy = RetryPolicy { max_retries: 2, ..Default::default() };
+
+        assert!(policy.next_delay(0, &rate_limited(None)).is_some());
+        assert!(policy.next_delay(1, &rate_limited(None)).is_some());
+        assert!(policy.next_delay(2, &rate_limited(None)).is_none());
+        assert!(policy.next_delay(0, &LLMError::from_status("Test", 401, None, "")).is_none());
+        assert!(RetryPolicy::none().next_delay(0, &rate_limited(None)).is_none());
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = DateTime::parse_from_rfc3339("2015-10-21T07:28:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(parse_retry_after("12", now), Some(Duration::from_secs(12)));
+        assert_eq!(parse_retry_after(" 1.5 ", now), Some(Duration::from_millis(1500)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:27:00 GMT", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("-1", now), None);
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+}
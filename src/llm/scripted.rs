@@ -0,0 +1,258 @@
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use futures::stream;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use super::client::{ChatMessage, StructuredOutput, TextStream, ToolCall, ToolDefinition, ToolReply, ToolTurn};
+use super::error::LLMError;
+use super::model::ChatModel;
+
+/// Canned answer to one request sent to a [`ScriptedModel`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptedReply {
+    /// Reply text, also a final answer when tools are offered
+    Text(String),
+    /// Tool input for a structured request; other requests get it as JSON text
+    Json(serde_json::Value),
+    /// Tool calls, for a request that offered tools
+    ToolCalls { text: String, calls: Vec<ToolCall> },
+    /// Fail the request with this error
+    Error(LLMError),
+}
+
+impl ScriptedReply {
+    /// Reply with `text`
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::Text(text.into())
+    }
+
+    /// Call one tool with `arguments`, writing no text alongside
+    pub fn tool_call(id: impl Into<String>, name: impl Into<String>, arguments: serde_json::Value) -> Self {
+        Self::ToolCalls {
+            text: String::new(),
+            calls: vec![ToolCall { id: id.into(), name: name.into(), arguments }],
+        }
+    }
+}
+
+/// A request received by a [`ScriptedModel`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptedRequest {
+    pub system: String,
+    /// Chat turns; for conversations with tool calls, the message turns only
+    pub messages: Vec<ChatMessage>,
+    /// Names of the tools offered
+    pub tools: Vec<String>,
+    /// Whether the model was allowed to call the tools
+    pub allow_calls: bool,
+}
+
+/// [`ChatModel`] answering with scripted replies, for deterministic tests
+///
+/// Each request takes the next reply in order and is recorded, so tests can
+/// check the prompts that were sent. Running out of replies fails the request.
+pub struct ScriptedModel {
+    model: String,
+    replies: Mutex<VecDeque<ScriptedReply>>,
+    requests: Mutex<Vec<ScriptedRequest>>,
+}
+
+impl ScriptedModel {
+    /// Create a model that gives `replies` in order
+    pub fn new(replies: impl IntoIterator<Item = ScriptedReply>) -> Self {
+        Self {
+            model: "scripted".to_string(),
+            replies: Mutex::new(replies.into_iter().collect()),
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Set the model name reported by [`ChatModel::model`]
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Queue another reply after the scripted ones
+    pub fn push(&self, reply: ScriptedReply) {
+        if let Ok(mut replies) = self.replies.lock() {
+            replies.push_back(reply);
+        }
+    }
+
+    /// Requests received so far, oldest first
+    pub fn requests(&self) -> Vec<ScriptedRequest> {
+        self.requests.lock().map(|requests| requests.clone()).unwrap_or_default()
+    }
+
+    /// Number of replies not used yet
+    pub fn remaining(&self) -> usize {
+        self.replies.lock().map(|replies| replies.len()).unwrap_or_default()
+    }
+
+    /// Record `request` and take the next reply
+    fn next_reply(&self, request: ScriptedRequest) -> Result<ScriptedReply> {
+        if let Ok(mut requests) = self.requests.lock() {
+            requests.push(request);
+        }
+        self.replies
+            .lock()
+            .ok()
+            .and_then(|mut replies| replies.pop_front())
+            .context("ScriptedModel has no replies left")
+    }
+
+    /// Next reply as text, for requests that offered no tools
+    fn next_text(&self, system: &str, messages: &[ChatMessage]) -> Result<String> {
+        let request = ScriptedRequest {
+            system: system.to_string(),
+            messages: messages.to_vec(),
+            tools: Vec::new(),
+            allow_calls: false,
+        };
+        match self.next_reply(request)? {
+            ScriptedReply::Text(text) => Ok(text),
+            ScriptedReply::Json(value) => Ok(value.to_string()),
+            ScriptedReply::ToolCalls { .. } => bail!("ScriptedModel: tool calls scripted for a request without tools"),
+            ScriptedReply::Error(error) => Err(error.into()),
+        }
+    }
+}
+
+#[async_trait]
+impl ChatModel for ScriptedModel {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn chat(&self, system: &str, messages: &[ChatMessage]) -> Result<String> {
+        self.next_text(system, messages)
+    }
+
+    /// Streams the reply word by word
+    async fn chat_stream(&self, system: &str, messages: &[ChatMessage]) -> Result<TextStream> {
+        let reply = self.next_text(system, messages)?;
+        let deltas: Vec<Result<String>> = reply.split_inclusive(' ').map(|delta| Ok(delta.to_string())).collect();
+        Ok(Box::pin(stream::iter(deltas)))
+    }
+
+    async fn complete_structured(
+        &self,
+        system: &str,
+        user_message: &str,
+        tool: &ToolDefinition,
+    ) -> Result<StructuredOutput> {
+        let request = ScriptedRequest {
+            system: system.to_string(),
+            messages: vec![ChatMessage::user(user_message)],
+            tools: vec![tool.name.clone()],
+            allow_calls: true,
+        };
+        match self.next_reply(request)? {
+            ScriptedReply::Json(value) => Ok(StructuredOutput::Json(value)),
+            ScriptedReply::Text(text) => Ok(StructuredOutput::Text(text)),
+            ScriptedReply::ToolCalls { .. } => bail!("ScriptedModel: tool calls scripted for a structured request"),
+            ScriptedReply::Error(error) => Err(error.into()),
+        }
+    }
+
+    async fn chat_with_tools(
+        &self,
+        system: &str,
+        turns: &[ToolTurn],
+        tools: &[ToolDefinition],
+        allow_calls: bool,
+    ) -> Result<ToolReply> {
+        let request = ScriptedRequest {
+            system: system.to_string(),
+            messages: turns
+                .iter()
+                .filter_map(|turn| match turn {
+                    ToolTurn::Message(message) => Some(message.clone()),
+                    _ => None,
+                })
+                .collect(),
+            tools: tools.iter().map(|tool| tool.name.clone()).collect(),
+            allow_calls,
+        };
+        match self.next_reply(request)? {
+            ScriptedReply::Text(text) => Ok(ToolReply::Text(text)),
+            ScriptedReply::Json(value) => Ok(ToolReply::Text(value.to_string())),
+            ScriptedReply::ToolCalls { text, calls } => Ok(ToolReply::ToolCalls { text, calls }),
+            ScriptedReply::Error(error) => Err(error.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_replies_in_order_and_records_requests() {
+        let model = ScriptedModel::new([ScriptedReply::text("first"), ScriptedReply::Json(json!({"a": 1}))]);
+
+        assert_eq!(model.complete("sys", "hello").await.unwrap(), "first");
+        assert_eq!(model.chat("sys", &[ChatMessage::user("again")]).await.unwrap(), r#"{"a":1}"#);
+        assert!(model.complete("sys", "more").await.is_err());
+        assert_eq!(model.remaining(), 0);
+
+        let requests = model.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].system, "sys");
+        assert_eq!(requests[0].messages, vec![ChatMessage::user("hello")]);
+    }
+
+    #[tokio::test]
+    async fn test_scripted_error_is_typed() {
+        let model = ScriptedModel::new([ScriptedReply::Error(LLMError::from_status("Scripted", 400, None, "no tools"))]);
+        let error = model.complete("sys", "hello").await.unwrap_err();
+        assert!(matches!(error.downcast_ref::<LLMError>(), Some(LLMError::BadRequest { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_stream_concatenates_to_reply() {
+        let model = ScriptedModel::new([ScriptedReply::text("Alice works on GraphLite")]);
+        let deltas: Vec<String> = model
+            .chat_stream("sys", &[ChatMessage::user("hi")])
+            .await
+            .unwrap()
+            .map(|delta| delta.unwrap())
+            .collect()
+            .await;
+        assert_eq!(deltas, ["Alice ", "works ", "on ", "GraphLite"]);
+    }
+
+    #[tokio::test]
+    async fn test_structured_and_tool_replies() {
+        let tool = ToolDefinition {
+            name: "record_entities".to_string(),
+            description: String::new(),
+            input_schema: json!({"type": "object"}),
+        };
+        let model = ScriptedModel::new([
+            ScriptedReply::Json(json!({"people": ["Alice"]})),
+            ScriptedReply::tool_call("call_1", "list_tasks", json!({})),
+            ScriptedReply::text("Done"),
+        ]);
+
+        assert_eq!(
+            model.complete_structured("sys", "Alice", &tool).await.unwrap(),
+            StructuredOutput::Json(json!({"people": ["Alice"]}))
+        );
+        let turns = [ToolTurn::Message(ChatMessage::user("What is open?"))];
+        let tools = [tool];
+        assert!(matches!(
+            model.chat_with_tools("sys", &turns, &tools, true).await.unwrap(),
+            ToolReply::ToolCalls { calls, .. } if calls[0].name == "list_tasks"
+        ));
+        assert_eq!(
+            model.chat_with_tools("sys", &turns, &tools, false).await.unwrap(),
+            ToolReply::Text("Done".to_string())
+        );
+        assert_eq!(model.requests()[1].tools, ["record_entities"]);
+    }
+}
@@ -0,0 +1,601 @@
+//! Model Context Protocol server over stdio
+//!
+//! Agent hosts start `agentic-memory mcp` as a subprocess and exchange
+//! newline-delimited JSON-RPC 2.0 messages on its stdin and stdout. The
+//! memory is offered as tools (storing and searching messages, people,
+//! topics, tasks and history) and conversations as resources under
+//! `memory://conversations/{id}`. Nothing else may be written to stdout.
+
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use graphlite_sdk::Session;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+use crate::agent::{memory_tools, AgenticMemory, ContextRetriever, MemoryTool};
+use crate::graph::TaskStatus;
+use crate::llm::{ChatRole, ToolCall, ToolDefinition};
+
+/// Protocol revisions this server speaks, newest first
+const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// Title of the conversation messages are stored in when no id is given
+const SESSION_CONVERSATION_TITLE: &str = "MCP session";
+
+/// Messages returned by `get_recent_history` unless the host asks for fewer
+const DEFAULT_HISTORY_LIMIT: usize = 10;
+
+/// Upper bound on `get_recent_history` results
+const MAX_HISTORY_LIMIT: usize = 50;
+
+/// Conversations listed by `resources/list`
+const MAX_LISTED_CONVERSATIONS: usize = 100;
+
+/// URI of the conversation index; conversations live below it
+const CONVERSATIONS_URI: &str = "memory://conversations";
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+/// MCP's code for reading an unknown resource
+const RESOURCE_NOT_FOUND: i64 = -32002;
+
+/// JSON-RPC error object
+#[derive(Debug, Clone, PartialEq)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
+impl From<anyhow::Error> for RpcError {
+    fn from(error: anyhow::Error) -> Self {
+        Self::new(INTERNAL_ERROR, format!("{:#}", error))
+    }
+}
+
+/// A tool call from the host with validated arguments
+#[derive(Debug, Clone, PartialEq)]
+enum McpTool {
+    /// Store a message, extracting entities from user messages
+    StoreMessage {
+        content: String,
+        role: ChatRole,
+        conversation_id: Option<String>,
+    },
+    /// One of the tools the assistant itself can call
+    Memory(MemoryTool),
+    /// Entities connected to a topic
+    TopicContext { name: String },
+    /// Change a task's status, due date or assignee
+    UpdateTask {
+        task: String,
+        status: Option<TaskStatus>,
+        due_date: Option<NaiveDate>,
+        assignee: Option<String>,
+    },
+    /// Latest messages of a conversation
+    RecentHistory { conversation_id: Option<String>, limit: usize },
+}
+
+/// Tools offered to MCP hosts
+fn mcp_tools() -> Vec<ToolDefinition> {
+    let tool = |name: &str, description: &str, input_schema: Value| ToolDefinition {
+        name: name.to_string(),
+        description: description.to_string(),
+        input_schema,
+    };
+    let shared = memory_tools();
+    let reuse = |name: &str| shared.iter().find(|tool| tool.name == name).cloned();
+
+    let mut tools = vec![tool(
+        "store_message",
+        "Store a message in memory. People, topics, tasks and documents are extracted from user messages \
+         and linked in the graph; tasks the message reports as finished are completed.",
+        json!({
+            "type": "object",
+            "properties": {
+                "content": {"type": "string"},
+                "role": {"type": "string", "enum": ["user", "assistant"], "description": "Defaults to user"},
+                "conversation_id": {
+                    "type": "string",
+                    "description": "Conversation to add the message to (default: one conversation per MCP session)"
+                }
+            },
+            "required": ["content"]
+        }),
+    )];
+    tools.extend(reuse("search_memory"));
+    tools.push(tool(
+        "get_person_context",
+        "Everything remembered about a person: topics, tasks, relationships and recent messages",
+        json!({
+            "type": "object",
+            "properties": {"name": {"type": "string"}},
+            "required": ["name"]
+        }),
+    ));
+    tools.push(tool(
+        "get_topic_context",
+        "People, topics, tasks and documents connected to a topic in the memory graph",
+        json!({
+            "type": "object",
+            "properties": {"name": {"type": "string"}},
+            "required": ["name"]
+        }),
+    ));
+    tools.extend(reuse("list_tasks"));
+    tools.push(tool(
+        "update_task",
+        "Change a task's status, due date or assignee",
+        json!({
+            "type": "object",
+            "properties": {
+                "task": {"type": "string", "description": "Task id or unique id prefix"},
+                "status": {"type": "string", "enum": ["pending", "in_progress", "completed"]},
+                "due_date": {"type": "string", "description": "YYYY-MM-DD"},
+                "assignee": {"type": "string", "description": "Name of the person to assign the task to"}
+            },
+            "required": ["task"]
+        }),
+    ));
+    tools.push(tool(
+        "get_recent_history",
+        "The latest messages of a conversation, newest first",
+        json!({
+            "type": "object",
+            "properties": {
+                "conversation_id": {
+                    "type": "string",
+                    "description": "Defaults to this session's conversation, or the most recently active one"
+                },
+                "limit": {"type": "integer", "minimum": 1, "maximum": MAX_HISTORY_LIMIT}
+            }
+        }),
+    ));
+    tools
+}
+
+impl McpTool {
+    /// Validate a `tools/call` request
+    fn parse(name: &str, args: &Value) -> Result<Self> {
+        let optional = |field: &str| -> Option<String> {
+            args[field]
+                .as_str()
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(String::from)
+        };
+        let text = |field: &str| -> Result<String> {
+            optional(field).with_context(|| format!("'{}' requires a non-empty '{}' argument", name, field))
+        };
+
+        match name {
+            "store_message" => {
+                let role = match optional("role") {
+                    None => ChatRole::User,
+                    Some(role) => ChatRole::parse(&role)
+                        .with_context(|| format!("Unknown role '{}' (expected user or assistant)", role))?,
+                };
+                Ok(McpTool::StoreMessage {
+                    content: text("content")?,
+                    role,
+                    conversation_id: optional("conversation_id"),
+                })
+            }
+            "search_memory" | "list_tasks" => {
+                let call = ToolCall {
+                    id: String::new(),
+                    name: name.to_string(),
+                    arguments: args.clone(),
+                };
+                Ok(McpTool::Memory(MemoryTool::parse(&call)?))
+            }
+            "get_person_context" => Ok(McpTool::Memory(MemoryTool::GetPerson { name: text("name")? })),
+            "get_topic_context" => Ok(McpTool::TopicContext { name: text("name")? }),
+            "update_task" => {
+                let status = optional("status")
+                    .map(|status| {
+                        TaskStatus::parse(&status).with_context(|| format!("Unknown task status: {}", status))
+                    })
+                    .transpose()?;
+                let due_date = optional("due_date")
+                    .map(|due| {
+                        NaiveDate::parse_from_str(&due, "%Y-%m-%d")
+                            .with_context(|| format!("Invalid due date '{}' (expected YYYY-MM-DD)", due))
+                    })
+                    .transpose()?;
+                let assignee = optional("assignee");
+                if status.is_none() && due_date.is_none() && assignee.is_none() {
+                    anyhow::bail!("'update_task' needs a status, due_date or assignee to change");
+                }
+                Ok(McpTool::UpdateTask { task: text("task")?, status, due_date, assignee })
+            }
+            "get_recent_history" => Ok(McpTool::RecentHistory {
+                conversation_id: optional("conversation_id"),
+                limit: args["limit"]
+                    .as_u64()
+                    .map_or(DEFAULT_HISTORY_LIMIT, |limit| (limit as usize).clamp(1, MAX_HISTORY_LIMIT)),
+            }),
+            other => anyhow::bail!("Unknown tool: {}", other),
+        }
+    }
+}
+
+/// MCP server state: the memory, one database session and the conversation
+/// messages are stored in by default
+pub struct McpServer {
+    memory: AgenticMemory,
+    session: Session,
+    conversation_id: Option<String>,
+}
+
+impl McpServer {
+    pub fn new(memory: AgenticMemory, session: Session) -> Self {
+        Self { memory, session, conversation_id: None }
+    }
+
+    /// Handle one JSON-RPC message, returning the response to send (none for notifications)
+    pub async fn handle(&mut self, line: &str) -> Option<Value> {
+        let message: Value = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(error) => {
+                return Some(error_response(Value::Null, RpcError::new(PARSE_ERROR, error.to_string())));
+            }
+        };
+
+        let id = message.get("id").cloned();
+        let method = match message["method"].as_str() {
+            Some(method) if message["jsonrpc"] == "2.0" => method,
+            // Responses to requests we never send are ignored
+            _ if id.is_some() && (message.get("result").is_some() || message.get("error").is_some()) => {
+                return None;
+            }
+            _ => {
+                let error = RpcError::new(INVALID_REQUEST, "Expected a JSON-RPC 2.0 request");
+                return Some(error_response(id.unwrap_or(Value::Null), error));
+            }
+        };
+
+        let result = self.dispatch(method, &message["params"]).await;
+        let id = id?;
+        Some(match result {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+            Err(error) => error_response(id, error),
+        })
+    }
+
+    async fn dispatch(&mut self, method: &str, params: &Value) -> Result<Value, RpcError> {
+        match method {
+            "initialize" => Ok(initialize_result(params["protocolVersion"].as_str())),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({
+                "tools": mcp_tools()
+                    .into_iter()
+                    .map(|tool| json!({
+                        "name": tool.name,
+                        "description": tool.description,
+                        "inputSchema": tool.input_schema,
+                    }))
+                    .collect::<Vec<_>>()
+            })),
+            "tools/call" => {
+                let name = params["name"]
+                    .as_str()
+                    .ok_or_else(|| RpcError::new(INVALID_PARAMS, "tools/call requires a tool name"))?;
+                if !mcp_tools().iter().any(|tool| tool.name == name) {
+                    return Err(RpcError::new(INVALID_PARAMS, format!("Unknown tool: {}", name)));
+                }
+                // Failures are reported to the host as tool output, not protocol errors
+                let (text, is_error) = match McpTool::parse(name, &params["arguments"]) {
+                    Ok(tool) => match self.call_tool(tool).await {
+                        Ok(text) => (text, false),
+                        Err(error) => (format!("Error: {:#}", error), true),
+                    },
+                    Err(error) => (format!("Error: {:#}", error), true),
+                };
+                Ok(json!({"content": [{"type": "text", "text": text}], "isError": is_error}))
+            }
+            "resources/list" => self.list_resources(),
+            "resources/templates/list" => Ok(json!({
+                "resourceTemplates": [{
+                    "uriTemplate": format!("{}/{{id}}", CONVERSATIONS_URI),
+                    "name": "Conversation transcript",
+                    "description": "All messages of a conversation, oldest first",
+                    "mimeType": "text/plain",
+                }]
+            })),
+            "resources/read" => {
+                let uri = params["uri"]
+                    .as_str()
+                    .ok_or_else(|| RpcError::new(INVALID_PARAMS, "resources/read requires a uri"))?;
+                self.read_resource(uri)
+            }
+            method if method.starts_with("notifications/") => Ok(Value::Null),
+            other => Err(RpcError::new(METHOD_NOT_FOUND, format!("Method not found: {}", other))),
+        }
+    }
+
+    /// Run a tool and describe the result for the host
+    async fn call_tool(&mut self, tool: McpTool) -> Result<String> {
+        let session = &self.session;
+        let graph = self.memory.graph();
+        let retriever = ContextRetriever::new(graph)
+            .with_relation_types(self.memory.relation_types())
+            .with_traversal(self.memory.traversal());
+
+        match tool {
+            McpTool::StoreMessage { content, role, conversation_id } => {
+                let conversation_id = match conversation_id {
+                    Some(id) => {
+                        graph
+                            .get_conversation(session, &id)?
+                            .with_context(|| format!("Conversation not found: {}", id))?;
+                        id
+                    }
+                    None => self.session_conversation()?,
+                };
+                let session = &self.session;
+
+                match role {
+                    ChatRole::User => {
+                        let (message_id, entities) = self
+                            .memory
+                            .process_user_message_in(session, &conversation_id, &content)
+                            .await?;
+                        Ok(format!(
+                            "Stored message {} in conversation {}\nExtracted: {}",
+                            message_id,
+                            conversation_id,
+                            serde_json::to_string(&entities)?
+                        ))
+                    }
+                    ChatRole::Assistant => {
+                        let message_id = self
+                            .memory
+                            .store_assistant_message_in(session, &conversation_id, &content)?;
+                        Ok(format!("Stored message {} in conversation {}", message_id, conversation_id))
+                    }
+                }
+            }
+            McpTool::Memory(tool) => tool.run(session, graph, &retriever),
+            McpTool::TopicContext { name } => retriever.get_topic_context(session, &name),
+            McpTool::UpdateTask { task, status, due_date, assignee } => {
+                let task = graph.find_task(session, &task)?;
+                let mut changes = Vec::new();
+                if let Some(status) = status {
+                    graph.set_task_status(session, &task.id, status)?;
+                    changes.push(format!("status {}", status.as_str()));
+                }
+                if let Some(due_date) = due_date {
+                    graph.set_task_due_date(session, &task.id, due_date)?;
+                    changes.push(format!("due {}", due_date));
+                }
+                if let Some(assignee) = assignee {
+                    graph.assign_task(session, &task.id, &assignee)?;
+                    changes.push(format!("assigned to {}", assignee));
+                }
+                Ok(format!("Updated task {}: {}", task.description, changes.join(", ")))
+            }
+            McpTool::RecentHistory { conversation_id, limit } => {
+                let conversation_id = match conversation_id.or_else(|| self.conversation_id.clone()) {
+                    Some(id) => id,
+                    None => graph
+                        .latest_conversation_id(session)?
+                        .context("No conversations stored yet")?,
+                };
+                retriever.get_recent_history(session, &conversation_id, limit)
+            }
+        }
+    }
+
+    /// The conversation this session stores messages in, started on first use
+    fn session_conversation(&mut self) -> Result<String> {
+        if let Some(id) = &self.conversation_id {
+            return Ok(id.clone());
+        }
+        let id = self
+            .memory
+            .graph()
+            .start_conversation(&self.session, Some(SESSION_CONVERSATION_TITLE.to_string()))?;
+        self.conversation_id = Some(id.clone());
+        Ok(id)
+    }
+
+    /// The conversation index and the most recently active conversations
+    fn list_resources(&self) -> Result<Value, RpcError> {
+        let conversations = self
+            .memory
+            .graph()
+            .list_conversations(&self.session, 0, MAX_LISTED_CONVERSATIONS)?;
+
+        let mut resources = vec![json!({
+            "uri": CONVERSATIONS_URI,
+            "name": "Conversations",
+            "description": "All conversations with message counts, most recently active first",
+            "mimeType": "application/json",
+        })];
+        resources.extend(conversations.iter().map(|conversation| {
+            json!({
+                "uri": format!("{}/{}", CONVERSATIONS_URI, conversation.id),
+                "name": conversation.title.as_deref().unwrap_or("untitled"),
+                "description": format!(
+                    "{} messages, last active {}",
+                    conversation.message_count,
+                    conversation.last_activity.format("%Y-%m-%d %H:%M")
+                ),
+                "mimeType": "text/plain",
+            })
+        }));
+        Ok(json!({ "resources": resources }))
+    }
+
+    fn read_resource(&self, uri: &str) -> Result<Value, RpcError> {
+        let graph = self.memory.graph();
+        let not_found = || RpcError::new(RESOURCE_NOT_FOUND, format!("Resource not found: {}", uri));
+
+        let (mime_type, text) = match conversation_uri(uri).ok_or_else(not_found)? {
+            None => {
+                let conversations = graph.list_conversations(&self.session, 0, usize::MAX)?;
+                ("application/json", serde_json::to_string_pretty(&conversations).map_err(anyhow::Error::from)?)
+            }
+            Some(id) => {
+                let conversation = graph.get_conversation(&self.session, id)?.ok_or_else(not_found)?;
+                let mut text = format!(
+                    "# {}\nStarted {}\n\n",
+                    conversation.title.as_deref().unwrap_or("untitled"),
+                    conversation.started_at.to_rfc3339()
+                );
+                for (role, content, timestamp) in graph.get_conversation_transcript(&self.session, id)? {
+                    text.push_str(&format!("[{}] {}: {}\n", timestamp, role, content));
+                }
+                ("text/plain", text)
+            }
+        };
+
+        Ok(json!({
+            "contents": [{"uri": uri, "mimeType": mime_type, "text": text}]
+        }))
+    }
+}
+
+/// Parse a conversation resource URI: `Some(None)` for the index,
+/// `Some(Some(id))` for one conversation
+fn conversation_uri(uri: &str) -> Option<Option<&str>> {
+    let rest = uri.strip_prefix(CONVERSATIONS_URI)?;
+    match rest.trim_end_matches('/') {
+        "" => Some(None),
+        path => path
+            .strip_prefix('/')
+            .filter(|id| !id.is_empty() && !id.contains('/'))
+            .map(Some),
+    }
+}
+
+/// Reply to `initialize`, agreeing on the client's protocol version when we support it
+fn initialize_result(requested: Option<&str>) -> Value {
+    let version = requested
+        .filter(|version| PROTOCOL_VERSIONS.contains(version))
+        .unwrap_or(PROTOCOL_VERSIONS[0]);
+    json!({
+        "protocolVersion": version,
+        "capabilities": {"tools": {}, "resources": {}},
+        "serverInfo": {"name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION")},
+        "instructions": "Long-term memory shared across conversations. Store user messages with store_message \
+                         and look up people, topics, tasks and past messages before answering.",
+    })
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {"code": error.code, "message": error.message},
+    })
+}
+
+/// Serve MCP on stdin and stdout until stdin is closed
+pub async fn serve_stdio(memory: AgenticMemory, user: &str, password: &str) -> Result<()> {
+    let session = memory
+        .session(user, password)
+        .context("Failed to create database session")?;
+    let mut server = McpServer::new(memory, session);
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+    while let Some(line) = lines.next_line().await.context("Failed to read from stdin")? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = server.handle(&line).await {
+            let mut output = serde_json::to_vec(&response)?;
+            output.push(b'\n');
+            stdout.write_all(&output).await.context("Failed to write to stdout")?;
+            stdout.flush().await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mcp_tools() {
+        let tools = mcp_tools();
+        let names: Vec<&str> = tools.iter().map(|tool| tool.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "store_message",
+                "search_memory",
+                "get_person_context",
+                "get_topic_context",
+                "list_tasks",
+                "update_task",
+                "get_recent_history"
+            ]
+        );
+        for tool in &tools {
+            assert_eq!(tool.input_schema["type"], "object");
+        }
+    }
+
+    #[test]
+    fn test_parse_tools() {
+        assert_eq!(
+            McpTool::parse("store_message", &json!({"content": " Hi "})).unwrap(),
+            McpTool::StoreMessage { content: "Hi".to_string(), role: ChatRole::User, conversation_id: None }
+        );
+        assert!(McpTool::parse("store_message", &json!({"content": "Hi", "role": "system"})).is_err());
+        assert_eq!(
+            McpTool::parse("get_person_context", &json!({"name": "Alice"})).unwrap(),
+            McpTool::Memory(MemoryTool::GetPerson { name: "Alice".to_string() })
+        );
+        assert_eq!(
+            McpTool::parse("list_tasks", &json!({"status": "completed"})).unwrap(),
+            McpTool::Memory(MemoryTool::ListTasks { status: Some(TaskStatus::Completed) })
+        );
+        assert_eq!(
+            McpTool::parse("update_task", &json!({"task": "1a2b", "due_date": "2025-03-01"})).unwrap(),
+            McpTool::UpdateTask {
+                task: "1a2b".to_string(),
+                status: None,
+                due_date: NaiveDate::from_ymd_opt(2025, 3, 1),
+                assignee: None,
+            }
+        );
+        assert!(McpTool::parse("update_task", &json!({"task": "1a2b"})).is_err());
+        assert!(McpTool::parse("update_task", &json!({"task": "1a2b", "due_date": "soon"})).is_err());
+        assert_eq!(
+            McpTool::parse("get_recent_history", &json!({"limit": 1000})).unwrap(),
+            McpTool::RecentHistory { conversation_id: None, limit: MAX_HISTORY_LIMIT }
+        );
+    }
+
+    #[test]
+    fn test_conversation_uri() {
+        assert_eq!(conversation_uri("memory://conversations"), Some(None));
+        assert_eq!(conversation_uri("memory://conversations/"), Some(None));
+        assert_eq!(conversation_uri("memory://conversations/abc"), Some(Some("abc")));
+        assert_eq!(conversation_uri("memory://conversations/abc/messages"), None);
+        assert_eq!(conversation_uri("memory://conversationsabc"), None);
+        assert_eq!(conversation_uri("memory://tasks"), None);
+    }
+
+    #[test]
+    fn test_initialize_negotiates_version() {
+        assert_eq!(initialize_result(Some("2024-11-05"))["protocolVersion"], "2024-11-05");
+        assert_eq!(initialize_result(Some("1999-01-01"))["protocolVersion"], PROTOCOL_VERSIONS[0]);
+        assert_eq!(initialize_result(None)["capabilities"]["tools"], json!({}));
+    }
+}
@@ -0,0 +1,394 @@
+//! HTTP API exposing the memory to web and chat front ends
+//!
+//! All request and response bodies are JSON; errors are returned as
+//! `{"error": "..."}` with a matching status code. Each request works in its
+//! own database session and names its conversation explicitly, so any number
+//! of clients can share one memory database.
+//!
+//! OpenAI-compatible chat completions are served under `/v1` (see [`openai`]).
+
+mod openai;
+
+use anyhow::{Context, Result};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use colored::*;
+use graphlite_sdk::Session;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use crate::agent::{group_paths, AgenticMemory, ContextRetriever};
+use crate::graph::{Conversation, ConversationSummary, EntityKind, EntityRef, ExtractedEntities, Task, TaskStatus};
+
+/// Conversations returned by `GET /conversations` unless a limit is given
+const DEFAULT_PAGE_SIZE: usize = 20;
+
+/// Shared by all request handlers
+struct AppState {
+    memory: AgenticMemory,
+    user: String,
+    password: String,
+    /// Conversation chat completions are stored in when the client names none
+    proxy_conversation: Mutex<Option<String>>,
+}
+
+impl AppState {
+    fn session(&self) -> Result<Session, ApiError> {
+        Ok(self.memory.session(&self.user, &self.password)?)
+    }
+}
+
+type SharedState = Arc<AppState>;
+
+/// Error response: a status code and a message sent as `{"error": ...}`
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn bad_request(message: impl Into<String>) -> Self {
+        Self { status: StatusCode::BAD_REQUEST, message: message.into() }
+    }
+
+    fn not_found(message: impl Into<String>) -> Self {
+        Self { status: StatusCode::NOT_FOUND, message: message.into() }
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(error: anyhow::Error) -> Self {
+        Self {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            message: format!("{:#}", error),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        if self.status.is_server_error() {
+            eprintln!("{}", format!("Error: {}", self.message).red());
+        }
+        (self.status, Json(json!({ "error": self.message }))).into_response()
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+/// Body of `POST /conversations`
+#[derive(Debug, Default, Deserialize)]
+struct NewConversation {
+    title: Option<String>,
+}
+
+/// Query string of `GET /conversations`
+#[derive(Debug, Deserialize)]
+struct Page {
+    #[serde(default)]
+    offset: usize,
+    limit: Option<usize>,
+}
+
+/// A stored message as returned by the API
+#[derive(Debug, Serialize)]
+struct MessageView {
+    role: String,
+    content: String,
+    timestamp: String,
+}
+
+/// A conversation with its messages, oldest first
+#[derive(Debug, Serialize)]
+struct ConversationView {
+    conversation: Conversation,
+    messages: Vec<MessageView>,
+}
+
+/// Body of `POST /conversations/{id}/messages`
+#[derive(Debug, Deserialize)]
+struct PostMessage {
+    content: String,
+}
+
+/// Result of posting a message: what was extracted and the assistant's reply
+#[derive(Debug, Serialize)]
+struct MessageReply {
+    message_id: String,
+    entities: ExtractedEntities,
+    reply_id: String,
+    reply: String,
+}
+
+/// Query string of `GET /tasks`
+#[derive(Debug, Deserialize)]
+struct TaskFilter {
+    status: Option<String>,
+}
+
+/// An entity connected to the requested one
+#[derive(Debug, Serialize)]
+struct RelatedView {
+    entity: EntityRef,
+    /// How it is connected, e.g. "Alice (works on GraphQL, also mentioned with Bob)"
+    description: String,
+    score: f64,
+    mentions: usize,
+}
+
+/// Entities related to `entity`, best first
+#[derive(Debug, Serialize)]
+struct RelatedEntities {
+    entity: EntityRef,
+    related: Vec<RelatedView>,
+}
+
+/// Routes of the API
+fn router(state: SharedState) -> Router {
+    Router::new()
+        .route("/health", get(health))
+        .route("/conversations", get(list_conversations).post(create_conversation))
+        .route("/conversations/latest", get(latest_conversation))
+        .route("/conversations/{id}", get(get_conversation))
+        .route("/conversations/{id}/messages", post(post_message))
+        .route("/tasks", get(list_tasks))
+        .route("/entities/{kind}", get(list_entities))
+        .route("/entities/{kind}/{name}/related", get(related_entities))
+        .merge(openai::routes())
+        .with_state(state)
+}
+
+/// Serve the API on `address` until interrupted with Ctrl-C
+pub async fn serve(memory: AgenticMemory, user: &str, password: &str, address: SocketAddr) -> Result<()> {
+    let state = Arc::new(AppState {
+        memory,
+        user: user.to_string(),
+        password: password.to_string(),
+        proxy_conversation: Mutex::new(None),
+    });
+
+    let listener = tokio::net::TcpListener::bind(address)
+        .await
+        .with_context(|| format!("Failed to listen on {}", address))?;
+    println!("{}", format!("Serving the memory API on http://{}", listener.local_addr()?).green());
+
+    axum::serve(listener, router(state))
+        .with_graceful_shutdown(async {
+            tokio::signal::ctrl_c().await.ok();
+        })
+        .await
+        .context("HTTP server failed")
+}
+
+async fn health() -> Json<serde_json::Value> {
+    Json(json!({ "status": "ok" }))
+}
+
+async fn list_conversations(
+    State(state): State<SharedState>,
+    Query(page): Query<Page>,
+) -> ApiResult<Vec<ConversationSummary>> {
+    let session = state.session()?;
+    let limit = page.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    Ok(Json(state.memory.graph().list_conversations(&session, page.offset, limit)?))
+}
+
+/// Start a conversation; the body may be empty
+async fn create_conversation(
+    State(state): State<SharedState>,
+    body: Option<Json<NewConversation>>,
+) -> Result<(StatusCode, Json<Conversation>), ApiError> {
+    let session = state.session()?;
+    let Json(request) = body.unwrap_or_default();
+    let graph = state.memory.graph();
+
+    let id = graph.start_conversation(&session, request.title)?;
+    let conversation = graph
+        .get_conversation(&session, &id)?
+        .context("Conversation missing after it was created")?;
+    Ok((StatusCode::CREATED, Json(conversation)))
+}
+
+async fn get_conversation(State(state): State<SharedState>, Path(id): Path<String>) -> ApiResult<ConversationView> {
+    let session = state.session()?;
+    Ok(Json(conversation_view(&state, &session, &id)?))
+}
+
+/// The conversation with the most recent activity, for resuming where a
+/// client left off
+async fn latest_conversation(State(state): State<SharedState>) -> ApiResult<ConversationView> {
+    let session = state.session()?;
+    let id = state
+        .memory
+        .graph()
+        .latest_conversation_id(&session)?
+        .ok_or_else(|| ApiError::not_found("No conversations yet"))?;
+    Ok(Json(conversation_view(&state, &session, &id)?))
+}
+
+/// Store a user message, extract its entities and reply with context from memory
+async fn post_message(
+    State(state): State<SharedState>,
+    Path(id): Path<String>,
+    Json(request): Json<PostMessage>,
+) -> ApiResult<MessageReply> {
+    let content = request.content.trim();
+    if content.is_empty() {
+        return Err(ApiError::bad_request("Message content is empty"));
+    }
+
+    let session = state.session()?;
+    find_conversation(&state, &session, &id)?;
+
+    let memory = &state.memory;
+    let (message_id, entities) = memory.process_user_message_in(&session, &id, content).await?;
+    let reply = memory
+        .generate_response_in(&session, Some(&id), content, &entities)
+        .await?;
+    let reply_id = memory.store_assistant_message_in(&session, &id, &reply)?;
+
+    Ok(Json(MessageReply { message_id, entities, reply_id, reply }))
+}
+
+async fn list_tasks(State(state): State<SharedState>, Query(filter): Query<TaskFilter>) -> ApiResult<Vec<Task>> {
+    let session = state.session()?;
+    let graph = state.memory.graph();
+    let tasks = match task_filter(filter.status.as_deref())? {
+        TaskQuery::Open => graph.list_open_tasks(&session)?,
+        TaskQuery::Status(status) => graph.list_tasks(&session, status)?,
+    };
+    Ok(Json(tasks))
+}
+
+/// Names of every entity of a kind, e.g. `GET /entities/person`
+async fn list_entities(State(state): State<SharedState>, Path(kind): Path<String>) -> ApiResult<Vec<String>> {
+    let kind = entity_kind(&kind)?;
+    let session = state.session()?;
+    Ok(Json(state.memory.graph().list_entity_names(&session, kind)?))
+}
+
+/// Entities reachable from one entity, grouped by first hop as in the prompt context
+async fn related_entities(
+    State(state): State<SharedState>,
+    Path((kind, name)): Path<(String, String)>,
+) -> ApiResult<RelatedEntities> {
+    let kind = entity_kind(&kind)?;
+    let session = state.session()?;
+    let graph = state.memory.graph();
+
+    let names = graph.list_entity_names(&session, kind)?;
+    let retriever = ContextRetriever::new(graph)
+        .with_relation_types(state.memory.relation_types())
+        .with_traversal(state.memory.traversal());
+    let entity = retriever.resolve(&session, kind, &name)?;
+    if !names.contains(&entity.name) {
+        return Err(ApiError::not_found(format!("{} not found: {}", kind.label(), name)));
+    }
+
+    let paths = retriever.traverse(&session, &entity)?;
+    let related = group_paths(&entity, &paths)
+        .into_iter()
+        .map(|group| RelatedView {
+            entity: group.entity,
+            description: group.text,
+            score: group.score,
+            mentions: group.mentions,
+        })
+        .collect();
+    Ok(Json(RelatedEntities { entity, related }))
+}
+
+/// Look up a conversation, answering 404 if there is none with this id
+fn find_conversation(state: &AppState, session: &Session, id: &str) -> Result<Conversation, ApiError> {
+    state
+        .memory
+        .graph()
+        .get_conversation(session, id)?
+        .ok_or_else(|| ApiError::not_found(format!("Conversation not found: {}", id)))
+}
+
+fn conversation_view(state: &AppState, session: &Session, id: &str) -> Result<ConversationView, ApiError> {
+    let conversation = find_conversation(state, session, id)?;
+    let messages = state
+        .memory
+        .graph()
+        .get_conversation_transcript(session, id)?
+        .into_iter()
+        .map(|(role, content, timestamp)| MessageView { role, content, timestamp })
+        .collect();
+    Ok(ConversationView { conversation, messages })
+}
+
+/// Which tasks `GET /tasks` returns
+#[derive(Debug, PartialEq)]
+enum TaskQuery {
+    /// Pending and in-progress tasks
+    Open,
+    /// Tasks with this status, or all of them
+    Status(Option<TaskStatus>),
+}
+
+/// Parse the `status` parameter: a task status, "open" or "all" (the default)
+fn task_filter(status: Option<&str>) -> Result<TaskQuery, ApiError> {
+    match status.map(str::trim) {
+        None | Some("") | Some("all") => Ok(TaskQuery::Status(None)),
+        Some("open") => Ok(TaskQuery::Open),
+        Some(value) => TaskStatus::parse(value)
+            .map(|status| TaskQuery::Status(Some(status)))
+            .ok_or_else(|| {
+                ApiError::bad_request(format!(
+                    "Unknown task status '{}' (expected pending, in_progress, completed, open or all)",
+                    value
+                ))
+            }),
+    }
+}
+
+/// Parse the `{kind}` path segment
+fn entity_kind(value: &str) -> Result<EntityKind, ApiError> {
+    EntityKind::parse(value).ok_or_else(|| {
+        ApiError::bad_request(format!(
+            "Unknown entity kind '{}' (expected person, topic, task or document)",
+            value
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_filter() {
+        assert_eq!(task_filter(None).unwrap(), TaskQuery::Status(None));
+        assert_eq!(task_filter(Some("all")).unwrap(), TaskQuery::Status(None));
+        assert_eq!(task_filter(Some("open")).unwrap(), TaskQuery::Open);
+        assert_eq!(
+            task_filter(Some("in-progress")).unwrap(),
+            TaskQuery::Status(Some(TaskStatus::InProgress))
+        );
+
+        let error = task_filter(Some("later")).unwrap_err();
+        assert_eq!(error.status, StatusCode::BAD_REQUEST);
+        assert!(error.message.contains("'later'"));
+    }
+
+    #[test]
+    fn test_entity_kind() {
+        assert_eq!(entity_kind("people").unwrap(), EntityKind::Person);
+        assert_eq!(entity_kind("alias").unwrap_err().status, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_api_error_from_anyhow() {
+        let error: ApiError = anyhow::anyhow!("disk full").context("Failed to store message").into();
+        assert_eq!(error.status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(error.message, "Failed to store message: disk full");
+    }
+}
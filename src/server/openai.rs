@@ -0,0 +1,472 @@
+//! OpenAI-compatible chat completions that add memory transparently
+//!
+//! Apps built on an OpenAI SDK point their base URL at `/v1` and keep sending
+//! their usual requests. The latest user turn is stored with its extracted
+//! entities, graph context is appended to the system message, and the request
+//! is answered by the configured provider; the reply is stored once complete.
+//! Clients may pin a conversation with the `x-conversation-id` header, which
+//! is also returned on every response.
+//!
+//! Requests without the header all go to one "OpenAI proxy" conversation
+//! shared by every such client of the server. Its recent messages become
+//! context for each of those requests, so clients that must not see each
+//! other's turns should start their own conversation (`POST /conversations`)
+//! and send its id in the header.
+
+use anyhow::Context;
+use axum::extract::rejection::JsonRejection;
+use axum::extract::State;
+use axum::http::{HeaderMap, HeaderValue};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use colored::*;
+use futures::StreamExt;
+use graphlite_sdk::Session;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::convert::Infallible;
+
+use super::{ApiError, AppState, SharedState};
+use crate::llm::{ChatMessage, ChatRole, TextStream};
+
+/// Header naming the conversation a request belongs to
+const CONVERSATION_HEADER: &str = "x-conversation-id";
+
+/// Title of the conversation used when a client names none
+const PROXY_CONVERSATION_TITLE: &str = "OpenAI proxy";
+
+/// Routes under `/v1`
+pub(super) fn routes() -> Router<SharedState> {
+    Router::new()
+        .route("/v1/chat/completions", post(chat_completions))
+        .route("/v1/models", get(list_models))
+}
+
+/// Error in the shape OpenAI clients expect: `{"error": {"message", "type", "code"}}`
+struct OpenAIError(ApiError);
+
+impl From<ApiError> for OpenAIError {
+    fn from(error: ApiError) -> Self {
+        Self(error)
+    }
+}
+
+impl From<anyhow::Error> for OpenAIError {
+    fn from(error: anyhow::Error) -> Self {
+        Self(error.into())
+    }
+}
+
+impl IntoResponse for OpenAIError {
+    fn into_response(self) -> Response {
+        let ApiError { status, message } = self.0;
+        if status.is_server_error() {
+            eprintln!("{}", format!("Error: {}", message).red());
+        }
+        let kind = if status.is_client_error() { "invalid_request_error" } else { "api_error" };
+        (status, Json(json!({"error": {"message": message, "type": kind, "code": null}}))).into_response()
+    }
+}
+
+/// The parts of a chat completion request that are used; the model, sampling
+/// options and tools are ignored in favour of the configured provider
+#[derive(Debug, Deserialize)]
+struct ChatCompletionRequest {
+    messages: Vec<RequestMessage>,
+    #[serde(default)]
+    stream: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct RequestMessage {
+    role: String,
+    #[serde(default)]
+    content: Value,
+}
+
+/// A request split into what is sent to the provider
+#[derive(Debug, PartialEq)]
+struct Prompt {
+    /// System and developer messages, joined
+    system: String,
+    turns: Vec<ChatMessage>,
+    /// Text of the last user message
+    latest_user: String,
+}
+
+/// Split the request messages into a system prompt and chat turns
+///
+/// Tool results and other roles are dropped, since tools are not forwarded.
+fn prompt_from_messages(messages: &[RequestMessage]) -> Result<Prompt, ApiError> {
+    let mut system = Vec::new();
+    let mut turns = Vec::new();
+    for message in messages {
+        let text = message_text(&message.content);
+        match message.role.as_str() {
+            "system" | "developer" => system.push(text),
+            "user" => turns.push(ChatMessage::user(text)),
+            "assistant" if !text.is_empty() => turns.push(ChatMessage::assistant(text)),
+            _ => {}
+        }
+    }
+
+    let latest_user = turns
+        .iter()
+        .rev()
+        .find(|turn| turn.role == ChatRole::User)
+        .map(|turn| turn.content.trim().to_string())
+        .filter(|content| !content.is_empty())
+        .ok_or_else(|| ApiError::bad_request("The request has no user message"))?;
+
+    Ok(Prompt { system: system.join("\n\n"), turns, latest_user })
+}
+
+/// Text of a message's content: a string, or the text parts of a content array
+fn message_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter(|part| part["type"] == "text")
+            .filter_map(|part| part["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+async fn chat_completions(
+    State(state): State<SharedState>,
+    headers: HeaderMap,
+    request: Result<Json<ChatCompletionRequest>, JsonRejection>,
+) -> Result<Response, OpenAIError> {
+    let Json(request) = request.map_err(|rejection| ApiError::bad_request(rejection.body_text()))?;
+    let prompt = prompt_from_messages(&request.messages)?;
+
+    let session = state.session()?;
+    let conversation_id = match headers.get(CONVERSATION_HEADER).and_then(|id| id.to_str().ok()) {
+        Some(id) => super::find_conversation(&state, &session, id)?.id,
+        None => proxy_conversation(&state, &session)?,
+    };
+
+    let memory = &state.memory;
+    let (_, entities) = memory
+        .process_user_message_in(&session, &conversation_id, &prompt.latest_user)
+        .await?;
+    let completion = Completion::new(memory.model().to_string());
+
+    let mut response = if request.stream {
+        let deltas = memory
+            .chat_with_context_stream(&session, Some(&conversation_id), &prompt.system, &prompt.turns, &entities)
+            .await?;
+        let events = completion_events(deltas, completion, state.clone(), session, conversation_id.clone());
+        Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+    } else {
+        let reply = memory
+            .chat_with_context(&session, Some(&conversation_id), &prompt.system, &prompt.turns, &entities)
+            .await?;
+        memory.store_assistant_message_in(&session, &conversation_id, &reply)?;
+        Json(completion.response(&reply)).into_response()
+    };
+
+    if let Ok(value) = HeaderValue::from_str(&conversation_id) {
+        response.headers_mut().insert(CONVERSATION_HEADER, value);
+    }
+    Ok(response)
+}
+
+/// The configured model, the only one this server answers with
+async fn list_models(State(state): State<SharedState>) -> Json<Value> {
+    Json(json!({
+        "object": "list",
+        "data": [{"id": state.memory.model(), "object": "model", "created": 0, "owned_by": "agentic-memory"}]
+    }))
+}
+
+/// The conversation used for requests without a conversation header, started on first use
+fn proxy_conversation(state: &AppState, session: &Session) -> Result<String, ApiError> {
+    let mut conversation = state
+        .proxy_conversation
+        .lock()
+        .map_err(|_| anyhow::anyhow!("Proxy conversation lock poisoned"))?;
+    if let Some(id) = conversation.as_ref() {
+        return Ok(id.clone());
+    }
+    let id = state
+        .memory
+        .graph()
+        .start_conversation(session, Some(PROXY_CONVERSATION_TITLE.to_string()))?;
+    *conversation = Some(id.clone());
+    Ok(id)
+}
+
+/// Fields shared by a response and all chunks of a stream
+#[derive(Debug, Clone)]
+struct Completion {
+    id: String,
+    created: i64,
+    model: String,
+}
+
+impl Completion {
+    fn new(model: String) -> Self {
+        Self {
+            id: format!("chatcmpl-{}", uuid::Uuid::new_v4().simple()),
+            created: chrono::Utc::now().timestamp(),
+            model,
+        }
+    }
+
+    /// `chat.completion` object for a finished reply
+    fn response(&self, reply: &str) -> Value {
+        json!({
+            "id": self.id,
+            "object": "chat.completion",
+            "created": self.created,
+            "model": self.model,
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": reply},
+                "finish_reason": "stop"
+            }]
+        })
+    }
+
+    /// `chat.completion.chunk` object carrying `delta`
+    fn chunk(&self, delta: Value, finish_reason: Option<&str>) -> Value {
+        json!({
+            "id": self.id,
+            "object": "chat.completion.chunk",
+            "created": self.created,
+            "model": self.model,
+            "choices": [{"index": 0, "delta": delta, "finish_reason": finish_reason}]
+        })
+    }
+}
+
+/// Where a completion stream is
+enum StreamPhase {
+    Start,
+    Streaming,
+    Finishing,
+    Done,
+}
+
+/// Turn text deltas into OpenAI stream events, storing the reply once the
+/// provider's stream ends
+///
+/// The first chunk carries the role and the last the finish reason, followed
+/// by `[DONE]`. A provider error ends the stream with an error event and the
+/// partial reply is not stored.
+fn completion_events(
+    deltas: TextStream,
+    completion: Completion,
+    state: SharedState,
+    session: Session,
+    conversation_id: String,
+) -> impl futures::Stream<Item = Result<Event, Infallible>> {
+    let data = |value: Value| Ok(Event::default().data(value.to_string()));
+
+    futures::stream::unfold(
+        (deltas, session, String::new(), StreamPhase::Start),
+        move |(mut deltas, session, mut reply, phase)| {
+            let completion = completion.clone();
+            let state = state.clone();
+            let conversation_id = conversation_id.clone();
+            async move {
+                let (event, phase) = match phase {
+                    StreamPhase::Start => (
+                        data(completion.chunk(json!({"role": "assistant", "content": ""}), None)),
+                        StreamPhase::Streaming,
+                    ),
+                    StreamPhase::Streaming => match deltas.next().await {
+                        Some(Ok(delta)) => {
+                            reply.push_str(&delta);
+                            (data(completion.chunk(json!({"content": delta}), None)), StreamPhase::Streaming)
+                        }
+                        Some(Err(error)) => {
+                            let message = format!("{:#}", error.context("Response stream interrupted"));
+                            eprintln!("{}", format!("Error: {}", message).red());
+                            (
+                                data(json!({"error": {"message": message, "type": "api_error", "code": null}})),
+                                StreamPhase::Done,
+                            )
+                        }
+                        None => {
+                            if let Err(error) = state
+                                .memory
+                                .store_assistant_message_in(&session, &conversation_id, &reply)
+                                .context("Failed to store the streamed reply")
+                            {
+                                eprintln!("{}", format!("Warning: {:#}", error).yellow());
+                            }
+                            (data(completion.chunk(json!({}), Some("stop"))), StreamPhase::Finishing)
+                        }
+                    },
+                    StreamPhase::Finishing => (Ok(Event::default().data("[DONE]")), StreamPhase::Done),
+                    StreamPhase::Done => return None,
+                };
+                Some((event, (deltas, session, reply, phase)))
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::AgenticMemory;
+    use crate::llm::{ScriptedModel, ScriptedReply};
+    use axum::http::StatusCode;
+    use std::sync::{Arc, Mutex};
+
+    /// Server state over a fresh database whose replies come from `replies`
+    async fn scripted_state(replies: impl IntoIterator<Item = ScriptedReply>) -> (SharedState, std::path::PathBuf) {
+        let db_path = std::env::temp_dir().join(format!("agentic-memory-openai-{}", uuid::Uuid::new_v4()));
+        let model = Arc::new(ScriptedModel::new(replies));
+        let memory = AgenticMemory::new(db_path.to_str().unwrap(), "admin", "admin123", model.clone(), model)
+            .await
+            .unwrap();
+        let state = AppState {
+            memory,
+            user: "admin".to_string(),
+            password: "admin123".to_string(),
+            proxy_conversation: Mutex::new(None),
+        };
+        (Arc::new(state), db_path)
+    }
+
+    /// Run `deltas` through [`completion_events`] and return the `data:` payloads
+    async fn stream_payloads(state: &SharedState, conversation_id: &str, deltas: TextStream) -> Vec<String> {
+        let completion = Completion::new("scripted".to_string());
+        let events = completion_events(deltas, completion, state.clone(), state.session().unwrap(), conversation_id.to_string());
+        let body = Sse::new(events).into_response().into_body();
+        let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        String::from_utf8(bytes.to_vec())
+            .unwrap()
+            .lines()
+            .filter_map(|line| line.strip_prefix("data: "))
+            .map(String::from)
+            .collect()
+    }
+
+    /// Assistant messages stored in a conversation
+    fn assistant_messages(state: &SharedState, conversation_id: &str) -> Vec<String> {
+        let session = state.session().unwrap();
+        state
+            .memory
+            .graph()
+            .get_conversation_messages(&session, conversation_id, 10)
+            .unwrap()
+            .into_iter()
+            .filter(|(role, _, _)| role == "assistant")
+            .map(|(_, content, _)| content)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_completion_events_store_the_reply() {
+        let (state, db_path) = scripted_state([ScriptedReply::text("Alice works on GraphLite")]).await;
+        let session = state.session().unwrap();
+        let conversation_id = state.memory.graph().start_conversation(&session, None).unwrap();
+        let deltas = state
+            .memory
+            .chat_with_context_stream(&session, Some(&conversation_id), "", &[ChatMessage::user("Hi")], &Default::default())
+            .await
+            .unwrap();
+
+        let payloads = stream_payloads(&state, &conversation_id, deltas).await;
+
+        let (last, chunks) = payloads.split_last().unwrap();
+        assert_eq!(last, "[DONE]");
+        let chunks: Vec<Value> = chunks.iter().map(|chunk| serde_json::from_str(chunk).unwrap()).collect();
+        let (first, rest) = chunks.split_first().unwrap();
+        let (finish, content) = rest.split_last().unwrap();
+        assert_eq!(first["choices"][0]["delta"], json!({"role": "assistant", "content": ""}));
+        let text: String = content
+            .iter()
+            .map(|chunk| {
+                assert!(chunk["choices"][0]["finish_reason"].is_null());
+                chunk["choices"][0]["delta"]["content"].as_str().unwrap()
+            })
+            .collect();
+        assert_eq!(text, "Alice works on GraphLite");
+        assert_eq!(finish["choices"][0]["finish_reason"], "stop");
+        assert_eq!(finish["choices"][0]["delta"], json!({}));
+
+        assert_eq!(assistant_messages(&state, &conversation_id), ["Alice works on GraphLite"]);
+        std::fs::remove_dir_all(&db_path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_completion_events_stream_error_stores_nothing() {
+        let (state, db_path) = scripted_state([]).await;
+        let session = state.session().unwrap();
+        let conversation_id = state.memory.graph().start_conversation(&session, None).unwrap();
+        let deltas: TextStream = Box::pin(futures::stream::iter([
+            Ok("Alice ".to_string()),
+            Err(anyhow::anyhow!("connection reset")),
+        ]));
+
+        let payloads = stream_payloads(&state, &conversation_id, deltas).await;
+
+        assert_eq!(payloads.len(), 3, "{:?}", payloads);
+        let error: Value = serde_json::from_str(&payloads[2]).unwrap();
+        assert!(error["error"]["message"].as_str().unwrap().contains("connection reset"));
+        assert!(!payloads.iter().any(|payload| payload == "[DONE]"));
+        assert!(assistant_messages(&state, &conversation_id).is_empty());
+        std::fs::remove_dir_all(&db_path).ok();
+    }
+
+    fn message(role: &str, content: Value) -> RequestMessage {
+        RequestMessage { role: role.to_string(), content }
+    }
+
+    #[test]
+    fn test_prompt_from_messages() {
+        let prompt = prompt_from_messages(&[
+            message("system", json!("You are terse.")),
+            message("user", json!("Hi, I'm Alice")),
+            message("assistant", json!("Hello Alice")),
+            message("assistant", Value::Null),
+            message("tool", json!("42")),
+            message("developer", json!("Answer in English.")),
+            message("user", json!([{"type": "text", "text": "What do I work on?"}, {"type": "image_url"}])),
+        ])
+        .unwrap();
+
+        assert_eq!(prompt.system, "You are terse.\n\nAnswer in English.");
+        assert_eq!(
+            prompt.turns,
+            vec![
+                ChatMessage::user("Hi, I'm Alice"),
+                ChatMessage::assistant("Hello Alice"),
+                ChatMessage::user("What do I work on?"),
+            ]
+        );
+        assert_eq!(prompt.latest_user, "What do I work on?");
+    }
+
+    #[test]
+    fn test_prompt_requires_user_message() {
+        let error = prompt_from_messages(&[message("system", json!("You are terse."))]).unwrap_err();
+        assert_eq!(error.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_completion_objects() {
+        let completion = Completion::new("gpt-test".to_string());
+        assert!(completion.id.starts_with("chatcmpl-"));
+
+        let response = completion.response("Hello");
+        assert_eq!(response["object"], "chat.completion");
+        assert_eq!(response["choices"][0]["message"]["content"], "Hello");
+
+        let chunk = completion.chunk(json!({"content": "Hel"}), None);
+        assert_eq!(chunk["object"], "chat.completion.chunk");
+        assert_eq!(chunk["id"], completion.id);
+        assert!(chunk["choices"][0]["finish_reason"].is_null());
+    }
+}
@@ -0,0 +1,214 @@
+//! Runs `GraphDB` operations against a database in a temporary directory
+
+use agentic_memory::agent::MemoryTool;
+use agentic_memory::graph::{EntityKind, EntityRef, ExtractedRelation, ImportSummary, TaskStatus, EXPORT_FORMAT, EXPORT_VERSION};
+use agentic_memory::{ContextRetriever, ExtractedEntities, GraphDB};
+use graphlite_sdk::Session;
+use serde_json::json;
+use std::path::PathBuf;
+
+/// Open a fresh database, returning it with a session and its directory
+async fn open_db(name: &str) -> (GraphDB, Session, PathBuf) {
+    let db_path = std::env::temp_dir().join(format!("agentic-memory-{}-{}", name, uuid::Uuid::new_v4()));
+    let graph = GraphDB::new(db_path.to_str().unwrap(), "admin", "admin123").await.unwrap();
+    let session = graph.session("admin", "admin123").unwrap();
+    (graph, session, db_path)
+}
+
+fn relation(subject: &str, predicate: &str, object: &str) -> ExtractedRelation {
+    ExtractedRelation {
+        subject: subject.to_string(),
+        predicate: predicate.to_string(),
+        object: object.to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_merge_entities_moves_every_edge() {
+    let (graph, session, db_path) = open_db("merge").await;
+    let conversation_id = graph.start_conversation(&session, None).unwrap();
+
+    let first = ExtractedEntities {
+        people: vec!["Al".to_string()],
+        topics: vec!["GraphLite".to_string()],
+        tasks: vec!["Review the schema".to_string()],
+        relations: vec![relation("Al", "WORKS_ON", "GraphLite")],
+        ..Default::default()
+    };
+    let first_id = graph
+        .add_message(&session, &conversation_id, "user", "Al works on GraphLite; review the schema", &first)
+        .unwrap();
+    let second = ExtractedEntities { people: vec!["Alice".to_string()], ..Default::default() };
+    graph.add_message(&session, &conversation_id, "user", "Alice is here", &second).unwrap();
+
+    let task = graph.list_tasks(&session, None).unwrap().remove(0);
+    graph.assign_task(&session, &task.id, "Al").unwrap();
+
+    let report = graph
+        .merge_entities(&session, EntityKind::Person, "Al", "Alice", &["WORKS_ON".to_string()])
+        .unwrap();
+    assert_eq!((report.mentions_moved, report.relations_moved), (1, 2));
+
+    // The alias node is gone and its name resolves to the canonical one
+    let people = graph.list_entity_names(&session, EntityKind::Person).unwrap();
+    assert_eq!(people, ["Alice"]);
+    assert_eq!(
+        graph.resolve_entity_name(&session, EntityKind::Person, "Al").unwrap().as_deref(),
+        Some("Alice")
+    );
+
+    // Mention, typed relation (with its message) and assignment all moved
+    let alice = EntityRef::new(EntityKind::Person, "Alice");
+    assert_eq!(graph.get_entity_mentions(&session, &alice).unwrap().len(), 2);
+    let works_on = graph.get_related_by(&session, &alice, "WORKS_ON").unwrap();
+    assert_eq!(works_on.len(), 1);
+    assert_eq!(works_on[0].entity, EntityRef::new(EntityKind::Topic, "GraphLite"));
+    assert_eq!(works_on[0].message_id.as_deref(), Some(first_id.as_str()));
+    let task = graph.find_task(&session, &task.id).unwrap();
+    assert_eq!(task.assignee.as_deref(), Some("Alice"));
+
+    std::fs::remove_dir_all(&db_path).ok();
+}
+
+#[tokio::test]
+async fn test_complete_task_tool_matches_full_ids_only() {
+    let (graph, session, db_path) = open_db("complete-task").await;
+    let conversation_id = graph.start_conversation(&session, None).unwrap();
+    let entities = ExtractedEntities {
+        tasks: vec!["Deploy the release".to_string(), "Write the changelog".to_string()],
+        ..Default::default()
+    };
+    graph.add_message(&session, &conversation_id, "user", "Deploy and write the changelog", &entities).unwrap();
+    let retriever = ContextRetriever::new(&graph);
+    let complete = |task: &str| MemoryTool::CompleteTask { task: task.to_string() }.run(&session, &graph, &retriever);
+
+    // Complete the task by the id `list_tasks` shows the model
+    let listed = MemoryTool::ListTasks { status: None }.run(&session, &graph, &retriever).unwrap();
+    let line = listed.lines().find(|line| line.contains("Deploy the release")).unwrap();
+    let shown_id = line.trim_start_matches('[').split(']').next().unwrap();
+    assert_eq!(complete(shown_id).unwrap(), "Completed task: Deploy the release");
+    assert_eq!(complete(shown_id).unwrap(), "Task already completed: Deploy the release");
+    assert_eq!(complete("write the changelog").unwrap(), "Completed task: Write the changelog");
+    assert!(graph.list_open_tasks(&session).unwrap().is_empty());
+
+    std::fs::remove_dir_all(&db_path).ok();
+}
+
+#[tokio::test]
+async fn test_export_import_round_trip() {
+    let (source, source_session, source_path) = open_db("export").await;
+    let conversation_id = source.start_conversation(&source_session, Some("Planning".to_string())).unwrap();
+    let entities = ExtractedEntities {
+        people: vec!["Alice".to_string()],
+        topics: vec!["GraphLite".to_string()],
+        tasks: vec!["Review the schema".to_string()],
+        relations: vec![relation("Alice", "WORKS_ON", "GraphLite")],
+        ..Default::default()
+    };
+    source
+        .add_message(&source_session, &conversation_id, "user", "Alice works on GraphLite; review the schema", &entities)
+        .unwrap();
+    let task = source.list_tasks(&source_session, None).unwrap().remove(0);
+    source.assign_task(&source_session, &task.id, "Alice").unwrap();
+
+    let mut export = Vec::new();
+    let exported = source.export_jsonl(&source_session, &mut export).unwrap();
+    let expected = source.snapshot(&source_session).unwrap();
+    // Typed relations keep their message id
+    assert!(expected
+        .edges
+        .iter()
+        .any(|edge| edge.label == "WORKS_ON" && edge.properties.contains_key("message_id")));
+
+    // The task already exists in the target, with only its id and description
+    let (target, target_session, target_path) = open_db("import").await;
+    let partial = format!(
+        "{}\n{}\n",
+        json!({"type": "header", "format": EXPORT_FORMAT, "version": EXPORT_VERSION, "exported_at": "2024-01-01T00:00:00Z"}),
+        json!({
+            "type": "node",
+            "id": format!("task:{}", task.id),
+            "label": "Task",
+            "properties": {"id": task.id, "description": task.description},
+        }),
+    );
+    target.import_jsonl(&target_session, partial.as_bytes()).unwrap();
+
+    let first = target.import_jsonl(&target_session, export.as_slice()).unwrap();
+    assert_eq!(
+        first,
+        ImportSummary {
+            nodes_created: exported.nodes - 1,
+            nodes_merged: 1,
+            edges_created: exported.edges,
+            edges_existing: 0,
+            skipped: 0,
+        }
+    );
+
+    // Importing the same file again changes nothing
+    let second = target.import_jsonl(&target_session, export.as_slice()).unwrap();
+    assert_eq!(
+        second,
+        ImportSummary {
+            nodes_created: 0,
+            nodes_merged: exported.nodes,
+            edges_created: 0,
+            edges_existing: exported.edges,
+            skipped: 0,
+        }
+    );
+
+    // The merged task gained its missing properties, so both graphs match
+    assert_eq!(target.snapshot(&target_session).unwrap(), expected);
+    let mut reexport = Vec::new();
+    assert_eq!(target.export_jsonl(&target_session, &mut reexport).unwrap(), exported);
+
+    std::fs::remove_dir_all(&source_path).ok();
+    std::fs::remove_dir_all(&target_path).ok();
+}
+
+#[tokio::test]
+async fn test_task_updates_reject_unknown_ids() {
+    let (graph, session, db_path) = open_db("unknown-task").await;
+
+    let status = graph.set_task_status(&session, "missing", TaskStatus::Completed);
+    assert_eq!(status.unwrap_err().to_string(), "Task not found: missing");
+    let due = graph.set_task_due_date(&session, "missing", chrono::NaiveDate::from_ymd_opt(2024, 6, 1).unwrap());
+    assert_eq!(due.unwrap_err().to_string(), "Task not found: missing");
+    let assign = graph.assign_task(&session, "missing", "Bob");
+    assert_eq!(assign.unwrap_err().to_string(), "Task not found: missing");
+
+    // The failed assignment did not create its assignee
+    assert!(graph.list_entity_names(&session, EntityKind::Person).unwrap().is_empty());
+
+    std::fs::remove_dir_all(&db_path).ok();
+}
+
+#[tokio::test]
+async fn test_assign_task_resolves_the_assignee() {
+    let (graph, session, db_path) = open_db("assign").await;
+    let conversation_id = graph.start_conversation(&session, None).unwrap();
+    let entities = ExtractedEntities {
+        people: vec!["Alice".to_string()],
+        tasks: vec!["Review the schema".to_string()],
+        ..Default::default()
+    };
+    graph.add_message(&session, &conversation_id, "user", "Alice should review the schema", &entities).unwrap();
+    graph.add_alias(&session, EntityKind::Person, "Al", "Alice").unwrap();
+    let task = graph.list_tasks(&session, None).unwrap().remove(0);
+
+    // Different spellings and aliases land on the existing person
+    for name in ["alice", "Al"] {
+        graph.assign_task(&session, &task.id, name).unwrap();
+        assert_eq!(graph.find_task(&session, &task.id).unwrap().assignee.as_deref(), Some("Alice"));
+    }
+    assert_eq!(graph.list_entity_names(&session, EntityKind::Person).unwrap(), ["Alice"]);
+
+    // An unknown name still creates the person
+    graph.assign_task(&session, &task.id, "Bob").unwrap();
+    assert_eq!(graph.find_task(&session, &task.id).unwrap().assignee.as_deref(), Some("Bob"));
+    assert_eq!(graph.list_entity_names(&session, EntityKind::Person).unwrap(), ["Alice", "Bob"]);
+
+    std::fs::remove_dir_all(&db_path).ok();
+}
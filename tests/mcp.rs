@@ -0,0 +1,208 @@
+//! Drives `agentic-memory mcp` through a scripted JSON-RPC session, with a
+//! local stand-in for the LLM provider that extracts the same entities from
+//! every message
+
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::process::{Command, Stdio};
+
+/// Extraction result returned for every LLM request
+const EXTRACTED: &str = r#"{"people": ["Alice"], "topics": ["GraphLite"], "tasks": ["Review the schema"],
+"documents": [], "completed_tasks": [], "relations": [{"subject": "Alice", "predicate": "WORKS_ON", "object": "GraphLite"}]}"#;
+
+/// Answer every request with an OpenAI-style completion containing [`EXTRACTED`]
+fn start_fake_llm() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap_or(0);
+                    }
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).ok();
+
+            let completion = json!({
+                "id": "chatcmpl-test",
+                "object": "chat.completion",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": EXTRACTED},
+                    "finish_reason": "stop"
+                }]
+            })
+            .to_string();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                completion.len(),
+                completion
+            );
+            stream.write_all(response.as_bytes()).ok();
+        }
+    });
+
+    format!("http://{}/v1", address)
+}
+
+fn request(id: u64, method: &str, params: Value) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params})
+}
+
+fn call(id: u64, tool: &str, arguments: Value) -> Value {
+    request(id, "tools/call", json!({"name": tool, "arguments": arguments}))
+}
+
+/// Run one MCP session with the given messages and return the responses by id
+fn run_session(db_path: &std::path::Path, llm_url: &str, messages: &[Value]) -> Vec<Value> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_agentic-memory"))
+        .args(["--db-path", db_path.to_str().unwrap(), "mcp"])
+        .env("LLM_PROVIDER", "local")
+        .env("LLM_BASE_URL", llm_url)
+        .env("LLM_MAX_RETRIES", "0")
+        .env_remove("EXTRACTION_PROVIDER")
+        .env_remove("EXTRACTION_MODEL")
+        .env_remove("RELATION_TYPES")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .expect("failed to start agentic-memory mcp");
+
+    let mut stdin = child.stdin.take().unwrap();
+    for message in messages {
+        writeln!(stdin, "{}", message).unwrap();
+    }
+    drop(stdin);
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "mcp exited with {}", output.status);
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap_or_else(|_| panic!("not JSON-RPC: {}", line)))
+        .collect()
+}
+
+fn response(responses: &[Value], id: u64) -> &Value {
+    responses
+        .iter()
+        .find(|response| response["id"] == id)
+        .unwrap_or_else(|| panic!("no response with id {} in {:?}", id, responses))
+}
+
+fn tool_text(responses: &[Value], id: u64) -> &str {
+    let result = &response(responses, id)["result"];
+    assert_eq!(result["isError"], false, "tool call {} failed: {}", id, result);
+    result["content"][0]["text"].as_str().unwrap()
+}
+
+#[test]
+fn test_scripted_mcp_session() {
+    let llm_url = start_fake_llm();
+    let db_path = std::env::temp_dir().join(format!("agentic-memory-mcp-{}", uuid::Uuid::new_v4()));
+
+    let responses = run_session(
+        &db_path,
+        &llm_url,
+        &[
+            request(1, "initialize", json!({
+                "protocolVersion": "2025-03-26",
+                "capabilities": {},
+                "clientInfo": {"name": "test", "version": "0"}
+            })),
+            json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+            request(2, "tools/list", json!({})),
+            call(3, "store_message", json!({"content": "Alice is moving our GraphLite schema; I need to review the schema"})),
+            call(4, "search_memory", json!({"query": "schema"})),
+            call(5, "get_person_context", json!({"name": "alice"})),
+            call(6, "get_topic_context", json!({"name": "GraphLite"})),
+            call(7, "list_tasks", json!({})),
+            call(8, "get_recent_history", json!({})),
+            request(9, "resources/list", json!({})),
+            call(10, "update_task", json!({"task": "no-such-task", "status": "completed"})),
+            call(11, "no_such_tool", json!({})),
+            request(12, "no/such/method", json!({})),
+            json!("not a request"),
+        ],
+    );
+
+    // The notification gets no response; every request and the bad message do
+    assert_eq!(responses.len(), 13);
+
+    let initialize = &response(&responses, 1)["result"];
+    assert_eq!(initialize["protocolVersion"], "2025-03-26");
+    assert_eq!(initialize["serverInfo"]["name"], "agentic-memory");
+
+    let tools: Vec<&str> = response(&responses, 2)["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tool| tool["name"].as_str().unwrap())
+        .collect();
+    assert!(tools.contains(&"store_message") && tools.contains(&"update_task"), "{:?}", tools);
+
+    let stored = tool_text(&responses, 3);
+    assert!(stored.contains("\"people\":[\"Alice\"]"), "{}", stored);
+
+    assert!(tool_text(&responses, 4).contains("review the schema"));
+    assert!(tool_text(&responses, 5).contains("GraphLite"));
+    assert!(tool_text(&responses, 6).contains("Alice"));
+    let tasks = tool_text(&responses, 7);
+    assert!(tasks.contains("Review the schema (pending)"), "{}", tasks);
+    assert!(tool_text(&responses, 8).contains("user: Alice is moving our GraphLite schema"));
+
+    let resources = response(&responses, 9)["result"]["resources"].as_array().unwrap();
+    assert_eq!(resources[0]["uri"], "memory://conversations");
+    let conversation_uri = resources[1]["uri"].as_str().unwrap().to_string();
+    assert_eq!(resources[1]["name"], "MCP session");
+
+    // Failing tools are reported in the result, unknown tools and methods as errors
+    assert_eq!(response(&responses, 10)["result"]["isError"], true);
+    assert_eq!(response(&responses, 11)["error"]["code"], -32602);
+    assert_eq!(response(&responses, 12)["error"]["code"], -32601);
+    let invalid = responses.iter().find(|response| response["id"].is_null()).unwrap();
+    assert_eq!(invalid["error"]["code"], -32600);
+
+    // A second session sees the stored conversation and can update the task
+    let task_id = tasks
+        .trim_start_matches('[')
+        .split(']')
+        .next()
+        .unwrap()
+        .to_string();
+    let responses = run_session(
+        &db_path,
+        &llm_url,
+        &[
+            request(1, "resources/read", json!({"uri": conversation_uri})),
+            call(2, "update_task", json!({"task": task_id, "status": "in_progress", "assignee": "Alice"})),
+            call(3, "list_tasks", json!({"status": "in_progress"})),
+            request(4, "resources/read", json!({"uri": "memory://conversations/missing"})),
+        ],
+    );
+
+    let contents = &response(&responses, 1)["result"]["contents"][0];
+    assert_eq!(contents["mimeType"], "text/plain");
+    let transcript = contents["text"].as_str().unwrap();
+    assert!(transcript.starts_with("# MCP session\n"), "{}", transcript);
+    assert!(transcript.contains("user: Alice is moving our GraphLite schema"));
+
+    assert!(tool_text(&responses, 2).contains("status in_progress, assigned to Alice"));
+    assert!(tool_text(&responses, 3).contains("Review the schema (in_progress, assigned to Alice)"));
+    assert_eq!(response(&responses, 4)["error"]["code"], -32002);
+
+    std::fs::remove_dir_all(&db_path).ok();
+}
@@ -0,0 +1,147 @@
+//! Runs `AgenticMemory` as a library against scripted models, checking what
+//! is stored and which prompts reach the model
+
+use agentic_memory::{AgenticMemory, ChatRole, ScriptedModel, ScriptedReply};
+use serde_json::json;
+use std::sync::Arc;
+
+#[tokio::test]
+async fn test_memory_with_scripted_models() {
+    let db_path = std::env::temp_dir().join(format!("agentic-memory-scripted-{}", uuid::Uuid::new_v4()));
+    let replies = Arc::new(ScriptedModel::new([
+        ScriptedReply::text("Noted, Alice works on GraphLite."),
+        ScriptedReply::text("Alice works on GraphLite."),
+    ]));
+    let extraction = Arc::new(ScriptedModel::new([
+        ScriptedReply::Json(json!({
+            "people": ["Alice"],
+            "topics": ["GraphLite"],
+            "relations": [{"subject": "Alice", "predicate": "WORKS_ON", "object": "GraphLite"}]
+        })),
+        ScriptedReply::Json(json!({"people": ["Alice"]})),
+    ]));
+
+    let mut memory = AgenticMemory::new(db_path.to_str().unwrap(), "admin", "admin123", replies.clone(), extraction.clone())
+        .await
+        .unwrap();
+    assert_eq!(memory.model(), "scripted");
+
+    let session = memory.session("admin", "admin123").unwrap();
+    let conversation_id = memory.start_conversation(&session, Some("Scripted".to_string())).unwrap();
+
+    for message in ["Alice works on GraphLite", "What does Alice work on?"] {
+        let (_, entities) = memory.process_user_message(&session, message).await.unwrap();
+        assert_eq!(entities.people, ["Alice"]);
+        let reply = memory.generate_response(&session, message, &entities).await.unwrap();
+        memory.store_assistant_message(&session, &reply).unwrap();
+    }
+    assert_eq!(replies.remaining(), 0);
+    assert_eq!(extraction.remaining(), 0);
+
+    // The second reply was written with the graph context and the earlier turns
+    let second = &replies.requests()[1];
+    assert!(second.system.contains("GraphLite"), "{}", second.system);
+    let roles: Vec<ChatRole> = second.messages.iter().map(|message| message.role).collect();
+    assert_eq!(roles, [ChatRole::User, ChatRole::Assistant, ChatRole::User]);
+    assert_eq!(second.messages[2].content, "What does Alice work on?");
+
+    let messages = memory.graph().get_conversation_messages(&session, &conversation_id, 10).unwrap();
+    assert_eq!(messages.len(), 4);
+    assert!(messages
+        .iter()
+        .any(|(role, content, _)| role == "assistant" && content == "Alice works on GraphLite."));
+
+    std::fs::remove_dir_all(&db_path).ok();
+}
+
+/// Open a memory in a fresh temporary database with a conversation started
+async fn open_memory(
+    name: &str,
+    replies: Arc<ScriptedModel>,
+    extraction: Arc<ScriptedModel>,
+) -> (AgenticMemory, graphlite_sdk::Session, std::path::PathBuf) {
+    let db_path = std::env::temp_dir().join(format!("agentic-memory-{}-{}", name, uuid::Uuid::new_v4()));
+    let mut memory = AgenticMemory::new(db_path.to_str().unwrap(), "admin", "admin123", replies, extraction)
+        .await
+        .unwrap();
+    let session = memory.session("admin", "admin123").unwrap();
+    memory.start_conversation(&session, None).unwrap();
+    (memory, session, db_path)
+}
+
+#[tokio::test]
+async fn test_tool_calls_round_trip() {
+    let replies = Arc::new(ScriptedModel::new([
+        ScriptedReply::tool_call("call_1", "list_tasks", json!({})),
+        ScriptedReply::tool_call("call_2", "list_tasks", json!({"status": "someday"})),
+        ScriptedReply::text("You need to review the schema."),
+    ]));
+    let extraction = Arc::new(ScriptedModel::new([ScriptedReply::Json(json!({"tasks": ["Review the schema"]}))]));
+    let (memory, session, db_path) = open_memory("tools", replies.clone(), extraction).await;
+
+    let message = "I need to review the schema. What is on my list?";
+    let (_, entities) = memory.process_user_message(&session, message).await.unwrap();
+    let response = memory.generate_response_with_tools(&session, message, &entities).await.unwrap();
+
+    assert_eq!(response.text, "You need to review the schema.");
+    assert_eq!(response.invocations.len(), 2);
+    assert!(!response.invocations[0].is_error);
+    assert!(response.invocations[0].output.contains("Review the schema (pending)"), "{}", response.invocations[0].output);
+
+    // A failing tool is reported back to the model and the turn goes on
+    assert!(response.invocations[1].is_error);
+    assert!(response.invocations[1].output.contains("Unknown task status: someday"));
+
+    let requests = replies.requests();
+    assert_eq!(requests.len(), 3);
+    assert!(requests.iter().all(|request| request.allow_calls && request.tools.contains(&"list_tasks".to_string())));
+
+    std::fs::remove_dir_all(&db_path).ok();
+}
+
+#[tokio::test]
+async fn test_tool_iteration_cap() {
+    let replies = Arc::new(ScriptedModel::new([
+        ScriptedReply::tool_call("call_1", "list_tasks", json!({})),
+        ScriptedReply::tool_call("call_2", "list_tasks", json!({})),
+        ScriptedReply::tool_call("call_3", "list_tasks", json!({})),
+    ]));
+    let extraction = Arc::new(ScriptedModel::new([ScriptedReply::Json(json!({}))]));
+    let (mut memory, session, db_path) = open_memory("tool-cap", replies.clone(), extraction).await;
+    memory.set_max_tool_iterations(2);
+
+    let message = "What is on my list?";
+    let (_, entities) = memory.process_user_message(&session, message).await.unwrap();
+    let response = memory.generate_response_with_tools(&session, message, &entities).await.unwrap();
+
+    // The third request forbids tool calls; calls requested anyway are not run
+    let allowed: Vec<bool> = replies.requests().iter().map(|request| request.allow_calls).collect();
+    assert_eq!(allowed, [true, true, false]);
+    assert_eq!(response.invocations.len(), 2);
+    assert!(!response.text.is_empty());
+
+    std::fs::remove_dir_all(&db_path).ok();
+}
+
+#[tokio::test]
+async fn test_history_found_under_canonical_name() {
+    let replies = Arc::new(ScriptedModel::new([ScriptedReply::text("Alice owns the migration.")]));
+    let extraction = Arc::new(ScriptedModel::new([
+        ScriptedReply::Json(json!({"people": ["Alice"]})),
+        ScriptedReply::Json(json!({"people": ["alice"]})),
+    ]));
+    let (mut memory, session, db_path) = open_memory("history", replies.clone(), extraction).await;
+
+    memory.process_user_message(&session, "Alice owns the GraphLite migration").await.unwrap();
+    memory.start_conversation(&session, None).unwrap();
+    let message = "What does alice own?";
+    let (_, entities) = memory.process_user_message(&session, message).await.unwrap();
+    memory.generate_response(&session, message, &entities).await.unwrap();
+
+    // The earlier message was stored under "Alice" and is still found for "alice"
+    let system = &replies.requests()[0].system;
+    assert!(system.contains("Earlier conversations"), "{}", system);
+    assert!(system.contains("Alice owns the GraphLite migration"), "{}", system);
+
+    std::fs::remove_dir_all(&db_path).ok();
+}